@@ -73,6 +73,22 @@ pub(crate) fn is_bom(c: char) -> bool {
     c == '\u{FEFF}'
 }
 
+/// Check whether the character may appear in a YAML stream (the `c-printable` production).
+///
+/// This excludes most C0 and C1 control characters, `DEL`, `U+FFFE` and `U+FFFF`.
+#[inline]
+pub(crate) fn is_printable(c: char) -> bool {
+    matches!(
+        c,
+        '\t' | '\n' | '\r'
+            | '\x20'..='\x7E'
+            | '\u{85}'
+            | '\u{A0}'..='\u{D7FF}'
+            | '\u{E000}'..='\u{FFFD}'
+            | '\u{10000}'..='\u{10FFFF}'
+    )
+}
+
 /// Check whether the character is a YAML non-breaking character.
 #[inline]
 pub(crate) fn is_yaml_non_break(c: char) -> bool {
@@ -0,0 +1,181 @@
+//! Helpers associating comments with the events they are about.
+
+use std::collections::VecDeque;
+
+use crate::{input::str::StrInput, Event, Marker, Parser, ScanError, Span};
+
+/// A comment found in the source, see [`CommentedEvents`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Comment<'input> {
+    /// The text of the comment, after its `#` and up to the end of its line.
+    pub text: &'input str,
+    /// The range of the comment in the source, from its `#` to the end of its line.
+    pub span: Span,
+    /// Whether the comment follows the event it is attached to on the same line.
+    ///
+    /// Otherwise, it is alone on its line and precedes the event it is attached to.
+    pub trailing: bool,
+}
+
+/// An iterator over the events of a [`Parser`], each with the comments attached to it.
+///
+/// Comments are attached to events as follows:
+///   - A comment alone on its line is attached to the next event that has a non-empty span. A
+///     comment on the line above a key is hence attached to the key rather than to the implicit
+///     start of the mapping. Comments that no such event follows are attached to
+///     [`Event::StreamEnd`].
+///   - A comment that follows an event with a non-empty span on the same line is attached to that
+///     event. In `a: b # c`, `# c` is attached to the scalar `b`. The last event before the
+///     comment of `a: [b] # c` is the end of the flow sequence, to which `# c` is attached.
+///
+/// The comments of an event are in source order.
+///
+/// ```
+/// # use saphyr_parser::{CommentedEvents, Event, Parser};
+/// let yaml = "# The key.\nkey: value # The value.\n";
+/// let comments: Vec<_> = CommentedEvents::new(Parser::new_from_str(yaml))
+///     .filter_map(|ev| match ev.unwrap() {
+///         (Event::Scalar(value, ..), _, comments) => Some((value, comments[0].text)),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(
+///     comments,
+///     [
+///         ("key".to_owned(), " The key."),
+///         ("value".to_owned(), " The value.")
+///     ]
+/// );
+/// ```
+pub struct CommentedEvents<'input> {
+    /// The parser we pull events from.
+    parser: Parser<StrInput<'input>>,
+    /// The source the parser parses, from which comments are sliced.
+    source: &'input str,
+    /// The comments found that are not yet attached to an event, in source order.
+    comments: VecDeque<Comment<'input>>,
+    /// The last event pulled from the parser.
+    ///
+    /// It is held until the next one is pulled, at which point all of its trailing comments have
+    /// been found.
+    pending: Option<(Event, Span, Vec<Comment<'input>>)>,
+    /// An error returned by the parser after [`Self::pending`], to return once it is.
+    error: Option<ScanError>,
+}
+
+impl<'input> CommentedEvents<'input> {
+    /// Create a new [`CommentedEvents`] attaching comments to the events of `parser`.
+    ///
+    /// This enables the recording of trivia in `parser` (see [`Parser::set_record_trivia`]), which
+    /// must not have returned any event yet.
+    #[must_use]
+    pub fn new(mut parser: Parser<StrInput<'input>>) -> Self {
+        parser.set_record_trivia(true);
+        let source = parser.input().source();
+        Self {
+            parser,
+            source,
+            comments: VecDeque::new(),
+            pending: None,
+            error: None,
+        }
+    }
+
+    /// Find the comments in the trivia the parser recorded since the last call.
+    fn collect_comments(&mut self) {
+        for trivia in self.parser.take_trivia() {
+            let mut mark = trivia.start;
+            let text = &self.source[trivia.start.index()..trivia.end.index()];
+            for line in text.split_inclusive('\n') {
+                let content = line.trim_end_matches(['\r', '\n']);
+                if let Some(start) = content.find('#') {
+                    let mut start_mark = mark;
+                    start_mark.advance_multibyte(content[..start].chars().count(), start);
+                    let mut end_mark = start_mark;
+                    end_mark
+                        .advance_multibyte(content[start..].chars().count(), content.len() - start);
+                    self.comments.push_back(Comment {
+                        text: &content[start + 1..],
+                        span: Span::new(start_mark, end_mark),
+                        trailing: false,
+                    });
+                }
+                if line.len() > content.len() {
+                    mark = Marker::new(mark.index() + line.len(), mark.line() + 1, 0);
+                }
+            }
+        }
+    }
+
+    /// Attach the comments found before `next` on the line the pending event ends on to it.
+    fn attach_trailing_comments(&mut self, next: Option<Marker>) {
+        let Some((_, span, comments)) = &mut self.pending else {
+            return;
+        };
+        if span.is_empty() {
+            return;
+        }
+        while let Some(comment) = self.comments.front() {
+            if comment.span.start.line() != span.end.line()
+                || comment.span.start.index() < span.start.index()
+                || next.is_some_and(|next| comment.span.start.index() >= next.index())
+            {
+                break;
+            }
+            let mut comment = self.comments.pop_front().unwrap();
+            comment.trailing = true;
+            comments.push(comment);
+        }
+    }
+
+    /// Return the comments found before `start`.
+    fn take_leading_comments(&mut self, start: Marker) -> Vec<Comment<'input>> {
+        let mut comments = vec![];
+        while let Some(comment) = self.comments.front() {
+            if comment.span.start.index() >= start.index() {
+                break;
+            }
+            comments.push(self.comments.pop_front().unwrap());
+        }
+        comments
+    }
+}
+
+impl<'input> Iterator for CommentedEvents<'input> {
+    type Item = Result<(Event, Span, Vec<Comment<'input>>), ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
+        }
+        loop {
+            let next = self.parser.next_event();
+            self.collect_comments();
+            match next {
+                Some(Ok((event, span))) => {
+                    self.attach_trailing_comments(Some(span.start));
+                    let comments = if !span.is_empty() || event == Event::StreamEnd {
+                        self.take_leading_comments(span.start)
+                    } else {
+                        vec![]
+                    };
+                    if let Some(ready) = self.pending.replace((event, span, comments)) {
+                        return Some(Ok(ready));
+                    }
+                }
+                Some(Err(error)) => {
+                    self.attach_trailing_comments(None);
+                    let Some(ready) = self.pending.take() else {
+                        return Some(Err(error));
+                    };
+                    self.error = Some(error);
+                    return Some(Ok(ready));
+                }
+                None => {
+                    self.attach_trailing_comments(None);
+                    return self.pending.take().map(Ok);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Helpers iterating over the events of a stream one document at a time.
+
+use crate::{input::Input, parser::ParseResult, Event, Parser};
+
+/// The documents of a stream, each of which can be iterated over lazily.
+///
+/// This is returned by [`Parser::into_document_events`]. Each call to [`Self::next_document`]
+/// returns a [`DocumentEvents`] iterator over the events of the next document, from its
+/// [`Event::DocumentStart`] to its [`Event::DocumentEnd`]. Events are parsed as they are pulled
+/// from that iterator; none are collected.
+///
+/// The events of a document borrow the [`DocumentStream`], which therefore cannot implement
+/// [`Iterator`]. Requesting the next document while events of the current one are yet to be
+/// pulled skips them. Once an error is returned, there are no more documents.
+///
+/// ```
+/// # use saphyr_parser::{Event, Parser};
+/// let mut documents = Parser::new_from_str("a: 1\n---\n[b, c]\n").into_document_events();
+/// let mut scalars = vec![];
+/// while let Some(document) = documents.next_document() {
+///     let values: Vec<_> = document
+///         .filter_map(|ev| match ev.unwrap().0 {
+///             Event::Scalar(value, ..) => Some(value),
+///             _ => None,
+///         })
+///         .collect();
+///     scalars.push(values);
+/// }
+/// assert_eq!(scalars, [["a", "1"], ["b", "c"]]);
+/// ```
+pub struct DocumentStream<T: Input> {
+    /// The parser we pull events from.
+    parser: Parser<T>,
+    /// An event pulled from the parser before the document it belongs to was requested.
+    pending: Option<ParseResult>,
+    /// Whether we are in a document whose events have not all been pulled.
+    in_document: bool,
+    /// Whether the end of the stream or an error was reached.
+    done: bool,
+}
+
+/// An iterator over the events of a single document, see [`DocumentStream`].
+///
+/// The iterator ends after the [`Event::DocumentEnd`] of the document, or after returning an
+/// error.
+pub struct DocumentEvents<'a, T: Input> {
+    /// The stream the document belongs to.
+    stream: &'a mut DocumentStream<T>,
+}
+
+impl<T: Input> DocumentStream<T> {
+    /// Create a new [`DocumentStream`] iterating over the documents parsed by `parser`.
+    pub(crate) fn new(parser: Parser<T>) -> Self {
+        Self {
+            parser,
+            pending: None,
+            in_document: false,
+            done: false,
+        }
+    }
+
+    /// Return an iterator over the events of the next document, or `None` if there is none.
+    ///
+    /// The remaining events of the previous document, if any, are skipped. If an error is found
+    /// before the next document starts, the returned iterator yields that error only.
+    pub fn next_document(&mut self) -> Option<DocumentEvents<'_, T>> {
+        while self.next_in_document().is_some() {}
+        while !self.done {
+            match self.parser.next_event() {
+                Some(Ok((Event::StreamStart, _))) => {}
+                Some(Ok((Event::StreamEnd, _))) | None => self.done = true,
+                next => {
+                    self.pending = next;
+                    self.in_document = true;
+                    return Some(DocumentEvents { stream: self });
+                }
+            }
+        }
+        None
+    }
+
+    /// Consume the [`DocumentStream`] and return the parser.
+    ///
+    /// As with [`Self::next_document`], the remaining events of the current document, if any, are
+    /// skipped.
+    pub fn into_parser(mut self) -> Parser<T> {
+        while self.next_in_document().is_some() {}
+        self.parser
+    }
+
+    /// Return the next event of the current document, if any.
+    fn next_in_document(&mut self) -> Option<ParseResult> {
+        if !self.in_document {
+            return None;
+        }
+        let next = self.pending.take().or_else(|| self.parser.next_event());
+        match &next {
+            Some(Ok((Event::DocumentEnd, _))) => self.in_document = false,
+            Some(Ok(_)) => {}
+            Some(Err(_)) | None => {
+                self.in_document = false;
+                self.done = true;
+            }
+        }
+        next
+    }
+}
+
+impl<T: Input> Iterator for DocumentEvents<'_, T> {
+    type Item = ParseResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stream.next_in_document()
+    }
+}
@@ -0,0 +1,352 @@
+//! Best-effort conversion of events back to YAML.
+//!
+//! This is not a full-fledged emitter. It is meant for testing and debugging tools and produces
+//! YAML that parses back to the same events, with no regard for the layout of the original input.
+
+use std::fmt::Write;
+
+use crate::{char_traits::is_tag_char, Event, TScalarStyle, Tag};
+
+/// The prefix of the `!!` tag handle, as resolved by the parser.
+const YAML_TAG_PREFIX: &str = "tag:yaml.org,2002:";
+
+/// Implicit keys cannot be longer than 1024 characters. Longer keys are emitted as explicit keys.
+const MAX_IMPLICIT_KEY_LEN: usize = 1024;
+
+/// Serialize the given events back to YAML.
+///
+/// This is a best-effort helper meant for testing and debugging. The output is canonical rather
+/// than faithful to the original input:
+///   - Collections are always emitted in flow style (`[a, b]`, `{a: b}`). Keys which are
+///     collections are emitted as explicit keys (`{? [a] : b}`).
+///   - Plain scalars are emitted as such when possible. Plain scalars which cannot be written in a
+///     flow context (e.g.: `a, b`, which may appear in block context) are double-quoted. Since
+///     they contain characters no other type can contain, they still resolve to strings.
+///   - Single-quoted scalars are kept single-quoted if they fit on one line. Other scalars
+///     (including block scalars) are emitted double-quoted.
+///   - Anchors are named after their ID (`&1`, `*1`).
+///   - Tags are emitted using the `!` and `!!` handles where possible. Other handles are declared
+///     with `%TAG` directives.
+///   - A document start marker (`---`) is emitted for explicit documents and documents which need
+///     directives. A document end marker (`...`) is emitted only when the next document requires
+///     it.
+///
+/// Parsing the output yields the same events, except for the style of scalars, as described
+/// above. Events are expected to be well-formed (as emitted by [`Parser`]). `StreamStart` and
+/// `StreamEnd` events are optional. `Indicator` events are ignored.
+///
+/// ```
+/// # use saphyr_parser::{events_to_yaml, Event, Parser};
+/// let events: Vec<Event> = Parser::new_from_str("a: &x [b, 'c']\nd: *x\n")
+///     .map(|ev| ev.unwrap().0)
+///     .collect();
+/// assert_eq!(events_to_yaml(&events), "{a: &1 [b, 'c'], d: *1}\n");
+/// ```
+///
+/// [`Parser`]: crate::Parser
+#[must_use]
+pub fn events_to_yaml(events: &[Event]) -> String {
+    let events: Vec<&Event> = events
+        .iter()
+        .filter(|event| !matches!(event, Event::Indicator(_)))
+        .collect();
+    let mut emitter = Emitter {
+        events: &events,
+        pos: 0,
+        handles: vec![],
+    };
+    let mut out = String::new();
+    let mut first_document = true;
+    while let Some(event) = emitter.next_event() {
+        let Event::DocumentStart(explicit) = event else {
+            continue;
+        };
+        emitter.collect_handles();
+        if !first_document && (!*explicit || !emitter.handles.is_empty()) {
+            out.push_str("...\n");
+        }
+        first_document = false;
+        for (i, prefix) in emitter.handles.iter().enumerate() {
+            let _ = writeln!(out, "%TAG !t{i}! {prefix}");
+        }
+        if *explicit || !emitter.handles.is_empty() {
+            out.push_str("--- ");
+        }
+        emitter.emit_node(&mut out);
+        out.push('\n');
+    }
+    out
+}
+
+/// State of [`events_to_yaml`].
+struct Emitter<'a> {
+    /// The events to emit.
+    events: &'a [&'a Event],
+    /// The index of the next event to emit.
+    pos: usize,
+    /// Tag prefixes which need a `%TAG` directive in the current document.
+    ///
+    /// The handle of each prefix is `!t<index>!`.
+    handles: Vec<&'a str>,
+}
+
+impl<'a> Emitter<'a> {
+    /// Return the next event, if any.
+    fn next_event(&mut self) -> Option<&'a Event> {
+        let event = self.events.get(self.pos)?;
+        self.pos += 1;
+        Some(*event)
+    }
+
+    /// Collect the tag prefixes which need a `%TAG` directive in the document we are starting.
+    fn collect_handles(&mut self) {
+        self.handles.clear();
+        for event in &self.events[self.pos..] {
+            let tag = match event {
+                Event::DocumentEnd => break,
+                Event::Scalar(_, _, _, tag)
+                | Event::SequenceStart(_, tag)
+                | Event::MappingStart(_, tag) => tag,
+                _ => continue,
+            };
+            if let Some(Tag { handle, .. }) = tag {
+                if !matches!(handle.as_str(), "" | "!" | YAML_TAG_PREFIX)
+                    && !self.handles.contains(&handle.as_str())
+                {
+                    self.handles.push(handle);
+                }
+            }
+        }
+    }
+
+    /// Emit the node starting at the next event.
+    ///
+    /// Return whether a node was emitted. If there is no node (i.e.: events are ill-formed),
+    /// nothing is emitted.
+    fn emit_node(&mut self, out: &mut String) -> bool {
+        let Some(event) = self.next_event() else {
+            return false;
+        };
+        match event {
+            Event::Alias(id) => {
+                let _ = write!(out, "*{id}");
+            }
+            Event::Scalar(value, style, anchor, tag) => {
+                let has_properties = self.emit_properties(out, *anchor, tag.as_ref());
+                if *style == TScalarStyle::Plain && value.is_empty() && has_properties {
+                    // An empty node. Remove the separator after its properties.
+                    out.pop();
+                } else if *style == TScalarStyle::Plain && is_plain_safe(value) {
+                    out.push_str(value);
+                } else if *style == TScalarStyle::SingleQuoted && is_single_quoted_safe(value) {
+                    out.push('\'');
+                    out.push_str(&value.replace('\'', "''"));
+                    out.push('\'');
+                } else {
+                    push_double_quoted(out, value);
+                }
+            }
+            Event::SequenceStart(anchor, tag) => {
+                self.emit_properties(out, *anchor, tag.as_ref());
+                out.push('[');
+                let mut first = true;
+                while !self.next_is(&Event::SequenceEnd) {
+                    if !first {
+                        out.push_str(", ");
+                    }
+                    first = false;
+                    if !self.emit_node(out) {
+                        break;
+                    }
+                }
+                self.skip_if(&Event::SequenceEnd);
+                out.push(']');
+            }
+            Event::MappingStart(anchor, tag) => {
+                self.emit_properties(out, *anchor, tag.as_ref());
+                out.push('{');
+                let mut first = true;
+                while !self.next_is(&Event::MappingEnd) {
+                    if !first {
+                        out.push_str(", ");
+                    }
+                    first = false;
+                    if !self.emit_mapping_entry(out) {
+                        break;
+                    }
+                }
+                self.skip_if(&Event::MappingEnd);
+                out.push('}');
+            }
+            Event::StreamStart
+            | Event::StreamEnd
+            | Event::DocumentStart(_)
+            | Event::DocumentEnd
+            | Event::SequenceEnd
+            | Event::MappingEnd
+            | Event::Indicator(_)
+            | Event::Nothing => {
+                // Not a node. Leave it to the caller.
+                self.pos -= 1;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Return whether the next event is `event`, or whether there are no more events.
+    fn next_is(&self, event: &Event) -> bool {
+        self.events
+            .get(self.pos)
+            .map_or(true, |next| *next == event)
+    }
+
+    /// Skip the next event if it is `event`.
+    fn skip_if(&mut self, event: &Event) {
+        if self.events.get(self.pos) == Some(&event) {
+            self.pos += 1;
+        }
+    }
+
+    /// Emit a key and its value in a flow mapping.
+    ///
+    /// Return whether a key was emitted.
+    fn emit_mapping_entry(&mut self, out: &mut String) -> bool {
+        let (is_collection, ends_with_name) = match self.events.get(self.pos) {
+            Some(Event::SequenceStart(..) | Event::MappingStart(..)) => (true, false),
+            Some(Event::Alias(_)) => (false, true),
+            Some(Event::Scalar(value, TScalarStyle::Plain, ..)) => (false, value.is_empty()),
+            _ => (false, false),
+        };
+        let mut key = String::new();
+        if !self.emit_node(&mut key) {
+            return false;
+        }
+        if is_collection || key.len() > MAX_IMPLICIT_KEY_LEN {
+            let _ = write!(out, "? {key} : ");
+        } else if ends_with_name {
+            // `:` is a valid character in anchor and tag names. It must be separated from the
+            // alias or the properties of an empty key.
+            let _ = write!(out, "{key} : ");
+        } else {
+            let _ = write!(out, "{key}: ");
+        }
+        self.emit_node(out);
+        true
+    }
+
+    /// Emit the anchor and tag of a node, each followed by a space.
+    ///
+    /// Return whether anything was emitted.
+    fn emit_properties(&self, out: &mut String, anchor: usize, tag: Option<&Tag>) -> bool {
+        if anchor > 0 {
+            let _ = write!(out, "&{anchor} ");
+        }
+        let Some(tag) = tag else {
+            return anchor > 0;
+        };
+        if tag.is_non_specific() {
+            out.push('!');
+        } else if tag.handle == "!" {
+            out.push('!');
+            push_tag_suffix(out, &tag.suffix);
+        } else if tag.handle == YAML_TAG_PREFIX {
+            out.push_str("!!");
+            push_tag_suffix(out, &tag.suffix);
+        } else if let Some(i) = self.handles.iter().position(|&h| h == tag.handle) {
+            let _ = write!(out, "!t{i}!");
+            push_tag_suffix(out, &tag.suffix);
+        } else {
+            let _ = write!(out, "!<{}>", tag.suffix);
+        }
+        out.push(' ');
+        true
+    }
+}
+
+/// Return whether `value` can be emitted as a plain scalar in a flow context.
+///
+/// This is conservative. Some scalars which could be plain may be rejected.
+fn is_plain_safe(value: &str) -> bool {
+    let is_separator = |c: Option<char>| {
+        c.map_or(true, |c| {
+            matches!(c, ' ' | '\t' | '\n' | '\r' | ',' | '[' | ']' | '{' | '}')
+        })
+    };
+    let mut chars = value.chars();
+    let first = chars.next();
+    let starts_with_indicator = match first {
+        None => true,
+        Some('-' | '?' | ':') => is_separator(chars.next()),
+        Some(c) => " ,[]{}#&*!|>'\"%@`".contains(c),
+    };
+    if starts_with_indicator {
+        return false;
+    }
+    // Would be a document marker if the scalar is at the start of a line.
+    if value.starts_with("---") || value.starts_with("...") {
+        return false;
+    }
+    let mut prev = None;
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        let unsafe_char = match c {
+            ',' | '[' | ']' | '{' | '}' | '\t' | '\n' | '\r' | '\u{feff}' => true,
+            ':' => is_separator(chars.peek().copied()),
+            '#' => prev == Some(' '),
+            ' ' => chars.peek().is_none(),
+            _ => c.is_control(),
+        };
+        if unsafe_char {
+            return false;
+        }
+        prev = Some(c);
+    }
+    true
+}
+
+/// Return whether `value` can be emitted as a single-quoted scalar on a single line.
+fn is_single_quoted_safe(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c == '\t' || (!c.is_control() && c != '\u{feff}'))
+}
+
+/// Push `value` as a double-quoted scalar, escaping characters as needed.
+fn push_double_quoted(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\0' => out.push_str("\\0"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{1b}' => out.push_str("\\e"),
+            '\u{85}' => out.push_str("\\N"),
+            '\u{2028}' => out.push_str("\\L"),
+            '\u{2029}' => out.push_str("\\P"),
+            '\u{feff}' => out.push_str("\\uFEFF"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\x{:02X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Push the suffix of a tag shorthand, escaping characters as needed.
+fn push_tag_suffix(out: &mut String, suffix: &str) {
+    for c in suffix.chars() {
+        if is_tag_char(c) && c != '%' {
+            out.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                let _ = write!(out, "%{byte:02X}");
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! A standalone check for tabs in indentation, which does not parse the input.
+
+use crate::scanner::Marker;
+
+/// Return the position of the first tab in the indentation of each line of `input`.
+///
+/// YAML forbids tabs in indentation, which files written as "YAML-ish" often contain. This is a
+/// cheap check scanning the bytes of `input`, independent of the parser, so that tools can report
+/// every line to fix at once rather than the first error the parser finds.
+///
+/// The indentation of a line is the run of spaces and tabs it starts with. Lines that are empty
+/// or only contain a comment after their indentation are not reported, as tabs are allowed there.
+/// As the input is not parsed, tabs in the indentation of a block scalar's content are reported,
+/// even though they are part of the scalar if they come after its indentation.
+///
+/// ```
+/// # use saphyr_parser::scan_indentation_issues;
+/// let yaml = "a:\n\tb: c\n  \td: e\n\t# comment\n";
+/// let lines: Vec<_> = scan_indentation_issues(yaml)
+///     .iter()
+///     .map(|mark| (mark.line(), mark.col()))
+///     .collect();
+/// assert_eq!(lines, [(2, 0), (3, 2)]);
+/// ```
+#[must_use]
+pub fn scan_indentation_issues(input: &str) -> Vec<Marker> {
+    let bytes = input.as_bytes();
+    let mut issues = Vec::new();
+    let mut line_start = 0;
+    let mut line = 1;
+    while line_start < bytes.len() {
+        let indent_len = bytes[line_start..]
+            .iter()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+        let content = line_start + indent_len;
+        if !matches!(bytes.get(content), None | Some(b'\n' | b'\r' | b'#')) {
+            if let Some(col) = bytes[line_start..content].iter().position(|&b| b == b'\t') {
+                issues.push(Marker::new(line_start + col, line, col));
+            }
+        }
+
+        // `\r\n` is a single line break.
+        let Some(eol) = bytes[content..]
+            .iter()
+            .position(|&b| b == b'\n' || b == b'\r')
+        else {
+            break;
+        };
+        let eol = content + eol;
+        line_start = if bytes[eol..].starts_with(b"\r\n") {
+            eol + 2
+        } else {
+            eol + 1
+        };
+        line += 1;
+    }
+    issues
+}
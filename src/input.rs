@@ -1,11 +1,20 @@
 pub mod buffered;
+pub mod fallible;
 pub mod str;
+pub mod tee;
 
 #[allow(clippy::module_name_repetitions)]
 pub use buffered::BufferedInput;
+#[allow(clippy::module_name_repetitions)]
+pub use fallible::FallibleInput;
+#[allow(clippy::module_name_repetitions)]
+pub use tee::TeeInput;
+
+use std::error::Error;
 
 use crate::char_traits::{
-    is_alpha, is_blank, is_blank_or_breakz, is_break, is_breakz, is_digit, is_flow, is_z,
+    is_alpha, is_blank, is_blank_or_breakz, is_break, is_breakz, is_digit, is_flow, is_printable,
+    is_z,
 };
 
 /// Interface for a source of characters.
@@ -28,6 +37,37 @@ pub trait Input {
     /// parser tracks how many characters are loaded in the buffer and acts accordingly.
     fn lookahead(&mut self, count: usize);
 
+    /// Notify the input that the scanner needed to look `count` characters ahead, which is more
+    /// than [`Input::bufmaxlen`].
+    ///
+    /// In that case, the scanner falls back to looking ahead in several steps. This is meant for
+    /// inputs to keep track of how often their buffer is too small. It does nothing by default.
+    #[inline]
+    fn lookahead_exceeded(&mut self, count: usize) {
+        let _ = count;
+    }
+
+    /// Take the error that made the input stop early, if any.
+    ///
+    /// Inputs reading from a fallible source pad their buffer with `\0` when reading fails, as if
+    /// the input had ended. The scanner calls this method when it reaches a `\0` to tell a read
+    /// error apart from the end of the input, and reports the error it returns. Inputs that cannot
+    /// fail return `None`, which is the default.
+    #[inline]
+    fn take_error(&mut self) -> Option<Box<dyn Error + Send + Sync + 'static>> {
+        None
+    }
+
+    /// Return the number of bytes left to read from the input, if known.
+    ///
+    /// This is a hint meant for pre-sizing collections. Inputs that cannot tell how much is left
+    /// return `None`, which is the default.
+    #[inline]
+    #[must_use]
+    fn remaining_hint(&self) -> Option<usize> {
+        None
+    }
+
     /// Return the number of buffered characters in `self`.
     #[must_use]
     fn buflen(&self) -> usize;
@@ -64,6 +104,26 @@ pub trait Input {
     /// Consume the next `count` character.
     fn skip_n(&mut self, count: usize);
 
+    /// Push back the last `n` consumed characters, so that they are the next ones again.
+    ///
+    /// The characters are placed back into the buffer, as if [`Input::lookahead`] had loaded them.
+    /// The buffer must have room for them: `n` may be at most `bufmaxlen() - buflen()`, which is
+    /// never more than [`Input::bufmaxlen`]. Only characters of the input count, not the `\0`s
+    /// padding its end. Callers keeping track of positions must move them back themselves.
+    ///
+    /// Return whether the characters were pushed back. Inputs that do not support it return
+    /// `false` without pushing anything back, which is the default. `StrInput` supports it, while
+    /// [`BufferedInput`] does not keep the characters it consumed.
+    ///
+    /// # Panics
+    /// If the input supports pushing characters back and `n` exceeds the room in the buffer or the
+    /// number of characters consumed.
+    #[must_use]
+    fn unread(&mut self, n: usize) -> bool {
+        let _ = n;
+        false
+    }
+
     /// Return the next character, without consuming it.
     ///
     /// Users of the [`Input`] must make sure that the character has been loaded through a prior
@@ -136,6 +196,16 @@ pub trait Input {
         self.peek() == c1 && self.peek_nth(1) == c2 && self.peek_nth(2) == c3
     }
 
+    /// Return whether the next characters in the input source are those of `s`.
+    ///
+    /// This function assumes that the next `s.chars().count()` characters in the input have
+    /// already been fetched through [`Input::lookahead`].
+    #[inline]
+    #[must_use]
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.peek_nth(i) == c)
+    }
+
     /// Check whether the next characters correspond to a document indicator.
     ///
     /// This function assumes that the next 4 characters in the input has already been fetched
@@ -144,8 +214,7 @@ pub trait Input {
     #[must_use]
     fn next_is_document_indicator(&self) -> bool {
         assert!(self.buflen() >= 4);
-        is_blank_or_breakz(self.peek_nth(3))
-            && (self.next_3_are('.', '.', '.') || self.next_3_are('-', '-', '-'))
+        is_blank_or_breakz(self.peek_nth(3)) && (self.starts_with("...") || self.starts_with("---"))
     }
 
     /// Check whether the next characters correspond to a start of document.
@@ -156,7 +225,7 @@ pub trait Input {
     #[must_use]
     fn next_is_document_start(&self) -> bool {
         assert!(self.buflen() >= 4);
-        self.next_3_are('-', '-', '-') && is_blank_or_breakz(self.peek_nth(3))
+        self.starts_with("---") && is_blank_or_breakz(self.peek_nth(3))
     }
 
     /// Check whether the next characters correspond to an end of document.
@@ -167,15 +236,18 @@ pub trait Input {
     #[must_use]
     fn next_is_document_end(&self) -> bool {
         assert!(self.buflen() >= 4);
-        self.next_3_are('.', '.', '.') && is_blank_or_breakz(self.peek_nth(3))
+        self.starts_with("...") && is_blank_or_breakz(self.peek_nth(3))
     }
 
-    /// Skip yaml whitespace at most up to eol. Also skips comments. Advances the input.
+    /// Skip yaml whitespace at most up to eol or to the `#` starting a comment. Advances the input.
+    ///
+    /// The comment itself, if any, is not consumed. Use [`Input::skip_while_non_breakz`] to skip
+    /// it.
     ///
     /// # Return
     /// Return a tuple with the number of characters that were consumed and the result of skipping
-    /// whitespace. The number of characters returned can be used to advance the index and column,
-    /// since no end-of-line character will be consumed.
+    /// whitespace. All consumed characters are single-byte. The number of characters returned can
+    /// be used to advance the index and column, since no end-of-line character will be consumed.
     /// See [`SkipTabs`] For more details on the success variant.
     ///
     /// # Errors
@@ -203,12 +275,6 @@ pub trait Input {
                         Err("comments must be separated from other tokens by whitespace"),
                     );
                 }
-                '#' => {
-                    while !is_breakz(self.look_ch()) {
-                        self.skip();
-                        chars_consumed += 1;
-                    }
-                }
                 _ => break,
             }
             chars_consumed += 1;
@@ -368,18 +434,80 @@ pub trait Input {
     /// The characters are consumed from the input.
     ///
     /// # Return
-    /// Return the number of characters that were consumed. The number of characters returned can
-    /// be used to advance the index and column, since no end-of-line character will be consumed.
+    /// Return the number of characters and the number of bytes that were consumed. They can be
+    /// used to advance the column and index, since no end-of-line character will be consumed.
     ///
     /// [breakz]: is_breakz
     #[inline]
-    fn skip_while_non_breakz(&mut self) -> usize {
-        let mut count = 0;
-        while !is_breakz(self.look_ch()) {
-            count += 1;
+    fn skip_while_non_breakz(&mut self) -> (usize, usize) {
+        let mut n_chars = 0;
+        let mut n_bytes = 0;
+        loop {
+            let c = self.look_ch();
+            if is_breakz(c) {
+                break;
+            }
+            n_chars += 1;
+            n_bytes += c.len_utf8();
             self.skip();
         }
-        count
+        (n_chars, n_bytes)
+    }
+
+    /// Skip characters from the input until a [breakz], a tab or a non-[printable] character is
+    /// found.
+    ///
+    /// The characters are consumed from the input. This lets the scanner skip over comments in
+    /// bulk while still rejecting control characters.
+    ///
+    /// # Return
+    /// Return the number of characters and the number of bytes that were consumed. They can be
+    /// used to advance the column and index, since no end-of-line character will be consumed.
+    ///
+    /// [breakz]: is_breakz
+    /// [printable]: is_printable
+    #[inline]
+    fn skip_while_printable(&mut self) -> (usize, usize) {
+        let mut n_chars = 0;
+        let mut n_bytes = 0;
+        loop {
+            let c = self.look_ch();
+            if is_breakz(c) || c == '\t' || !is_printable(c) {
+                break;
+            }
+            n_chars += 1;
+            n_bytes += c.len_utf8();
+            self.skip();
+        }
+        (n_chars, n_bytes)
+    }
+
+    /// Fetch characters from the input while they are visible ASCII characters that continue a
+    /// plain scalar and store them in `out`.
+    ///
+    /// This stops at `:` and, if `in_flow`, at [flow] characters, since whether they are part of
+    /// the scalar depends on what follows them. At most `max` characters are fetched.
+    ///
+    /// The characters are consumed from the input. The last character consumed before calling
+    /// this function must not have been a blank, so that `#` does not start a comment.
+    ///
+    /// # Return
+    /// Return the number of characters that were consumed. Since they are all single-byte and no
+    /// end-of-line character is consumed, it can be used to advance the index and column.
+    ///
+    /// [flow]: is_flow
+    fn fetch_while_plain_ascii(&mut self, out: &mut String, in_flow: bool, max: usize) -> usize {
+        let mut n_chars = 0;
+        while n_chars < max {
+            let c = self.look_ch();
+            if !c.is_ascii_graphic() || c == ':' || (in_flow && is_flow(c)) {
+                break;
+            }
+            out.push(c);
+            self.skip();
+            n_chars += 1;
+        }
+        n_chars
     }
 
     /// Skip characters from the input while [blanks] are found.
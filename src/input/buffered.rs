@@ -28,6 +28,8 @@ pub struct BufferedInput<T: Iterator<Item = char>> {
     input: T,
     /// Buffer for the next characters to consume.
     buffer: ArrayDeque<char, BUFFER_LEN>,
+    /// The number of times the buffer was too small for the scanner's lookahead.
+    fallback_lookaheads: usize,
 }
 
 impl<T: Iterator<Item = char>> BufferedInput<T> {
@@ -36,8 +38,34 @@ impl<T: Iterator<Item = char>> BufferedInput<T> {
         Self {
             input,
             buffer: ArrayDeque::default(),
+            fallback_lookaheads: 0,
         }
     }
+
+    /// Return the number of times the scanner needed more lookahead than the buffer can hold.
+    ///
+    /// When that happens, the scanner falls back to a slower path filling the buffer several
+    /// times. This currently only happens when scanning block scalars indented by more than
+    /// `BUFFER_LEN - 2` (14) columns, once for each line of the scalar.
+    ///
+    /// ```
+    /// # use saphyr_parser::{BufferedInput, Parser};
+    /// let yaml = "a: |\n                x\n                y\n";
+    /// let mut parser = Parser::new(BufferedInput::new(yaml.chars()));
+    /// for event in &mut parser {
+    ///     event.unwrap();
+    /// }
+    /// assert_eq!(parser.input().fallback_lookahead_count(), 2);
+    /// ```
+    #[must_use]
+    pub fn fallback_lookahead_count(&self) -> usize {
+        self.fallback_lookaheads
+    }
+
+    /// Return a mutable reference to the iterator source.
+    pub(crate) fn source_mut(&mut self) -> &mut T {
+        &mut self.input
+    }
 }
 
 impl<T: Iterator<Item = char>> Input for BufferedInput<T> {
@@ -53,6 +81,11 @@ impl<T: Iterator<Item = char>> Input for BufferedInput<T> {
         }
     }
 
+    #[inline]
+    fn lookahead_exceeded(&mut self, _: usize) {
+        self.fallback_lookaheads += 1;
+    }
+
     #[inline]
     fn buflen(&self) -> usize {
         self.buffer.len()
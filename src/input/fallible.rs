@@ -0,0 +1,144 @@
+use std::error::Error;
+
+use crate::input::{BufferedInput, Input};
+
+/// An input reading from an [`Iterator`] of `Result<char, E>`s, such as a decoded stream.
+///
+/// The characters are buffered as with a [`BufferedInput`]. When the iterator returns an error,
+/// the input stops and the scanner reports a [`ScanError`] whose [`source`] is that error, instead
+/// of treating the input as if it had ended there.
+///
+/// ```
+/// # use std::error::Error;
+/// # use std::io;
+/// # use saphyr_parser::{FallibleInput, Parser};
+/// let error = io::Error::new(io::ErrorKind::ConnectionReset, "disconnected");
+/// let chars = "key: [a, b".chars().map(Ok).chain([Err(error)]);
+/// let err = Parser::new(FallibleInput::new(chars))
+///     .find_map(Result::err)
+///     .unwrap();
+/// assert_eq!(err.info(), "error reading input: disconnected");
+/// assert!(err.source().unwrap().downcast_ref::<io::Error>().is_some());
+/// ```
+///
+/// [`ScanError`]: crate::ScanError
+/// [`source`]: std::error::Error::source
+#[allow(clippy::module_name_repetitions)]
+pub struct FallibleInput<T, E>
+where
+    T: Iterator<Item = Result<char, E>>,
+    E: Error + Send + Sync + 'static,
+{
+    /// The buffered characters read until the first error.
+    inner: BufferedInput<UntilError<T, E>>,
+}
+
+impl<T, E> FallibleInput<T, E>
+where
+    T: Iterator<Item = Result<char, E>>,
+    E: Error + Send + Sync + 'static,
+{
+    /// Create a new [`FallibleInput`] with the given input.
+    pub fn new(input: T) -> Self {
+        Self {
+            inner: BufferedInput::new(UntilError { input, error: None }),
+        }
+    }
+}
+
+/// An [`Iterator`] returning the characters of an inner iterator until it returns an error.
+///
+/// The error is kept until the scanner takes it.
+struct UntilError<T, E> {
+    /// The iterator source.
+    input: T,
+    /// The error returned by `input`, if any.
+    error: Option<E>,
+}
+
+impl<T, E> Iterator for UntilError<T, E>
+where
+    T: Iterator<Item = Result<char, E>>,
+{
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.input.next()? {
+            Ok(c) => Some(c),
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+}
+
+impl<T, E> Input for FallibleInput<T, E>
+where
+    T: Iterator<Item = Result<char, E>>,
+    E: Error + Send + Sync + 'static,
+{
+    #[inline]
+    fn lookahead(&mut self, count: usize) {
+        self.inner.lookahead(count);
+    }
+
+    #[inline]
+    fn lookahead_exceeded(&mut self, count: usize) {
+        self.inner.lookahead_exceeded(count);
+    }
+
+    #[inline]
+    fn take_error(&mut self) -> Option<Box<dyn Error + Send + Sync + 'static>> {
+        let error = self.inner.source_mut().error.take()?;
+        Some(Box::new(error))
+    }
+
+    #[inline]
+    fn buflen(&self) -> usize {
+        self.inner.buflen()
+    }
+
+    #[inline]
+    fn bufmaxlen(&self) -> usize {
+        self.inner.bufmaxlen()
+    }
+
+    #[inline]
+    fn raw_read_ch(&mut self) -> char {
+        self.inner.raw_read_ch()
+    }
+
+    #[inline]
+    fn raw_read_non_breakz_ch(&mut self) -> Option<char> {
+        self.inner.raw_read_non_breakz_ch()
+    }
+
+    #[inline]
+    fn skip(&mut self) {
+        self.inner.skip();
+    }
+
+    #[inline]
+    fn skip_n(&mut self, count: usize) {
+        self.inner.skip_n(count);
+    }
+
+    #[inline]
+    fn unread(&mut self, n: usize) -> bool {
+        self.inner.unread(n)
+    }
+
+    #[inline]
+    fn peek(&self) -> char {
+        self.inner.peek()
+    }
+
+    #[inline]
+    fn peek_nth(&self, n: usize) -> char {
+        self.inner.peek_nth(n)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     char_traits::{
-        is_alpha, is_blank, is_blank_or_breakz, is_break, is_breakz, is_digit, is_flow, is_z,
+        is_alpha, is_blank, is_blank_or_breakz, is_break, is_breakz, is_digit, is_flow,
+        is_printable, is_z,
     },
     input::{Input, SkipTabs},
 };
@@ -8,6 +9,8 @@ use crate::{
 /// A parser input that uses a `&str` as source.
 #[allow(clippy::module_name_repetitions)]
 pub struct StrInput<'a> {
+    /// The whole input, including the part we have consumed.
+    source: &'a str,
     /// The input str buffer.
     buffer: &'a str,
     /// The number of characters we have looked ahead.
@@ -22,13 +25,57 @@ impl<'a> StrInput<'a> {
     #[must_use]
     pub fn new(input: &'a str) -> Self {
         Self {
+            source: input,
             buffer: input,
             lookahead: 0,
         }
     }
+
+    /// Return the whole input, including the part that has been consumed.
+    pub(crate) fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Create a new [`StrInput`] from UTF-8 encoded bytes.
+    ///
+    /// The bytes are validated upfront but not copied. This allows parsing large files without
+    /// loading them into a `String`, for instance by memory-mapping them (e.g.: with the
+    /// `memmap2` crate) and passing the mapped bytes to this function:
+    ///
+    /// ```ignore
+    /// let file = std::fs::File::open("large.yaml")?;
+    /// // SAFETY: The file must not be modified while it is mapped.
+    /// let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    /// let parser = Parser::new(StrInput::from_utf8(&mmap)?);
+    /// ```
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, StrInput};
+    /// let bytes: &[u8] = b"key: value\n";
+    /// let parser = Parser::new(StrInput::from_utf8(bytes).unwrap());
+    /// assert_eq!(parser.count(), 8);
+    /// assert!(StrInput::from_utf8(b"key: \xff\n").is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if `input` is not valid UTF-8.
+    pub fn from_utf8(input: &'a [u8]) -> Result<Self, std::str::Utf8Error> {
+        std::str::from_utf8(input).map(Self::new)
+    }
+
+    /// Return whether the byte at `index` is a blank or a breakz.
+    ///
+    /// The end of the input counts as a breakz. Non-ASCII bytes are neither.
+    #[inline]
+    fn is_blank_or_breakz_at(&self, index: usize) -> bool {
+        self.buffer
+            .as_bytes()
+            .get(index)
+            .map_or(true, |&b| is_blank_or_breakz(b as char))
+    }
 }
 
-impl<'a> Input for StrInput<'a> {
+impl Input for StrInput<'_> {
     #[inline]
     fn lookahead(&mut self, x: usize) {
         // We already have all characters that we need.
@@ -37,6 +84,11 @@ impl<'a> Input for StrInput<'a> {
         self.lookahead = self.lookahead.max(x);
     }
 
+    #[inline]
+    fn remaining_hint(&self) -> Option<usize> {
+        Some(self.buffer.len())
+    }
+
     #[inline]
     fn buflen(&self) -> usize {
         self.lookahead
@@ -95,6 +147,24 @@ impl<'a> Input for StrInput<'a> {
         self.buffer = chars.as_str();
     }
 
+    fn unread(&mut self, n: usize) -> bool {
+        if n == 0 {
+            return true;
+        }
+        assert!(
+            self.lookahead + n <= BUFFER_LEN,
+            "cannot push back more characters than the buffer can hold"
+        );
+        let consumed = &self.source[..self.source.len() - self.buffer.len()];
+        let (start, _) = consumed
+            .char_indices()
+            .nth_back(n - 1)
+            .expect("cannot push back more characters than were consumed");
+        self.buffer = &self.source[start..];
+        self.lookahead += n;
+        true
+    }
+
     #[inline]
     fn peek(&self) -> char {
         self.buffer.chars().next().unwrap_or('\0')
@@ -141,46 +211,25 @@ impl<'a> Input for StrInput<'a> {
             && chars.next().is_some_and(|c| c == c3)
     }
 
+    #[inline]
+    fn starts_with(&self, s: &str) -> bool {
+        // The whole input is available: there is no need to go through `peek_nth`.
+        self.buffer.starts_with(s)
+    }
+
     #[inline]
     fn next_is_document_indicator(&self) -> bool {
-        if self.buffer.len() < 3 {
-            false
-        } else {
-            // Since all characters we look for are ascii, we can directly use the byte API of str.
-            let bytes = self.buffer.as_bytes();
-            (bytes.len() == 3 || is_blank_or_breakz(bytes[3] as char))
-                && (bytes[0] == b'.' || bytes[0] == b'-')
-                && bytes[0] == bytes[1]
-                && bytes[1] == bytes[2]
-        }
+        (self.starts_with("---") || self.starts_with("...")) && self.is_blank_or_breakz_at(3)
     }
 
     #[inline]
     fn next_is_document_start(&self) -> bool {
-        if self.buffer.len() < 3 {
-            false
-        } else {
-            // Since all characters we look for are ascii, we can directly use the byte API of str.
-            let bytes = self.buffer.as_bytes();
-            (bytes.len() == 3 || is_blank_or_breakz(bytes[3] as char))
-                && bytes[0] == b'-'
-                && bytes[1] == b'-'
-                && bytes[2] == b'-'
-        }
+        self.starts_with("---") && self.is_blank_or_breakz_at(3)
     }
 
     #[inline]
     fn next_is_document_end(&self) -> bool {
-        if self.buffer.len() < 3 {
-            false
-        } else {
-            // Since all characters we look for are ascii, we can directly use the byte API of str.
-            let bytes = self.buffer.as_bytes();
-            (bytes.len() == 3 || is_blank_or_breakz(bytes[3] as char))
-                && bytes[0] == b'.'
-                && bytes[1] == b'.'
-                && bytes[2] == b'.'
-        }
+        self.starts_with("...") && self.is_blank_or_breakz_at(3)
     }
 
     fn skip_ws_to_eol(&mut self, skip_tabs: SkipTabs) -> (usize, Result<SkipTabs, &'static str>) {
@@ -213,28 +262,18 @@ impl<'a> Input for StrInput<'a> {
 
         // All characters consumed were ascii. We can use the byte length difference to count the
         // number of whitespace ignored.
-        let mut chars_consumed = self.buffer.len() - new_str.len();
-
-        if !new_str.is_empty() && new_str.as_bytes()[0] == b'#' {
-            if !encountered_tab && !has_yaml_ws {
-                return (
-                    chars_consumed,
-                    Err("comments must be separated from other tokens by whitespace"),
-                );
-            }
-
-            // Skip remaining characters until we hit a breakz.
-            while let Some((c, sub_str)) = split_first_char(new_str) {
-                if is_breakz(c) {
-                    break;
-                }
-                new_str = sub_str;
-                chars_consumed += 1;
-            }
-        }
+        let chars_consumed = self.buffer.len() - new_str.len();
 
         self.buffer = new_str;
 
+        if !new_str.is_empty() && new_str.as_bytes()[0] == b'#' && !encountered_tab && !has_yaml_ws
+        {
+            return (
+                chars_consumed,
+                Err("comments must be separated from other tokens by whitespace"),
+            );
+        }
+
         (
             chars_consumed,
             Ok(SkipTabs::Result(encountered_tab, has_yaml_ws)),
@@ -312,9 +351,9 @@ impl<'a> Input for StrInput<'a> {
         !self.buffer.is_empty() && is_alpha(self.buffer.as_bytes()[0] as char)
     }
 
-    fn skip_while_non_breakz(&mut self) -> usize {
+    fn skip_while_non_breakz(&mut self) -> (usize, usize) {
         let mut new_str = self.buffer;
-        let mut count = 0;
+        let mut n_chars = 0;
 
         // Skip over all non-breaks.
         while let Some((c, sub_str)) = split_first_char(new_str) {
@@ -322,12 +361,35 @@ impl<'a> Input for StrInput<'a> {
                 break;
             }
             new_str = sub_str;
-            count += 1;
+            n_chars += 1;
         }
 
+        let n_bytes = self.buffer.len() - new_str.len();
         self.buffer = new_str;
 
-        count
+        (n_chars, n_bytes)
+    }
+
+    fn skip_while_printable(&mut self) -> (usize, usize) {
+        let bytes = self.buffer.as_bytes();
+        let mut n_chars = 0;
+        let mut i = 0;
+        while let Some(&b) = bytes.get(i) {
+            if (b' '..=b'~').contains(&b) {
+                i += 1;
+            } else if b.is_ascii() {
+                // Breaks, tabs and other ASCII control characters.
+                break;
+            } else {
+                match split_first_char(&self.buffer[i..]) {
+                    Some((c, _)) if is_printable(c) => i += c.len_utf8(),
+                    _ => break,
+                }
+            }
+            n_chars += 1;
+        }
+        self.buffer = &self.buffer[i..];
+        (n_chars, i)
     }
 
     fn skip_while_blank(&mut self) -> usize {
@@ -343,6 +405,19 @@ impl<'a> Input for StrInput<'a> {
         i
     }
 
+    fn fetch_while_plain_ascii(&mut self, out: &mut String, in_flow: bool, max: usize) -> usize {
+        // Since all characters we look for are ascii, we can directly use the byte API of str.
+        let n_bytes = self
+            .buffer
+            .bytes()
+            .take(max)
+            .take_while(|&b| b.is_ascii_graphic() && b != b':' && !(in_flow && is_flow(b as char)))
+            .count();
+        out.push_str(&self.buffer[..n_bytes]);
+        self.buffer = &self.buffer[n_bytes..];
+        n_bytes
+    }
+
     fn fetch_while_is_alpha(&mut self, out: &mut String) -> usize {
         let mut not_alpha = None;
 
@@ -407,7 +482,7 @@ fn split_first_char(s: &str) -> Option<(char, &str)> {
 
 #[cfg(test)]
 mod test {
-    use crate::input::Input;
+    use crate::input::{BufferedInput, Input, TeeInput};
 
     use super::StrInput;
 
@@ -442,4 +517,113 @@ mod test {
         assert!(input.next_is_document_end());
         assert!(input.next_is_document_indicator());
     }
+
+    #[test]
+    pub fn is_not_document_indicator() {
+        for s in ["", "--", "-- -", "---a", "...é", "--—", "-\n-\n-"] {
+            let input = StrInput::new(s);
+            assert!(!input.next_is_document_start(), "{s:?}");
+            assert!(!input.next_is_document_end(), "{s:?}");
+            assert!(!input.next_is_document_indicator(), "{s:?}");
+        }
+    }
+
+    #[test]
+    pub fn starts_with() {
+        for (s, prefix, expected) in [
+            ("---\n", "---", true),
+            ("<<: *a", "<<", true),
+            ("é: b", "é:", true),
+            ("", "", true),
+            ("--", "---", false),
+            ("-.-", "---", false),
+            ("é", "e", false),
+        ] {
+            let input = StrInput::new(s);
+            assert_eq!(input.starts_with(prefix), expected, "{s:?}");
+            // The default implementation goes through `peek_nth`.
+            let mut input = BufferedInput::new(s.chars());
+            input.lookahead(prefix.chars().count());
+            assert_eq!(input.starts_with(prefix), expected, "{s:?}");
+        }
+    }
+
+    /// Consume 3 characters of `input`, push them back and check they are read again.
+    fn check_unread<I: Input>(mut input: I) {
+        input.lookahead(2);
+        input.skip_n(2);
+        assert_eq!(input.raw_read_ch(), 'b');
+        assert!(input.unread(3));
+        input.lookahead(3);
+        assert!(input.starts_with("aéb"));
+        input.skip_n(3);
+        input.lookahead(1);
+        assert_eq!(input.peek(), 'c');
+
+        // Only characters of the input can be pushed back, not the padding.
+        input.skip();
+        input.lookahead(2);
+        input.skip_n(2);
+        assert!(input.unread(1));
+        input.lookahead(2);
+        assert_eq!((input.peek(), input.peek_nth(1)), ('c', '\0'));
+    }
+
+    #[test]
+    pub fn remaining_hint() {
+        let mut input = StrInput::new("aébc");
+        assert_eq!(input.remaining_hint(), Some(5));
+        input.lookahead(2);
+        input.skip_n(2);
+        assert_eq!(input.remaining_hint(), Some(2));
+        assert_eq!(BufferedInput::new("aébc".chars()).remaining_hint(), None);
+
+        let mut consumed = String::new();
+        let input = TeeInput::new(StrInput::new("ab"), &mut consumed);
+        assert_eq!(input.remaining_hint(), Some(2));
+    }
+
+    #[test]
+    pub fn unread() {
+        check_unread(StrInput::new("aébc"));
+
+        let mut consumed = String::new();
+        {
+            let mut input = TeeInput::new(StrInput::new("aébc"), &mut consumed);
+            input.lookahead(3);
+            input.skip_n(3);
+            assert!(input.unread(2));
+        }
+        assert_eq!(consumed, "a");
+
+        // Inputs that keep no history do not push anything back.
+        let mut consumed = String::new();
+        {
+            let mut input = TeeInput::new(BufferedInput::new("abc".chars()), &mut consumed);
+            input.lookahead(2);
+            input.skip_n(2);
+            assert!(!input.unread(1));
+            input.lookahead(1);
+            assert_eq!(input.peek(), 'c');
+        }
+        assert_eq!(consumed, "ab");
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot push back more characters than were consumed")]
+    pub fn unread_too_many() {
+        let mut input = StrInput::new("ab");
+        input.lookahead(1);
+        input.skip();
+        let _ = input.unread(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot push back more characters than the buffer can hold")]
+    pub fn unread_past_buffer() {
+        let mut input = StrInput::new("abcdefghijklmnopqrstuvwxyz");
+        input.skip_n(4);
+        input.lookahead(input.bufmaxlen() - 1);
+        let _ = input.unread(2);
+    }
 }
@@ -0,0 +1,188 @@
+use std::error::Error;
+
+use crate::input::Input;
+
+/// An input wrapper that records every character consumed from an inner input.
+///
+/// All calls are forwarded to the inner input. Each character that the scanner consumes is
+/// appended to the provided `String`, in order. Once the input has been fully parsed, the `String`
+/// holds the whole source. Comparing the length of the `String` at two points in time gives the
+/// exact text the scanner consumed in between, even for inputs that cannot be sliced (e.g.: a
+/// [`BufferedInput`]).
+///
+/// The `\0` characters used by inputs to pad the end of the stream are not recorded.
+///
+/// ```
+/// # use saphyr_parser::{Parser, StrInput, TeeInput};
+/// let mut consumed = String::new();
+/// let parser = Parser::new(TeeInput::new(StrInput::new("key: [a, b]\n"), &mut consumed));
+/// assert!(parser.map(Result::unwrap).count() > 0);
+/// assert_eq!(consumed, "key: [a, b]\n");
+/// ```
+///
+/// [`BufferedInput`]: crate::BufferedInput
+#[allow(clippy::module_name_repetitions)]
+pub struct TeeInput<'s, I: Input> {
+    /// The input we forward calls to.
+    inner: I,
+    /// Where we record consumed characters.
+    sink: &'s mut String,
+}
+
+impl<'s, I: Input> TeeInput<'s, I> {
+    /// Create a new [`TeeInput`] recording characters consumed from `inner` into `sink`.
+    pub fn new(inner: I, sink: &'s mut String) -> Self {
+        Self { inner, sink }
+    }
+
+    /// Consume the [`TeeInput`] and return the inner input.
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+
+    /// Record `c` as consumed, unless it is end-of-stream padding.
+    #[inline]
+    fn record(&mut self, c: char) {
+        if c != '\0' {
+            self.sink.push(c);
+        }
+    }
+}
+
+// Consuming functions that have a default implementation in [`Input`] (`skip_ws_to_eol`,
+// `skip_while_blank`, ...) are not forwarded. Their default implementations consume through
+// `skip`, which records characters.
+impl<I: Input> Input for TeeInput<'_, I> {
+    #[inline]
+    fn lookahead(&mut self, count: usize) {
+        self.inner.lookahead(count);
+    }
+
+    #[inline]
+    fn lookahead_exceeded(&mut self, count: usize) {
+        self.inner.lookahead_exceeded(count);
+    }
+
+    #[inline]
+    fn take_error(&mut self) -> Option<Box<dyn Error + Send + Sync + 'static>> {
+        self.inner.take_error()
+    }
+
+    #[inline]
+    fn remaining_hint(&self) -> Option<usize> {
+        self.inner.remaining_hint()
+    }
+
+    #[inline]
+    fn buflen(&self) -> usize {
+        self.inner.buflen()
+    }
+
+    #[inline]
+    fn bufmaxlen(&self) -> usize {
+        self.inner.bufmaxlen()
+    }
+
+    #[inline]
+    fn buf_is_empty(&self) -> bool {
+        self.inner.buf_is_empty()
+    }
+
+    #[inline]
+    fn raw_read_ch(&mut self) -> char {
+        let c = self.inner.raw_read_ch();
+        self.record(c);
+        c
+    }
+
+    #[inline]
+    fn raw_read_non_breakz_ch(&mut self) -> Option<char> {
+        let c = self.inner.raw_read_non_breakz_ch();
+        if let Some(c) = c {
+            self.record(c);
+        }
+        c
+    }
+
+    #[inline]
+    fn skip(&mut self) {
+        let c = self.inner.peek();
+        self.record(c);
+        self.inner.skip();
+    }
+
+    #[inline]
+    fn skip_n(&mut self, count: usize) {
+        for i in 0..count {
+            let c = self.inner.peek_nth(i);
+            self.record(c);
+        }
+        self.inner.skip_n(count);
+    }
+
+    #[inline]
+    fn unread(&mut self, n: usize) -> bool {
+        if !self.inner.unread(n) {
+            return false;
+        }
+        for _ in 0..n {
+            self.sink.pop();
+        }
+        true
+    }
+
+    #[inline]
+    fn peek(&self) -> char {
+        self.inner.peek()
+    }
+
+    #[inline]
+    fn peek_nth(&self, n: usize) -> char {
+        self.inner.peek_nth(n)
+    }
+
+    #[inline]
+    fn next_char_is(&self, c: char) -> bool {
+        self.inner.next_char_is(c)
+    }
+
+    #[inline]
+    fn nth_char_is(&self, n: usize, c: char) -> bool {
+        self.inner.nth_char_is(n, c)
+    }
+
+    #[inline]
+    fn next_2_are(&self, c1: char, c2: char) -> bool {
+        self.inner.next_2_are(c1, c2)
+    }
+
+    #[inline]
+    fn next_3_are(&self, c1: char, c2: char, c3: char) -> bool {
+        self.inner.next_3_are(c1, c2, c3)
+    }
+
+    #[inline]
+    fn starts_with(&self, s: &str) -> bool {
+        self.inner.starts_with(s)
+    }
+
+    #[inline]
+    fn next_is_document_indicator(&self) -> bool {
+        self.inner.next_is_document_indicator()
+    }
+
+    #[inline]
+    fn next_is_document_start(&self) -> bool {
+        self.inner.next_is_document_start()
+    }
+
+    #[inline]
+    fn next_is_document_end(&self) -> bool {
+        self.inner.next_is_document_end()
+    }
+
+    #[inline]
+    fn next_can_be_plain_scalar(&self, in_flow: bool) -> bool {
+        self.inner.next_can_be_plain_scalar(in_flow)
+    }
+}
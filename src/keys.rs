@@ -0,0 +1,146 @@
+//! Helpers walking the keys of mappings without keeping their values.
+
+use crate::{input::Input, Event, Parser, ScanError, Span};
+
+/// A key of a mapping, see [`MappingKeys`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MappingKey {
+    /// The value of the key.
+    pub value: String,
+    /// The range of the key in the source.
+    pub span: Span,
+    /// The number of mappings the mapping of the key is nested in.
+    ///
+    /// Keys of a mapping at the root of a document have a depth of 0. Sequences are not counted:
+    /// the keys of `b: c` in `a: [{b: c}]` have a depth of 1.
+    pub depth: usize,
+}
+
+/// An iterator over the keys of the mappings of a stream.
+///
+/// This is returned by [`Parser::into_mapping_keys`]. It is meant to outline large documents
+/// (e.g.: to generate a schema skeleton) and yields the scalar keys of every mapping, block or
+/// flow, in source order:
+///   - Values are skipped, unless they are mappings, whose keys are yielded in turn.
+///   - Sequences have no keys. The keys of the mappings they contain are yielded.
+///   - Keys that are aliases or collections are skipped. The keys of a mapping used as a key are
+///     yielded like those of any other mapping.
+///
+/// Scalars that are not keys are read into a buffer which is reused, so that skipping them does
+/// not allocate.
+///
+/// ```
+/// # use saphyr_parser::Parser;
+/// let yaml = "a: 1\nb:\n  c: [2, {d: 3}]\n";
+/// let keys: Vec<_> = Parser::new_from_str(yaml)
+///     .into_mapping_keys()
+///     .map(|key| {
+///         let key = key.unwrap();
+///         (key.value, key.depth)
+///     })
+///     .collect();
+/// assert_eq!(
+///     keys,
+///     [
+///         ("a".to_owned(), 0),
+///         ("b".to_owned(), 0),
+///         ("c".to_owned(), 1),
+///         ("d".to_owned(), 2)
+///     ]
+/// );
+/// ```
+pub struct MappingKeys<T: Input> {
+    /// The parser we pull events from.
+    parser: Parser<T>,
+    /// The buffer scalars are read into.
+    buf: String,
+    /// The collections we are in.
+    ///
+    /// Mappings have whether their next node is a key, sequences `None`.
+    stack: Vec<Option<bool>>,
+    /// The number of mappings in [`Self::stack`].
+    depth: usize,
+    /// Whether the end of the stream or an error was reached.
+    done: bool,
+}
+
+impl<T: Input> MappingKeys<T> {
+    /// Create a new [`MappingKeys`] iterating over the keys parsed by `parser`.
+    pub(crate) fn new(parser: Parser<T>) -> Self {
+        Self {
+            parser,
+            buf: String::new(),
+            stack: vec![],
+            depth: 0,
+            done: false,
+        }
+    }
+
+    /// Record that a node starts in the innermost collection.
+    ///
+    /// Return whether it is a key.
+    fn start_node(&mut self) -> bool {
+        match self.stack.last_mut() {
+            Some(Some(next_is_key)) => {
+                *next_is_key = !*next_is_key;
+                !*next_is_key
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<T: Input> Iterator for MappingKeys<T> {
+    type Item = Result<MappingKey, ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let (event, span) = match self.parser.next_event_into(&mut self.buf)? {
+                Ok(x) => x,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+            match event {
+                Event::Scalar(..) => {
+                    let is_key = self.start_node();
+                    if is_key {
+                        return Some(Ok(MappingKey {
+                            value: std::mem::take(&mut self.buf),
+                            span,
+                            depth: self.depth - 1,
+                        }));
+                    }
+                }
+                Event::Alias(_) => {
+                    self.start_node();
+                }
+                Event::SequenceStart(..) => {
+                    self.start_node();
+                    self.stack.push(None);
+                }
+                Event::MappingStart(..) => {
+                    self.start_node();
+                    self.stack.push(Some(true));
+                    self.depth += 1;
+                }
+                Event::SequenceEnd => {
+                    self.stack.pop();
+                }
+                Event::MappingEnd => {
+                    self.stack.pop();
+                    self.depth -= 1;
+                }
+                Event::StreamEnd => {
+                    self.done = true;
+                    return None;
+                }
+                _ => {}
+            }
+        }
+    }
+}
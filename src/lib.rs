@@ -23,22 +23,73 @@
 //! # Features
 //! **Note:** With all features disabled, this crate's MSRV is `1.65.0`.
 //!
+//! #### `async`
+//! Enables [`EventStream`], a [`futures_core::Stream`] of the events parsed from an asynchronous
+//! source of characters, e.g. a socket.
+//!
 //! #### `debug_prints`
 //! Enables the `debug` module and usage of debug prints in the scanner and the parser. Do not
 //! enable if you are consuming the crate rather than working on it as this can significantly
 //! decrease performance.
 //!
 //! The MSRV for this feature is `1.70.0`.
+//!
+//! #### `emitter`
+//! Enables [`events_to_yaml`], a best-effort helper serializing events back to YAML. It is meant
+//! for testing and debugging tools rather than as a full-fledged emitter.
+//!
+//! #### `serde`
+//! Implements `serde::Serialize` for [`Marker`], [`Span`] and [`ScanError`], e.g. to report
+//! diagnostics as JSON. A marker serializes as `{ "index": .., "line": .., "col": .. }`, a span
+//! as `{ "start": .., "end": .. }` and an error as `{ "marker": .., "info": .. }`.
+//!
+//! #### `testutil`
+//! Enables the [`testutil`] module, with helpers comparing event streams in tests and reporting
+//! the first differing event rather than both streams in full, and dumping event streams for
+//! snapshot tests.
 
 #![warn(missing_docs, clippy::pedantic)]
 
 mod char_traits;
+mod comments;
 #[macro_use]
 mod debug;
+mod documents;
+#[cfg(feature = "emitter")]
+mod emitter;
+mod indentation;
 mod input;
+mod keys;
+mod lines;
+mod pairs;
 mod parser;
 mod scanner;
+mod schema;
+#[cfg(feature = "serde")]
+mod serialize;
+mod stack;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "testutil")]
+pub mod testutil;
 
-pub use crate::input::{str::StrInput, BufferedInput};
-pub use crate::parser::{Event, EventReceiver, Parser, SpannedEventReceiver, Tag};
-pub use crate::scanner::{Marker, ScanError, Span, TScalarStyle};
+pub use crate::comments::{Comment, CommentedEvents};
+pub use crate::documents::{DocumentEvents, DocumentStream};
+#[cfg(feature = "emitter")]
+pub use crate::emitter::events_to_yaml;
+pub use crate::indentation::scan_indentation_issues;
+pub use crate::input::{str::StrInput, BufferedInput, FallibleInput, TeeInput};
+pub use crate::keys::{MappingKey, MappingKeys};
+pub use crate::lines::line_start;
+pub use crate::pairs::MappingPairs;
+pub use crate::parser::{
+    count_documents, parse_partial, validate, ControlFlowEventReceiver, DocumentEndCause, Event,
+    EventReceiver, OneDocument, Parser, ParserMetrics, RootKind, SpannedEventReceiver, Tag,
+    UnsupportedAction,
+};
+pub use crate::scanner::{
+    BlockScalarHeader, Chomping, Conflict, FoldMode, Marker, ScanError, Span, TScalarStyle,
+};
+pub use crate::schema::{decode_base64, ResolvedScalar, ScalarType, ScalarValue, SchemaVersion};
+#[cfg(feature = "async")]
+pub use crate::stream::EventStream;
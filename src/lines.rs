@@ -0,0 +1,41 @@
+//! A standalone mapping of line numbers to byte offsets, which does not parse the input.
+
+/// Return the byte offset at which line `line` of `input` starts.
+///
+/// Lines are numbered from 1 and separated by `\n`, `\r\n` or a lone `\r`, as in the
+/// [`Marker`]s the parser reports. This only scans the bytes of `input` for line breaks, so that
+/// tools (e.g.: editors) can jump to a line of a large input without parsing it. The line after
+/// a final line break exists and is empty.
+///
+/// Return `None` if `line` is 0 or past the last line of `input`.
+///
+/// ```
+/// # use saphyr_parser::line_start;
+/// let yaml = "a: 1\r\nb: 2\rc: 3\n";
+/// assert_eq!(line_start(yaml, 1), Some(0));
+/// assert_eq!(line_start(yaml, 2), Some(6));
+/// assert_eq!(line_start(yaml, 3), Some(11));
+/// assert_eq!(line_start(yaml, 4), Some(16));
+/// assert_eq!(line_start(yaml, 5), None);
+/// ```
+///
+/// [`Marker`]: crate::Marker
+#[must_use]
+pub fn line_start(input: &str, line: usize) -> Option<usize> {
+    if line == 0 {
+        return None;
+    }
+    let bytes = input.as_bytes();
+    let mut start = 0;
+    for _ in 1..line {
+        let line_break = start
+            + bytes[start..]
+                .iter()
+                .position(|&b| b == b'\n' || b == b'\r')?;
+        start = line_break + 1;
+        if bytes[line_break] == b'\r' && bytes.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+    }
+    Some(start)
+}
@@ -0,0 +1,148 @@
+//! Helpers pairing the keys of mappings with their values.
+
+use crate::{Event, Marker, ScanError, Span};
+
+/// An iterator over the spans of the keys and values of mappings.
+///
+/// This is built on top of an iterator over events (e.g.: a [`Parser`]) and yields a
+/// `(key_span, value_span)` pair for each entry of each mapping in the stream, whether block or
+/// flow, nested or not. Keys and values may be scalars, aliases or collections (including complex
+/// keys introduced with `?`). The span of a collection ranges from its first to its last
+/// character.
+///
+/// A pair is yielded once its value has been fully parsed. The entries of a nested mapping are
+/// hence yielded before the entry that contains that mapping.
+///
+/// ```
+/// # use saphyr_parser::{MappingPairs, Parser};
+/// let yaml = "a:\n  b: c\nd: [e]\n";
+/// let pairs: Vec<_> = MappingPairs::new(Parser::new_from_str(yaml))
+///     .map(|pair| {
+///         let (key, value) = pair.unwrap();
+///         (
+///             &yaml[key.start.index()..key.end.index()],
+///             &yaml[value.start.index()..value.end.index()],
+///         )
+///     })
+///     .collect();
+/// assert_eq!(pairs, [("b", "c"), ("a", "b: c"), ("d", "[e]")]);
+/// ```
+///
+/// [`Parser`]: crate::Parser
+pub struct MappingPairs<I> {
+    /// The events we pair keys and values from.
+    events: I,
+    /// The collections we are in.
+    stack: Vec<Collection>,
+    /// The end of the last node that was completed.
+    ///
+    /// Block collections end with an empty span located at the next token. We instead end them
+    /// at their last node so their span does not include trailing whitespace and comments.
+    last_end: Marker,
+    /// Whether an error was returned. No further item is yielded past an error.
+    errored: bool,
+}
+
+/// A collection being traversed by [`MappingPairs`].
+enum Collection {
+    /// A sequence starting at the given position.
+    Sequence(Marker),
+    /// A mapping starting at the given position.
+    Mapping {
+        /// The start of the mapping.
+        start: Marker,
+        /// The span of the key whose value we are expecting, if any.
+        key: Option<Span>,
+    },
+}
+
+impl<I> MappingPairs<I>
+where
+    I: Iterator<Item = Result<(Event, Span), ScanError>>,
+{
+    /// Create a new [`MappingPairs`] pairing keys and values from the given events.
+    pub fn new(events: I) -> Self {
+        Self {
+            events,
+            stack: vec![],
+            last_end: Marker::default(),
+            errored: false,
+        }
+    }
+
+    /// Record that a node spanning `span` has been completed.
+    ///
+    /// Return the pair it completes, if it is the value of a mapping entry.
+    fn complete_node(&mut self, span: Span) -> Option<(Span, Span)> {
+        self.last_end = span.end;
+        let Some(Collection::Mapping { key, .. }) = self.stack.last_mut() else {
+            return None;
+        };
+        if let Some(key_span) = key.take() {
+            Some((key_span, span))
+        } else {
+            *key = Some(span);
+            None
+        }
+    }
+}
+
+impl<I> Iterator for MappingPairs<I>
+where
+    I: Iterator<Item = Result<(Event, Span), ScanError>>,
+{
+    type Item = Result<(Span, Span), ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.errored {
+            return None;
+        }
+        loop {
+            let (event, span) = match self.events.next()? {
+                Ok(x) => x,
+                Err(e) => {
+                    self.errored = true;
+                    return Some(Err(e));
+                }
+            };
+            let pair = match event {
+                Event::Scalar(..) | Event::Alias(..) => self.complete_node(span),
+                Event::SequenceStart(..) => {
+                    self.stack.push(Collection::Sequence(span.start));
+                    None
+                }
+                Event::MappingStart(..) => {
+                    self.stack.push(Collection::Mapping {
+                        start: span.start,
+                        key: None,
+                    });
+                    None
+                }
+                Event::SequenceEnd | Event::MappingEnd => {
+                    let start = match self.stack.pop() {
+                        Some(Collection::Sequence(start) | Collection::Mapping { start, .. }) => {
+                            start
+                        }
+                        None => span.start,
+                    };
+                    // Flow collections end with a `]` or `}`, block collections with an empty span.
+                    let end = if span.is_empty() {
+                        self.last_end
+                    } else {
+                        span.end
+                    };
+                    self.complete_node(Span::new(start, end))
+                }
+                Event::StreamStart
+                | Event::StreamEnd
+                | Event::DocumentStart(_)
+                | Event::DocumentEnd
+                | Event::Indicator(_)
+                | Event::Nothing => None,
+            };
+            if let Some(pair) = pair {
+                return Some(Ok(pair));
+            }
+        }
+    }
+}
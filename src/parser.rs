@@ -5,12 +5,23 @@
 //! YAML objects.
 
 use crate::{
+    decode_base64,
+    documents::DocumentStream,
     input::{str::StrInput, Input},
-    scanner::{ScanError, Scanner, Span, TScalarStyle, Token, TokenType},
-    Marker,
+    keys::MappingKeys,
+    scanner::{
+        BlockScalarHeader, FoldMode, ScanError, Scanner, Span, TScalarStyle, Token, TokenType,
+    },
+    stack::SmallStack,
+    Marker, ResolvedScalar, ScalarType, ScalarValue, SchemaVersion,
 };
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    iter::FusedIterator,
+    ops::ControlFlow,
+    sync::Arc,
+};
 
 #[derive(Clone, Copy, PartialEq, Debug, Eq)]
 enum State {
@@ -38,11 +49,45 @@ enum State {
     End,
 }
 
+impl State {
+    /// Return the name of the state, without its associated data.
+    fn name(self) -> &'static str {
+        match self {
+            State::StreamStart => "StreamStart",
+            State::ImplicitDocumentStart => "ImplicitDocumentStart",
+            State::DocumentStart => "DocumentStart",
+            State::DocumentContent => "DocumentContent",
+            State::DocumentEnd => "DocumentEnd",
+            State::BlockNode => "BlockNode",
+            State::BlockSequenceFirstEntry => "BlockSequenceFirstEntry",
+            State::BlockSequenceEntry => "BlockSequenceEntry",
+            State::IndentlessSequenceEntry => "IndentlessSequenceEntry",
+            State::BlockMappingFirstKey => "BlockMappingFirstKey",
+            State::BlockMappingKey => "BlockMappingKey",
+            State::BlockMappingValue => "BlockMappingValue",
+            State::FlowSequenceFirstEntry => "FlowSequenceFirstEntry",
+            State::FlowSequenceEntry => "FlowSequenceEntry",
+            State::FlowSequenceEntryMappingKey => "FlowSequenceEntryMappingKey",
+            State::FlowSequenceEntryMappingValue => "FlowSequenceEntryMappingValue",
+            State::FlowSequenceEntryMappingEnd(_) => "FlowSequenceEntryMappingEnd",
+            State::FlowMappingFirstKey => "FlowMappingFirstKey",
+            State::FlowMappingKey => "FlowMappingKey",
+            State::FlowMappingValue => "FlowMappingValue",
+            State::FlowMappingEmptyValue => "FlowMappingEmptyValue",
+            State::End => "End",
+        }
+    }
+}
+
 /// An event generated by the YAML parser.
 ///
 /// Events are used in the low-level event-based API (push parser). The API entrypoint is the
 /// [`EventReceiver`] trait.
+///
+/// New variants may be added in minor releases. Code matching on an [`Event`] outside of this
+/// crate must have a wildcard arm.
 #[derive(Clone, PartialEq, Debug, Eq)]
+#[non_exhaustive]
 pub enum Event {
     /// Reserved for internal use.
     Nothing,
@@ -58,9 +103,17 @@ pub enum Event {
     /// When the boolean is `false`, it is an implicit document start
     /// (without `---`).
     DocumentStart(bool),
-    /// The YAML end document directive (`...`).
+    /// The end of a YAML document.
+    ///
+    /// The document may be ended by a `...` marker, the start of the next document or the end of
+    /// the stream. See [`Parser::document_end_cause`].
     DocumentEnd,
     /// A YAML Alias.
+    ///
+    /// The parser never expands aliases: an alias is a single event however large the node of its
+    /// anchor is, and however many aliases that node contains. Aliases referring to nodes full of
+    /// aliases thus cost neither time nor stack to parse. Consumers that expand aliases must bound
+    /// the work this requires themselves.
     Alias(
         /// The anchor ID the alias refers to.
         usize,
@@ -85,6 +138,12 @@ pub enum Event {
     ),
     /// The end of a YAML mapping (object, hash).
     MappingEnd,
+    /// An indicator introducing a mapping key (`?`), a mapping value (`:`) or a block sequence
+    /// entry (`-`).
+    ///
+    /// Only emitted when enabled with [`Parser::set_indicator_events`], right before the node the
+    /// indicator introduces. Its span covers the indicator. Implicit keys have no `?` indicator.
+    Indicator(char),
 }
 
 /// A YAML tag.
@@ -96,7 +155,184 @@ pub struct Tag {
     pub suffix: String,
 }
 
+impl Tag {
+    /// Return whether this is the non-specific `!` tag.
+    ///
+    /// A node with a bare `!` tag (e.g.: `! 12`) is untagged, but must be resolved as if it were
+    /// not plain: it is a string, a sequence or a mapping depending on its kind. The `!` tag is
+    /// reported with an empty `handle` and a `"!"` `suffix`.
+    ///
+    /// Nodes without a tag property (the `?` non-specific tag) have no [`Tag`] at all.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let tags: Vec<_> = Parser::new_from_str("[! 12, 12]")
+    ///     .filter_map(|ev| match ev.unwrap().0 {
+    ///         Event::Scalar(_, _, _, tag) => Some(tag),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert!(tags[0].as_ref().is_some_and(|tag| tag.is_non_specific()));
+    /// assert!(tags[1].is_none());
+    /// ```
+    #[must_use]
+    pub fn is_non_specific(&self) -> bool {
+        self.handle.is_empty() && self.suffix == "!"
+    }
+}
+
 impl Event {
+    /// Return whether the event is a [`Event::Scalar`].
+    #[must_use]
+    pub fn is_scalar(&self) -> bool {
+        matches!(self, Event::Scalar(..))
+    }
+
+    /// Return the value, style, anchor ID and tag of a [`Event::Scalar`].
+    ///
+    /// Return `None` if the event is not a scalar.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, TScalarStyle};
+    /// let event = Event::Scalar("foo".to_owned(), TScalarStyle::Plain, 0, None);
+    /// assert_eq!(event.as_scalar(), Some(("foo", TScalarStyle::Plain, 0, None)));
+    /// assert_eq!(Event::StreamEnd.as_scalar(), None);
+    /// ```
+    #[must_use]
+    pub fn as_scalar(&self) -> Option<(&str, TScalarStyle, usize, Option<&Tag>)> {
+        match self {
+            Event::Scalar(value, style, anchor, tag) => {
+                Some((value, *style, *anchor, tag.as_ref()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Return whether the event is a [`Event::Scalar`] whose value is `value`.
+    ///
+    /// Only the value is compared: the style, anchor ID and tag of the scalar are ignored. Use
+    /// `==` to compare them as well.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let events: Vec<Event> = Parser::new_from_str("[a, 'a', \"a\", &x !t a, b]")
+    ///     .map(|ev| ev.unwrap().0)
+    ///     .filter(Event::is_scalar)
+    ///     .collect();
+    /// assert!(events[..4].iter().all(|ev| ev.scalar_value_eq("a")));
+    /// assert!(!events[4].scalar_value_eq("a"));
+    /// assert!(!Event::StreamEnd.scalar_value_eq(""));
+    /// ```
+    #[must_use]
+    pub fn scalar_value_eq(&self, value: &str) -> bool {
+        matches!(self, Event::Scalar(v, ..) if v == value)
+    }
+
+    /// Return whether the event is a [`Event::SequenceStart`] or a [`Event::MappingStart`].
+    ///
+    /// ```
+    /// # use saphyr_parser::Event;
+    /// assert!(Event::SequenceStart(0, None).is_collection_start());
+    /// assert!(Event::MappingStart(0, None).is_collection_start());
+    /// assert!(!Event::SequenceEnd.is_collection_start());
+    /// ```
+    #[must_use]
+    pub fn is_collection_start(&self) -> bool {
+        matches!(self, Event::SequenceStart(..) | Event::MappingStart(..))
+    }
+
+    /// Return whether the event is a [`Event::SequenceEnd`] or a [`Event::MappingEnd`].
+    ///
+    /// ```
+    /// # use saphyr_parser::Event;
+    /// assert!(Event::SequenceEnd.is_collection_end());
+    /// assert!(Event::MappingEnd.is_collection_end());
+    /// assert!(!Event::MappingStart(0, None).is_collection_end());
+    /// ```
+    #[must_use]
+    pub fn is_collection_end(&self) -> bool {
+        matches!(self, Event::SequenceEnd | Event::MappingEnd)
+    }
+
+    /// Return the anchor ID of the event.
+    ///
+    /// For a [`Event::Scalar`], [`Event::SequenceStart`] or [`Event::MappingStart`], this is the
+    /// ID of the anchor defined on the node. For an [`Event::Alias`], this is the ID of the anchor
+    /// it refers to. Return `None` for other events and for nodes without an anchor (ID `0`).
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let ids: Vec<_> = Parser::new_from_str("[&a x, *a, y]")
+    ///     .filter_map(|ev| {
+    ///         let event = ev.unwrap().0;
+    ///         event.is_scalar().then(|| event.anchor_id())
+    ///     })
+    ///     .collect();
+    /// assert_eq!(ids, [Some(1), None]);
+    /// ```
+    #[must_use]
+    pub fn anchor_id(&self) -> Option<usize> {
+        match self {
+            Event::Alias(id)
+            | Event::Scalar(_, _, id, _)
+            | Event::SequenceStart(id, _)
+            | Event::MappingStart(id, _) => Some(*id).filter(|&id| id != 0),
+            _ => None,
+        }
+    }
+
+    /// Return the tag of a [`Event::Scalar`], [`Event::SequenceStart`] or [`Event::MappingStart`].
+    ///
+    /// Return `None` for other events and for nodes without a tag.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let tags: Vec<_> = Parser::new_from_str("!!map {a: !t b}")
+    ///     .filter_map(|ev| ev.unwrap().0.tag().map(|tag| tag.suffix.clone()))
+    ///     .collect();
+    /// assert_eq!(tags, ["map", "t"]);
+    /// ```
+    #[must_use]
+    pub fn tag(&self) -> Option<&Tag> {
+        match self {
+            Event::Scalar(_, _, _, tag)
+            | Event::SequenceStart(_, tag)
+            | Event::MappingStart(_, tag) => tag.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Decode the content of a [`Event::Scalar`] tagged `!!binary` from base64.
+    ///
+    /// Return `None` if the event is not a scalar or is not tagged `!!binary`. Decoding errors are
+    /// reported at the start of `span`, the span of the event.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("icon: !!binary |\n  R0lG\n  ODlh\n");
+    /// let binaries: Vec<_> = parser
+    ///     .by_ref()
+    ///     .filter_map(|ev| {
+    ///         let (event, span) = ev.unwrap();
+    ///         event.decode_binary(span)
+    ///     })
+    ///     .map(Result::unwrap)
+    ///     .collect();
+    /// assert_eq!(binaries, [b"GIF89a"]);
+    /// ```
+    #[must_use]
+    pub fn decode_binary(&self, span: Span) -> Option<Result<Vec<u8>, ScanError>> {
+        match self {
+            Event::Scalar(value, _, _, Some(tag))
+                if tag.handle == "tag:yaml.org,2002:" && tag.suffix == "binary"
+                    || tag.handle.is_empty() && tag.suffix == "tag:yaml.org,2002:binary" =>
+            {
+                Some(decode_base64(value, span.start))
+            }
+            _ => None,
+        }
+    }
+
     /// Create an empty scalar.
     fn empty_scalar() -> Event {
         // a null scalar
@@ -109,6 +345,227 @@ impl Event {
     }
 }
 
+/// The kind of node expected at the root of documents, see [`Parser::expect_root`].
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+pub enum RootKind {
+    /// A block or flow mapping.
+    Mapping,
+    /// A block or flow sequence.
+    Sequence,
+    /// A scalar of any style, including the empty scalar of an empty document.
+    Scalar,
+}
+
+impl RootKind {
+    /// Return the kind of the node `event` starts, if it starts one.
+    fn of(event: &Event) -> Option<Self> {
+        match event {
+            Event::MappingStart(..) => Some(RootKind::Mapping),
+            Event::SequenceStart(..) => Some(RootKind::Sequence),
+            Event::Scalar(..) => Some(RootKind::Scalar),
+            _ => None,
+        }
+    }
+
+    /// Return the name of the kind, for error messages.
+    fn name(self) -> &'static str {
+        match self {
+            RootKind::Mapping => "a mapping",
+            RootKind::Sequence => "a sequence",
+            RootKind::Scalar => "a scalar",
+        }
+    }
+}
+
+/// What ended a document, see [`Parser::document_end_cause`].
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+pub enum DocumentEndCause {
+    /// The end of the stream.
+    Eof,
+    /// A document end marker (`...`).
+    ExplicitDots,
+    /// The document start marker (`---`) of the next document.
+    NextDocumentMarker,
+}
+
+/// What to do with an error the parser can skip, see [`Parser::set_on_unsupported`].
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+pub enum UnsupportedAction {
+    /// Ignore the error and carry on parsing.
+    Skip,
+    /// Return the error.
+    Fail,
+}
+
+/// The default of [`Parser::set_max_interned_bytes`].
+const DEFAULT_MAX_INTERNED_BYTES: usize = 1024 * 1024;
+
+/// What is recorded about an event on top of its span, if enabled.
+#[derive(Clone, Debug, Default)]
+struct EventDetails {
+    /// See [`Parser::indentation`].
+    indentation: Option<usize>,
+    /// See [`Parser::anchor_span`].
+    anchor_span: Option<Span>,
+    /// See [`Parser::tag_span`].
+    tag_span: Option<Span>,
+    /// See [`Parser::number`].
+    number: Option<ScalarValue>,
+    /// See [`Parser::interned_scalar`].
+    interned: Option<Arc<str>>,
+    /// See [`Parser::last_scalar_streamed`].
+    streamed: bool,
+}
+
+/// A callback deciding whether to skip errors, see [`Parser::set_on_unsupported`].
+struct OnUnsupported(Box<dyn FnMut(&ScanError) -> UnsupportedAction + Send + Sync>);
+
+impl std::fmt::Debug for OnUnsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("OnUnsupported")
+    }
+}
+
+// Callbacks must not keep the parser from being sent to or shared with other threads.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Parser<StrInput<'static>>>();
+    assert_send_sync::<Parser<crate::BufferedInput<std::str::Chars<'static>>>>();
+};
+
+/// Measurements of what a [`Parser`] parsed, see [`Parser::set_collect_metrics`].
+///
+/// These are the quantities the limits of the parser bound, which can help choosing these limits.
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
+pub struct ParserMetrics {
+    /// The number of events emitted.
+    events: usize,
+    /// The number of collections we are in.
+    depth: usize,
+    /// The maximum number of collections we have been in at once.
+    max_depth: usize,
+    /// The number of documents started.
+    documents: usize,
+    /// The number of aliases.
+    aliases: usize,
+    /// The total size, in bytes, of the values of scalars.
+    scalar_bytes: usize,
+}
+
+impl ParserMetrics {
+    /// Return the number of events the parser emitted.
+    ///
+    /// This includes [`Event::StreamStart`] and, if emitted, [`Event::Indicator`]s.
+    #[must_use]
+    pub fn events(&self) -> usize {
+        self.events
+    }
+
+    /// Return the maximum nesting depth reached: the largest number of collections, block or flow,
+    /// that were nested within one another.
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Return the number of documents the parser started.
+    #[must_use]
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    /// Return the number of aliases the parser emitted.
+    #[must_use]
+    pub fn aliases(&self) -> usize {
+        self.aliases
+    }
+
+    /// Return the total size, in bytes, of the values of the scalars the parser emitted.
+    ///
+    /// This is the size of the values once escape sequences are resolved and lines folded, not
+    /// that of the scalars in the source.
+    #[must_use]
+    pub fn scalar_bytes(&self) -> usize {
+        self.scalar_bytes
+    }
+
+    /// Account for `event` being emitted.
+    fn record(&mut self, event: &Event) {
+        self.events += 1;
+        match event {
+            Event::DocumentStart(_) => self.documents += 1,
+            Event::Alias(_) => self.aliases += 1,
+            Event::Scalar(value, ..) => self.scalar_bytes += value.len(),
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                self.depth += 1;
+                self.max_depth = self.max_depth.max(self.depth);
+            }
+            Event::SequenceEnd | Event::MappingEnd => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+}
+
+/// The outcome of [`Parser::parse_one_document`].
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub enum OneDocument {
+    /// A whole document was parsed.
+    ///
+    /// The events go from [`Event::DocumentStart`] to [`Event::DocumentEnd`]. The marker is the
+    /// position right after the document, including its `...` end marker, if any. If the document
+    /// is followed by the `---` of another document, the marker is at the start of the `---`.
+    Document(Vec<Event>, Marker),
+    /// The input ends in the middle of a document, which may be completed with more input.
+    NeedsMoreInput,
+    /// There is no document left in the input.
+    EndOfStream,
+}
+
+/// The options of a [`Parser`] which are either enabled or disabled, as a set of bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ParserFlags(u16);
+
+impl ParserFlags {
+    /// Make tags global across all documents, see [`Parser::keep_tags`].
+    const KEEP_TAGS: Self = Self(1);
+    /// Make anchors global across all documents, set by [`Parser::with_anchors`].
+    const KEEP_ANCHORS: Self = Self(1 << 1);
+    /// Error on anchors and aliases, see [`Parser::set_forbid_anchors`].
+    const FORBID_ANCHORS: Self = Self(1 << 2);
+    /// Accept empty entries in flow sequences, see [`Parser::set_allow_empty_flow_entries`].
+    const ALLOW_EMPTY_FLOW_ENTRIES: Self = Self(1 << 3);
+    /// Emit [`Event::Indicator`]s, see [`Parser::set_indicator_events`].
+    const INDICATOR_EVENTS: Self = Self(1 << 4);
+    /// Parse only a front matter block and ignore the rest of the input, see
+    /// [`Parser::set_frontmatter_mode`].
+    const FRONTMATTER_MODE: Self = Self(1 << 5);
+    /// Record the spans of the anchors and tags of nodes, see
+    /// [`Parser::set_record_property_spans`].
+    const RECORD_PROPERTY_SPANS: Self = Self(1 << 6);
+    /// Parse plain scalars that are numbers, see [`Parser::set_parse_numbers`].
+    const PARSE_NUMBERS: Self = Self(1 << 7);
+    /// Set when an option recording [`EventDetails`] is enabled.
+    ///
+    /// This is checked once per event so that parsing is not slowed down when none is.
+    const DETAILS_REQUESTED: Self = Self(1 << 8);
+
+    /// Return whether `flag` is set.
+    #[inline]
+    fn contains(self, flag: Self) -> bool {
+        self.0 & flag.0 != 0
+    }
+
+    /// Set or clear `flag`.
+    #[inline]
+    fn set(&mut self, flag: Self, value: bool) {
+        if value {
+            self.0 |= flag.0;
+        } else {
+            self.0 &= !flag.0;
+        }
+    }
+}
+
 /// A YAML parser.
 #[derive(Debug)]
 pub struct Parser<T: Input> {
@@ -118,7 +575,7 @@ pub struct Parser<T: Input> {
     ///
     /// States are pushed in the context of subobjects to this stack. The top-most element is the
     /// state in which to come back to when exiting the current state.
-    states: Vec<State>,
+    states: SmallStack<State, 16>,
     /// The state in which we currently are.
     state: State,
     /// The next token from the scanner.
@@ -141,8 +598,55 @@ pub struct Parser<T: Input> {
     /// Emitted means that it has been returned from [`Self::next_token`]. If it is stored in
     /// [`Self::token`], this is set to `false`.
     stream_end_emitted: bool,
-    /// Make tags global across all documents.
-    keep_tags: bool,
+    /// Whether loading an event returned an error.
+    errored: bool,
+    /// The options of the parser which are either enabled or disabled.
+    flags: ParserFlags,
+    /// The prefix of the primary `!` handle when no `%TAG` directive redefines it.
+    tag_base: Option<String>,
+    /// The maximum total size, in bytes, of the names in [`Self::anchors`], if any.
+    max_anchor_bytes: Option<usize>,
+    /// The total size, in bytes, of the names in [`Self::anchors`].
+    anchor_bytes: usize,
+    /// The maximum number of items a flow collection may contain, if any.
+    max_flow_items: Option<usize>,
+    /// The number of items in each of the flow collections we are in, innermost last.
+    flow_item_counts: Vec<usize>,
+    /// The schema used to resolve booleans and null.
+    schema: SchemaVersion,
+    /// Events to emit before running the state machine again.
+    ///
+    /// The state machine emits one event at a time. [`Event::Indicator`]s found while emitting an
+    /// event are queued here, followed by that event.
+    pending_events: VecDeque<(Event, Span)>,
+    /// The kind of node the root of documents must be, if any.
+    expected_root: Option<RootKind>,
+    /// Whether the next node is the root of a document.
+    at_document_root: bool,
+    /// What ended the last document, if one has ended.
+    document_end_cause: Option<DocumentEndCause>,
+    /// The version given by the `%YAML` directive of the current document, if any.
+    document_version: Option<(u32, u32)>,
+    /// The metrics of what has been parsed, if they are collected.
+    metrics: Option<ParserMetrics>,
+    /// The indentation of the collections we are in, innermost last, if indentation is recorded.
+    ///
+    /// Block collections have their indentation, flow collections `None`.
+    collection_indents: Option<Vec<Option<usize>>>,
+    /// The values of the scalars returned so far, if they are interned.
+    ///
+    /// See [`Parser::set_intern_scalars`].
+    interned_scalars: Option<InternedScalars>,
+    /// See [`Parser::set_max_interned_bytes`].
+    max_interned_bytes: usize,
+    /// The spans of the anchor and tag of the node being parsed, if recorded.
+    property_spans: (Option<Span>, Option<Span>),
+    /// What was recorded about the last event returned.
+    details: EventDetails,
+    /// What was recorded about the event [peeked](Parser::peek) at.
+    peeked_details: EventDetails,
+    /// The callback deciding whether to skip errors, if any.
+    on_unsupported: Option<OnUnsupported>,
 }
 
 /// Trait to be implemented in order to use the low-level parsing API.
@@ -166,6 +670,20 @@ pub struct Parser<T: Input> {
 ///
 /// In a sequence, values are sent consecutively until the [`Event::SequenceEnd`] event.
 ///
+/// # Empty streams and documents
+/// A stream with no content (empty, or only containing whitespace, comments or document end
+/// markers (`...`)) contains no document. It emits [`Event::StreamStart`] immediately followed by
+/// [`Event::StreamEnd`].
+///
+/// Each document start marker (`---`) starts an explicit document, even if it has no content. An
+/// empty document contains a single null scalar (`~`). For instance, `---` alone emits:
+/// ```text
+/// StreamStart, DocumentStart(true), Scalar("~", ..), DocumentEnd, StreamEnd
+/// ```
+///
+/// Directives (`%YAML`, `%TAG`) must be followed by a document start marker. A stream containing
+/// directives but no document is an error.
+///
 /// If a value is a sub-mapping or a sub-sequence, an [`Event::MappingStart`] or
 /// [`Event::SequenceStart`] event will be sent respectively. Following events until the associated
 /// [`Event::MappingStart`] or [`Event::SequenceEnd`] (beware of nested mappings or sequences) will
@@ -233,6 +751,51 @@ impl<R: EventReceiver> SpannedEventReceiver for R {
     }
 }
 
+/// Trait to be implemented for using the low-level parsing API and stopping it early.
+///
+/// Functionally similar to [`SpannedEventReceiver`], but the receiver can request the parser to
+/// stop by returning [`ControlFlow::Break`]. [`Parser::load`] then returns without pulling any
+/// further event from the input. This is useful when only part of the stream is of interest (e.g.:
+/// finding the first occurrence of a key). Note that [`SpannedEventReceiver`]s (and thus
+/// [`EventReceiver`]s) implement [`ControlFlowEventReceiver`] automatically and never stop early.
+///
+/// # Example
+/// ```
+/// # use std::ops::ControlFlow;
+/// # use saphyr_parser::{ControlFlowEventReceiver, Event, Parser, Span};
+/// #
+/// /// Stops at the first scalar and keeps its value.
+/// struct FirstScalar(Option<String>);
+///
+/// impl ControlFlowEventReceiver for FirstScalar {
+///     fn on_event(&mut self, ev: Event, _span: Span) -> ControlFlow<()> {
+///         if let Event::Scalar(value, ..) = ev {
+///             self.0 = Some(value);
+///             ControlFlow::Break(())
+///         } else {
+///             ControlFlow::Continue(())
+///         }
+///     }
+/// }
+///
+/// let mut recv = FirstScalar(None);
+/// Parser::new_from_str("[a, b, c]").load(&mut recv, true).unwrap();
+/// assert_eq!(recv.0.as_deref(), Some("a"));
+/// ```
+pub trait ControlFlowEventReceiver {
+    /// Handler called for each event that occurs.
+    ///
+    /// Returning [`ControlFlow::Break`] stops the parsing.
+    fn on_event(&mut self, ev: Event, span: Span) -> ControlFlow<()>;
+}
+
+impl<R: SpannedEventReceiver> ControlFlowEventReceiver for R {
+    fn on_event(&mut self, ev: Event, span: Span) -> ControlFlow<()> {
+        SpannedEventReceiver::on_event(self, ev, span);
+        ControlFlow::Continue(())
+    }
+}
+
 /// A convenience alias for a `Result` of a parser event.
 pub type ParseResult = Result<(Event, Span), ScanError>;
 
@@ -249,7 +812,7 @@ impl<T: Input> Parser<T> {
     pub fn new(src: T) -> Parser<T> {
         Parser {
             scanner: Scanner::new(src),
-            states: Vec::new(),
+            states: SmallStack::new(),
             state: State::StreamStart,
             token: None,
             current: None,
@@ -259,10 +822,50 @@ impl<T: Input> Parser<T> {
             anchor_id_count: 1,
             tags: HashMap::new(),
             stream_end_emitted: false,
-            keep_tags: false,
+            errored: false,
+            flags: ParserFlags::default(),
+            tag_base: None,
+            max_anchor_bytes: None,
+            anchor_bytes: 0,
+            max_flow_items: None,
+            flow_item_counts: Vec::new(),
+            schema: SchemaVersion::default(),
+            pending_events: VecDeque::new(),
+            expected_root: None,
+            at_document_root: false,
+            document_end_cause: None,
+            document_version: None,
+            metrics: None,
+            collection_indents: None,
+            interned_scalars: None,
+            max_interned_bytes: DEFAULT_MAX_INTERNED_BYTES,
+            property_spans: (None, None),
+            details: EventDetails::default(),
+            peeked_details: EventDetails::default(),
+            on_unsupported: None,
         }
     }
 
+    /// Return the name of the state the parser is in, for debugging purposes.
+    ///
+    /// The state is what the parser expects next (e.g.: `BlockMappingFirstKey` right after the
+    /// start of a block mapping). Names are meant to be logged while investigating how a document
+    /// is parsed. They are not part of the stable API and may change in any release.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: b");
+    /// assert_eq!(parser.state_name(), "StreamStart");
+    /// parser.next_event(); // StreamStart
+    /// parser.next_event(); // DocumentStart
+    /// parser.next_event(); // MappingStart
+    /// assert_eq!(parser.state_name(), "BlockMappingFirstKey");
+    /// ```
+    #[must_use]
+    pub fn state_name(&self) -> &'static str {
+        self.state.name()
+    }
+
     /// Whether to keep tags across multiple documents when parsing.
     ///
     /// This behavior is non-standard as per the YAML specification but can be encountered in the
@@ -284,11 +887,1045 @@ impl<T: Input> Parser<T> {
     /// With `keep_tags` set to `false`, the above YAML is rejected. As per the specification, tags
     /// only apply to the document immediately following them. This would error on `!prefix!B`.
     ///
-    /// With `keep_tags` set to `true`, the above YAML is accepted by the parser.
-    #[must_use]
-    pub fn keep_tags(mut self, value: bool) -> Self {
-        self.keep_tags = value;
-        self
+    /// With `keep_tags` set to `true`, the above YAML is accepted by the parser.
+    #[must_use]
+    pub fn keep_tags(mut self, value: bool) -> Self {
+        self.flags.set(ParserFlags::KEEP_TAGS, value);
+        self
+    }
+
+    /// Seed the anchors of the parser with `anchors`, a table of anchor names to anchor IDs.
+    ///
+    /// This is meant for protocols that split a single logical YAML stream across several inputs,
+    /// each parsed by a fresh parser: the table returned by [`Self::take_anchors`] once a fragment
+    /// is parsed lets aliases of the next fragment refer to anchors defined in earlier ones. New
+    /// anchors are given IDs greater than those in `anchors`, so IDs stay unique across fragments.
+    ///
+    /// This behavior is non-standard: as per the specification, anchors are scoped to their
+    /// document. Using this function makes anchors global across all documents of the parser,
+    /// including those of the first fragment, which should be parsed with an empty table.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut first = Parser::new_from_str("a: &x 1\n").with_anchors(Default::default());
+    /// first.by_ref().for_each(|ev| drop(ev.unwrap()));
+    /// let mut second = Parser::new_from_str("b: *x\n").with_anchors(first.take_anchors());
+    /// assert!(second.any(|ev| ev.unwrap().0 == Event::Alias(1)));
+    /// ```
+    #[must_use]
+    pub fn with_anchors(mut self, anchors: HashMap<String, usize>) -> Self {
+        self.anchor_id_count = anchors.values().max().map_or(1, |&id| id + 1);
+        self.anchor_bytes = anchors.keys().map(String::len).sum();
+        self.anchors = anchors;
+        self.flags.set(ParserFlags::KEEP_ANCHORS, true);
+        self
+    }
+
+    /// Take the table of anchor names to anchor IDs out of the parser.
+    ///
+    /// Unless [`Self::with_anchors`] was used, anchors are dropped at the end of each document and
+    /// this only returns the anchors of the current document. The parser is left without anchors.
+    pub fn take_anchors(&mut self) -> HashMap<String, usize> {
+        self.anchor_bytes = 0;
+        std::mem::take(&mut self.anchors)
+    }
+
+    /// Whether to reject anchors (`&anchor`) and aliases (`*alias`).
+    ///
+    /// When set to `true`, the parser returns an error at the first anchor or alias it encounters,
+    /// pointing at its `&` or `*` indicator. This is meant for untrusted input, where aliases could
+    /// otherwise be used to make consumers expand a small document into a huge one.
+    ///
+    /// Anchors and aliases are allowed by default.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: &x 1\nb: *x");
+    /// parser.set_forbid_anchors(true);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(err.marker().col(), 3);
+    /// ```
+    pub fn set_forbid_anchors(&mut self, forbid: bool) {
+        self.flags.set(ParserFlags::FORBID_ANCHORS, forbid);
+    }
+
+    /// Error if the names of the anchors of a document total more than `max` bytes.
+    ///
+    /// The parser keeps the name of every anchor it encounters to resolve aliases. A name is
+    /// stored once, however many anchors use it: only distinct names count towards the limit.
+    /// This is meant for untrusted input, where many long anchor names could otherwise use an
+    /// unbounded amount of memory.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("[&abc 1, &abc 2, &de 3]");
+    /// parser.set_max_anchor_bytes(4);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(err.marker().index(), 17);
+    /// ```
+    pub fn set_max_anchor_bytes(&mut self, max: usize) {
+        self.max_anchor_bytes = Some(max);
+    }
+
+    /// Emit an [`Event::Indicator`] for each `?`, `:` and `-` indicator.
+    ///
+    /// This is meant for tools that need to know where indicators are (e.g.: formatters), which
+    /// events otherwise do not tell. Each indicator event immediately precedes the event of the
+    /// node it introduces. Indicator events are disabled by default.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("- a: b");
+    /// parser.set_indicator_events(true);
+    /// let indicators: Vec<_> = parser
+    ///     .filter_map(|ev| match ev.unwrap() {
+    ///         (Event::Indicator(c), span) => Some((c, span.start.index())),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(indicators, [('-', 0), (':', 3)]);
+    /// ```
+    pub fn set_indicator_events(&mut self, indicator_events: bool) {
+        self.flags
+            .set(ParserFlags::INDICATOR_EVENTS, indicator_events);
+    }
+
+    /// Record the spans of the trivia found between tokens, for lossless tools.
+    ///
+    /// Trivia are the whitespace, line breaks, indentation and comments that separate tokens. Each
+    /// contiguous run of trivia is recorded as a single [`Span`], retrieved with
+    /// [`Self::take_trivia`]. Along with the spans of events, this allows reconstructing the
+    /// layout of the source (e.g.: to rewrite a document while changing as little as possible).
+    /// Trivia are not recorded by default.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let yaml = "a:  1 # one\n\nb: 2\n";
+    /// let mut parser = Parser::new_from_str(yaml);
+    /// parser.set_record_trivia(true);
+    /// for event in &mut parser {
+    ///     event.unwrap();
+    /// }
+    /// let trivia: Vec<_> = parser
+    ///     .take_trivia()
+    ///     .iter()
+    ///     .map(|span| &yaml[span.start.index()..span.end.index()])
+    ///     .collect();
+    /// assert_eq!(trivia, ["  ", " # one\n\n", " ", "\n"]);
+    /// ```
+    pub fn set_record_trivia(&mut self, record: bool) {
+        self.scanner.set_record_trivia(record);
+    }
+
+    /// Return the spans of the trivia recorded since the last call, in source order.
+    ///
+    /// The scanner reads ahead of the events returned by the parser. The trivia returned may thus
+    /// extend past the last event. See [`Self::set_record_trivia`].
+    pub fn take_trivia(&mut self) -> Vec<Span> {
+        self.scanner.take_trivia()
+    }
+
+    /// Hand the content of block scalars to `sink` in chunks, rather than storing it in their
+    /// event.
+    ///
+    /// This bounds the memory used to parse very large literal (`|`) and folded (`>`) scalars
+    /// (e.g.: logs). Once a sink is set, the content of every block scalar is handed to it in
+    /// chunks of about 64KiB and the [`Event::Scalar`] of the block scalar has an empty value,
+    /// which [`Self::last_scalar_streamed`] tells apart from an empty block scalar. A block scalar
+    /// whose content is empty is not handed to `sink`. Other scalars are not affected.
+    ///
+    /// The scanner reads ahead of the events returned by the parser: the chunks of a block scalar
+    /// are handed to `sink` before its event is returned, but possibly after the events that
+    /// precede it.
+    ///
+    /// `sink` must be [`Send`] and [`Sync`] so that the parser remains so.
+    ///
+    /// ```
+    /// # use std::sync::{Arc, Mutex};
+    /// # use saphyr_parser::{Event, Parser, TScalarStyle};
+    /// let content = Arc::new(Mutex::new(String::new()));
+    /// let mut parser = Parser::new_from_str("log: |\n  line 1\n  line 2\n");
+    /// let sink = Arc::clone(&content);
+    /// parser.set_block_scalar_sink(move |chunk| sink.lock().unwrap().push_str(chunk));
+    /// while let Some(event) = parser.next() {
+    ///     if let (Event::Scalar(value, TScalarStyle::Literal, ..), _) = event.unwrap() {
+    ///         assert_eq!(value, "");
+    ///         assert!(parser.last_scalar_streamed());
+    ///         assert_eq!(std::mem::take(&mut *content.lock().unwrap()), "line 1\nline 2\n");
+    ///     }
+    /// }
+    /// ```
+    pub fn set_block_scalar_sink<F: FnMut(&str) + Send + Sync + 'static>(&mut self, sink: F) {
+        self.scanner.set_block_scalar_sink(sink);
+        self.update_details_requested();
+    }
+
+    /// Return whether the last event returned is a block scalar whose content was handed to the
+    /// sink set with [`Self::set_block_scalar_sink`].
+    ///
+    /// The value of such a scalar is empty, its content being what the sink received.
+    #[must_use]
+    pub fn last_scalar_streamed(&self) -> bool {
+        self.details.streamed
+    }
+
+    /// Record the whitespace stripped from the end of the lines of plain scalars, for linters.
+    ///
+    /// Plain scalars never include the spaces and tabs that end their lines, which are often left
+    /// there by accident. Values are not affected by this option: each run of such whitespace is
+    /// only recorded as a [`Span`], retrieved with [`Self::take_trailing_whitespace`]. Whitespace
+    /// separating a scalar from a comment is not recorded. Trailing whitespace is not recorded by
+    /// default.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: b  \nc: d # e\n");
+    /// parser.set_record_trailing_whitespace(true);
+    /// for event in &mut parser {
+    ///     event.unwrap();
+    /// }
+    /// let spans = parser.take_trailing_whitespace();
+    /// assert_eq!(spans.len(), 1);
+    /// assert_eq!((spans[0].start.col(), spans[0].end.col()), (4, 6));
+    /// ```
+    pub fn set_record_trailing_whitespace(&mut self, record: bool) {
+        self.scanner.set_record_trailing_whitespace(record);
+    }
+
+    /// Return the spans of the trailing whitespace recorded since the last call, in source order.
+    ///
+    /// As with [`Self::take_trivia`], the scanner reads ahead of the events returned by the
+    /// parser. See [`Self::set_record_trailing_whitespace`].
+    pub fn take_trailing_whitespace(&mut self) -> Vec<Span> {
+        self.scanner.take_trailing_whitespace()
+    }
+
+    /// Record the header of each block scalar, for tools that need to preserve it.
+    ///
+    /// The header of a block scalar (e.g.: `|2-`) holds its indentation and chomping indicators,
+    /// which the value of the scalar does not reflect. Each header is recorded as a
+    /// [`BlockScalarHeader`], along with the indentation of the content of the scalar, and can be
+    /// retrieved with [`Self::take_block_scalar_headers`]. Headers are not recorded by default.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Chomping, Parser};
+    /// let mut parser = Parser::new_from_str("a: |2-\n    b\n");
+    /// parser.set_record_block_scalar_headers(true);
+    /// for event in &mut parser {
+    ///     event.unwrap();
+    /// }
+    /// let headers = parser.take_block_scalar_headers();
+    /// assert_eq!(headers[0].indentation_indicator, Some(2));
+    /// assert_eq!(headers[0].chomping, Chomping::Strip);
+    /// assert_eq!(headers[0].indent, 2);
+    /// ```
+    pub fn set_record_block_scalar_headers(&mut self, record: bool) {
+        self.scanner.set_record_block_scalar_headers(record);
+    }
+
+    /// Return the headers of the block scalars recorded since the last call, in source order.
+    ///
+    /// As with [`Self::take_trivia`], the scanner reads ahead of the events returned by the
+    /// parser. See [`Self::set_record_block_scalar_headers`].
+    pub fn take_block_scalar_headers(&mut self) -> Vec<BlockScalarHeader> {
+        self.scanner.take_block_scalar_headers()
+    }
+
+    /// Treat any `:` in a flow context as a value indicator, as JSON does.
+    ///
+    /// YAML requires a space after the `:` of a plain key. `{"a":1}` is valid YAML, but `{a:1}` is
+    /// a mapping with a single `a:1` key and a null value. With JSON mode enabled, `{a:1}` maps `a`
+    /// to `1`, and `{1:2}` maps `1` to `2`. In exchange, plain scalars within flow collections can
+    /// no longer contain a `:`. Block contexts are not affected.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("{1:2}");
+    /// parser.set_json_mode(true);
+    /// let scalars: Vec<_> = parser
+    ///     .filter_map(|ev| match ev.unwrap().0 {
+    ///         Event::Scalar(value, ..) => Some(value),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(scalars, ["1", "2"]);
+    /// ```
+    pub fn set_json_mode(&mut self, json_mode: bool) {
+        self.scanner.set_json_mode(json_mode);
+    }
+
+    /// Choose how the line breaks of multi-line plain scalars are interpreted.
+    ///
+    /// The YAML specification folds a single line break in a plain scalar into a space. Some
+    /// configuration formats instead write long texts as multi-line plain scalars whose line
+    /// breaks are meant to be kept. [`FoldMode::PreserveNewlines`] keeps them as `\n`, which is not
+    /// compliant with the specification. The default, [`FoldMode::SpecFold`], is.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, FoldMode, Parser};
+    /// let mut parser = Parser::new_from_str("text: first\n  second\n");
+    /// parser.set_plain_scalar_fold(FoldMode::PreserveNewlines);
+    /// let scalars: Vec<_> = parser
+    ///     .filter_map(|ev| match ev.unwrap().0 {
+    ///         Event::Scalar(value, ..) => Some(value),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(scalars, ["text", "first\nsecond"]);
+    /// ```
+    pub fn set_plain_scalar_fold(&mut self, fold: FoldMode) {
+        self.scanner.set_plain_scalar_fold(fold);
+    }
+
+    /// Accept control characters that YAML forbids, for lenient parsing.
+    ///
+    /// YAML streams may only contain characters of the `c-printable` production: most C0 and C1
+    /// control characters (e.g.: `\x07`) are rejected by default, with an error pointing at the
+    /// offending character. This applies to scalars, comments, anchors, tags and directives.
+    /// Escape sequences in double-quoted scalars (e.g.: `"\a"`) are always allowed.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("bell: \x07");
+    /// parser.set_allow_control_chars(true);
+    /// let scalars: Vec<_> = parser
+    ///     .filter_map(|ev| match ev.unwrap().0 {
+    ///         Event::Scalar(value, ..) => Some(value),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(scalars, ["bell", "\x07"]);
+    /// ```
+    pub fn set_allow_control_chars(&mut self, allow: bool) {
+        self.scanner.set_allow_control_chars(allow);
+    }
+
+    /// Reject every tab in the input, for strict linting.
+    ///
+    /// YAML only forbids tabs in indentation; elsewhere (separation whitespace, comments,
+    /// directives, quoted and block scalars) they are valid. Style guides often forbid them
+    /// altogether. When enabled, the first tab found, including one spelled as `\<TAB>` in a
+    /// double-quoted scalar, is reported as an error pointing at it. The `\t` escape sequence is
+    /// still accepted.
+    ///
+    /// Like other scanner errors, this error ends the parse and is not collected as a diagnostic.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("key:\tvalue");
+    /// parser.set_error_on_tab_anywhere(true);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(err.info(), "found a tab, but tabs are forbidden");
+    /// assert_eq!(err.marker().col(), 4);
+    /// ```
+    pub fn set_error_on_tab_anywhere(&mut self, error: bool) {
+        self.scanner.set_error_on_tab_anywhere(error);
+    }
+
+    /// Resolve local tags (the primary `!` handle) against the base URI `base`.
+    ///
+    /// By default, a local tag such as `!foo` is reported with a `"!"` handle, as the meaning of
+    /// local tags is application-specific. With a base, the handle is set to `base`, so that
+    /// `handle + suffix` is an absolute URI. As with any other handle, a `%TAG !` directive takes
+    /// precedence over the base. Secondary (`!!`) and named (`!e!`) handles, verbatim tags and the
+    /// non-specific `!` tag are unaffected.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("!foo bar");
+    /// parser.set_tag_base("tag:example.com,2000:");
+    /// let tag = parser.find_map(|ev| ev.unwrap().0.tag().cloned()).unwrap();
+    /// assert_eq!(tag.handle, "tag:example.com,2000:");
+    /// assert_eq!(tag.suffix, "foo");
+    /// ```
+    pub fn set_tag_base(&mut self, base: &str) {
+        self.tag_base = Some(base.to_owned());
+    }
+
+    /// Accept empty entries in flow sequences as null scalars, for lenient parsing.
+    ///
+    /// YAML does not allow empty entries between the commas of a flow collection (e.g.: `[1, ,
+    /// 3]`), which are rejected by default with an error pointing at the comma following the empty
+    /// entry. With this set, an empty entry of a flow sequence is instead an empty plain scalar,
+    /// which is null, as in some lenient formats. Empty entries of flow mappings are always
+    /// rejected. A single trailing comma (e.g.: `[1, 2,]`) is valid YAML and always allowed.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("[1, , 3]");
+    /// parser.set_allow_empty_flow_entries(true);
+    /// let scalars: Vec<_> = parser
+    ///     .filter_map(|ev| match ev.unwrap().0 {
+    ///         Event::Scalar(value, ..) => Some(value),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(scalars, ["1", "~", "3"]);
+    /// ```
+    pub fn set_allow_empty_flow_entries(&mut self, allow: bool) {
+        self.flags.set(ParserFlags::ALLOW_EMPTY_FLOW_ENTRIES, allow);
+    }
+
+    /// Treat `chars` as whitespace separating tokens, like spaces.
+    ///
+    /// Documents copied from web pages or word processors often contain non-breaking spaces
+    /// (`\u{A0}`) or other Unicode spaces where spaces were meant. YAML only separates tokens
+    /// with spaces, tabs and line breaks: those characters are otherwise part of the scalars
+    /// they are next to (e.g.: `a:\u{A0}b` is a single scalar). This is meant for tolerant
+    /// parsing of such documents.
+    ///
+    /// The given characters may appear wherever a space separates tokens, and are skipped like
+    /// spaces at the end of plain scalars. They are never indentation: at the start of a line,
+    /// they are content. Quoted and block scalars keep them as written. By default, only spaces
+    /// and tabs are whitespace, as the YAML specification requires.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("[a,\u{A0}b\u{A0}]");
+    /// parser.set_extra_whitespace(&['\u{A0}']);
+    /// let scalars: Vec<_> = parser
+    ///     .filter_map(|ev| match ev.unwrap().0 {
+    ///         Event::Scalar(value, ..) => Some(value),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(scalars, ["a", "b"]);
+    /// ```
+    pub fn set_extra_whitespace(&mut self, chars: &[char]) {
+        self.scanner.set_extra_whitespace(chars);
+    }
+
+    /// Error if a line contains more than `max` characters.
+    ///
+    /// This protects against pathological inputs that fit on a single line (e.g.: minified
+    /// machine-generated documents), which indentation-based limits do not catch. Line breaks are
+    /// not counted. Scalars are checked after each character, and the error points at the first
+    /// character past the limit. Other tokens, comments and whitespace are checked between tokens,
+    /// and the error points at where the parser stopped.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: [b, c, d, e, f]\n");
+    /// parser.set_max_line_length(10);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(err.info(), "line exceeds the maximum line length");
+    /// ```
+    pub fn set_max_line_length(&mut self, max: usize) {
+        self.scanner.set_max_line_length(max);
+    }
+
+    /// Error once more than `max` bytes of the input have been consumed.
+    ///
+    /// This is a coarse safety cap for streaming inputs: it bounds the total work of the parser
+    /// whatever the structure of the input, independently of the other limits. Scalars are checked
+    /// after each character, and the error points at the first character past the limit, which
+    /// may be in the middle of the scalar. Other tokens, comments and whitespace are checked
+    /// between tokens, and the error points at where the parser stopped.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: 1\nb: 2\nc: 3\n");
+    /// parser.set_max_total_bytes(8);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(err.info(), "input exceeds the maximum total size");
+    /// ```
+    pub fn set_max_total_bytes(&mut self, max: usize) {
+        self.scanner.set_max_total_bytes(max);
+    }
+
+    /// Error if more than `max` flow collections are nested.
+    ///
+    /// Flow collections can be nested deeply on a single line (e.g.: `[[[[a]]]]`), at little
+    /// cost for whoever crafts the input but with a stack of state to keep for the parser. Block
+    /// collections are not affected. Regardless of this setting, flow collections may never be
+    /// nested more than 255 levels deep.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: [b, {c: [d]}]\n");
+    /// parser.set_max_flow_depth(2);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(err.info(), "flow collections exceed the maximum nesting depth");
+    /// assert_eq!(err.marker().index(), 11);
+    /// ```
+    pub fn set_max_flow_depth(&mut self, max: usize) {
+        self.scanner.set_max_flow_depth(max);
+    }
+
+    /// Error if more than `max` possible simple keys are pending at once.
+    ///
+    /// A simple key is a key written without `?` (e.g.: `a` in `a: b`). Until the scanner finds
+    /// the `:` that follows it, or knows there is none, it cannot tell whether a node is a key and
+    /// keeps the tokens it scanned since. Each flow collection may have one such possible key
+    /// pending, its parent another, and so on: nested flow collections which all start a key
+    /// (e.g.: `[[[[a]: b]: c]: d]`) make the scanner hold on to more state. There is at most one
+    /// possible simple key per flow level, plus one outside of flow collections. By default,
+    /// there is no limit besides the one on the nesting of flow collections (see
+    /// [`Self::set_max_flow_depth`]).
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("[[[a]: b]: c]");
+    /// parser.set_max_simple_keys(3);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(err.info(), "possible simple keys exceed the maximum");
+    /// assert_eq!(err.marker().index(), 3);
+    /// ```
+    pub fn set_max_simple_keys(&mut self, max: usize) {
+        self.scanner.set_max_simple_keys(max);
+    }
+
+    /// Error if a flow collection contains more than `max` items.
+    ///
+    /// Items are the entries of a flow sequence and the key/value pairs of a flow mapping. Items
+    /// of nested flow collections count towards the limit of their own collection only. Block
+    /// collections are not affected.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("[1, 2, [3, 4], 5]");
+    /// parser.set_max_flow_items(3);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(err.marker().index(), 15);
+    /// ```
+    pub fn set_max_flow_items(&mut self, max: usize) {
+        self.max_flow_items = Some(max);
+    }
+
+    /// Error if the root node of a document is not of the given kind.
+    ///
+    /// This is meant for consumers which know the shape of the documents they accept (e.g.: a
+    /// configuration file must be a mapping), to reject others right after their start, before
+    /// building anything from them. The error points at the root node.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, RootKind};
+    /// let mut parser = Parser::new_from_str("just a scalar");
+    /// parser.expect_root(RootKind::Mapping);
+    /// let err = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(
+    ///     err.info(),
+    ///     "expected a mapping at the root of the document, found a scalar"
+    /// );
+    /// ```
+    pub fn expect_root(&mut self, kind: RootKind) {
+        self.expected_root = Some(kind);
+    }
+
+    /// Parse only a front matter block, as found at the top of Markdown files.
+    ///
+    /// The input must start with a `---` line. A single document is parsed, up to the closing
+    /// `---` or `...` line. The stream then ends and the rest of the input is never read. The
+    /// [`Event::StreamEnd`] is located right after the closing fence, so that the rest of the
+    /// input can be sliced from there. Leading comments (e.g.: a `#!` shebang line) are allowed
+    /// before the opening `---`.
+    ///
+    /// If the input does not start with a `---` line, it has no front matter: the stream ends
+    /// right away, at the start of the input, and contains no document. A front matter without a
+    /// closing fence is an error.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let input = "---\ntitle: Hello\n---\n# Hello: world [\n";
+    /// let mut parser = Parser::new_from_str(input);
+    /// parser.set_frontmatter_mode(true);
+    /// let (events, spans): (Vec<_>, Vec<_>) = parser.map(Result::unwrap).unzip();
+    /// assert_eq!(events.len(), 8);
+    /// let body_start = spans.last().unwrap().start.index();
+    /// assert_eq!(&input[body_start..], "\n# Hello: world [\n");
+    /// ```
+    pub fn set_frontmatter_mode(&mut self, frontmatter_mode: bool) {
+        self.flags
+            .set(ParserFlags::FRONTMATTER_MODE, frontmatter_mode);
+    }
+
+    /// Set the schema used by [`Self::resolve_bool_or_null`].
+    ///
+    /// This does not change the events the parser emits. It lets the loader built on top of the
+    /// parser choose the schema once and resolve ambiguous scalars (e.g.: `on`, `yes`)
+    /// consistently. The default is [`SchemaVersion::Core1_2`].
+    pub fn set_schema(&mut self, schema: SchemaVersion) {
+        self.schema = schema;
+    }
+
+    /// Return the schema set with [`Self::set_schema`].
+    #[must_use]
+    pub fn schema(&self) -> SchemaVersion {
+        self.schema
+    }
+
+    /// Resolve a scalar event as a boolean or null, according to the parser's schema.
+    ///
+    /// Only untagged plain scalars are resolved. Return `None` for other events, for quoted, block
+    /// or tagged scalars, and for plain scalars that are neither a boolean nor null.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, ResolvedScalar, SchemaVersion};
+    /// let mut parser = Parser::new_from_str("[yes, 'yes', true]");
+    /// parser.set_schema(SchemaVersion::Yaml1_1);
+    /// let mut resolved = vec![];
+    /// while let Some(ev) = parser.next_event() {
+    ///     let (event, _) = ev.unwrap();
+    ///     if event.is_scalar() {
+    ///         resolved.push(parser.resolve_bool_or_null(&event));
+    ///     }
+    /// }
+    /// assert_eq!(
+    ///     resolved,
+    ///     [Some(ResolvedScalar::Bool(true)), None, Some(ResolvedScalar::Bool(true))]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn resolve_bool_or_null(&self, event: &Event) -> Option<ResolvedScalar> {
+        match event {
+            Event::Scalar(value, TScalarStyle::Plain, _, None) => {
+                self.schema.resolve_bool_or_null(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolve the type of a scalar event, according to the parser's schema.
+    ///
+    /// Only untagged plain scalars are resolved. Return `None` for other events and for quoted,
+    /// block or tagged scalars, which are strings unless their tag says otherwise. See
+    /// [`SchemaVersion::resolve_type`].
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, ScalarType};
+    /// let mut parser = Parser::new_from_str("[1.0, '1.0', abc]");
+    /// let mut types = vec![];
+    /// while let Some(ev) = parser.next_event() {
+    ///     let (event, _) = ev.unwrap();
+    ///     if event.is_scalar() {
+    ///         types.push(parser.resolve_type(&event));
+    ///     }
+    /// }
+    /// assert_eq!(types, [Some(ScalarType::Float), None, Some(ScalarType::Str)]);
+    /// ```
+    #[must_use]
+    pub fn resolve_type(&self, event: &Event) -> Option<ScalarType> {
+        match event {
+            Event::Scalar(value, TScalarStyle::Plain, _, None) => {
+                Some(self.schema.resolve_type(value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse the input as if its first line started at column `indent`.
+    ///
+    /// This is intended for YAML fragments that are spliced into an indented context, as
+    /// templating tools do. Such fragments have their first line inserted after existing
+    /// indentation, while the following lines carry that indentation themselves:
+    ///
+    /// ```yaml
+    /// a: 1
+    ///     b: 2
+    /// ```
+    ///
+    /// With a base indent of 4, `a` is considered to be at column 4, like `b`, and both are keys
+    /// of the same mapping. Without it, the fragment is rejected.
+    ///
+    /// Only the first line is affected: columns of [`Marker`]s on the first line are offset by
+    /// `indent`, while the following lines keep their actual columns.
+    ///
+    /// This must be called before any event is pulled from the parser and has no effect otherwise.
+    pub fn set_base_indent(&mut self, indent: usize) {
+        self.scanner.set_base_indent(indent);
+    }
+
+    /// Return what ended the last document the parser emitted an [`Event::DocumentEnd`] for.
+    ///
+    /// This tells whether a document was ended by an explicit `...` marker, which is needed to
+    /// reproduce it (e.g.: in a formatter), or whether another document follows it. `None` is
+    /// returned until the first document ends.
+    ///
+    /// ```
+    /// # use saphyr_parser::{DocumentEndCause, Event, Parser};
+    /// let mut parser = Parser::new_from_str("a\n...\nb\n---\nc\n");
+    /// let mut causes = vec![];
+    /// while let Some(event) = parser.next_event() {
+    ///     if event?.0 == Event::DocumentEnd {
+    ///         causes.push(parser.document_end_cause().unwrap());
+    ///     }
+    /// }
+    /// assert_eq!(
+    ///     causes,
+    ///     [
+    ///         DocumentEndCause::ExplicitDots,
+    ///         DocumentEndCause::NextDocumentMarker,
+    ///         DocumentEndCause::Eof,
+    ///     ]
+    /// );
+    /// # Ok::<(), saphyr_parser::ScanError>(())
+    /// ```
+    pub fn document_end_cause(&self) -> Option<DocumentEndCause> {
+        self.document_end_cause
+    }
+
+    /// Parse the input as what follows a document of a stream, which ended at `start`.
+    ///
+    /// `start` must be at the start of a line, and `next_anchor_id` is the
+    /// [ID of the next anchor](Self::next_anchor_id) of the parser which parsed that document.
+    /// Events then have the positions and anchor IDs they have when the whole stream is parsed at
+    /// once. This has no effect if parsing has already started.
+    ///
+    /// What follows the end of a document is parsed as the start of a stream, except for its
+    /// [`Event::StreamStart`].
+    #[cfg(feature = "async")]
+    pub(crate) fn resume_after_document(&mut self, start: Marker, next_anchor_id: usize) {
+        if self.state == State::StreamStart {
+            self.scanner.set_start_mark(start);
+            self.anchor_id_count = next_anchor_id;
+        }
+    }
+
+    /// Return the ID the next anchor of the stream will be given.
+    #[cfg(feature = "async")]
+    pub(crate) fn next_anchor_id(&self) -> usize {
+        self.anchor_id_count
+    }
+
+    /// Return the version given by the `%YAML` directive of the current document, as `(major,
+    /// minor)`.
+    ///
+    /// This tells consumers how the document expects to be interpreted (e.g.: whether `yes` is a
+    /// boolean, as in YAML 1.1), which the parser does not act upon. The version is set when the
+    /// [`Event::DocumentStart`] of a document is returned and kept until the next one. `None` is
+    /// returned for documents without a `%YAML` directive, which are implicitly YAML 1.2.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("%YAML 1.1\n--- yes\n...\n--- no\n");
+    /// let mut versions = vec![];
+    /// while let Some(event) = parser.next_event() {
+    ///     if let Event::DocumentStart(_) = event?.0 {
+    ///         versions.push(parser.document_version());
+    ///     }
+    /// }
+    /// assert_eq!(versions, [Some((1, 1)), None]);
+    /// # Ok::<(), saphyr_parser::ScanError>(())
+    /// ```
+    #[must_use]
+    pub fn document_version(&self) -> Option<(u32, u32)> {
+        self.document_version
+    }
+
+    /// Collect [`ParserMetrics`] about what is parsed from now on, retrieved with
+    /// [`Self::metrics`].
+    ///
+    /// Metrics are not collected by default, in which case collecting them costs nothing.
+    /// Disabling them discards those collected so far.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: [&c b, *c]\n");
+    /// parser.set_collect_metrics(true);
+    /// for event in &mut parser {
+    ///     event.unwrap();
+    /// }
+    /// let metrics = parser.metrics().unwrap();
+    /// assert_eq!(metrics.events(), 11);
+    /// assert_eq!(metrics.max_depth(), 2);
+    /// assert_eq!(metrics.aliases(), 1);
+    /// assert_eq!(metrics.scalar_bytes(), 2);
+    /// ```
+    pub fn set_collect_metrics(&mut self, collect: bool) {
+        if !collect {
+            self.metrics = None;
+        } else if self.metrics.is_none() {
+            self.metrics = Some(ParserMetrics::default());
+        }
+    }
+
+    /// Return the metrics collected so far, if enabled with [`Self::set_collect_metrics`].
+    ///
+    /// Metrics account for the events the parser has emitted, including one that was
+    /// [peeked](Self::peek) at.
+    #[must_use]
+    pub fn metrics(&self) -> Option<ParserMetrics> {
+        self.metrics
+    }
+
+    /// Record the indentation of the events parsed from now on, retrieved with
+    /// [`Self::indentation`].
+    ///
+    /// Indentation is not recorded by default. It must be enabled before the first event is
+    /// parsed.
+    pub fn set_record_indentation(&mut self, record: bool) {
+        if !record {
+            self.collection_indents = None;
+            self.details.indentation = None;
+            self.peeked_details.indentation = None;
+        } else if self.collection_indents.is_none() {
+            self.collection_indents = Some(Vec::new());
+        }
+        self.update_details_requested();
+    }
+
+    /// Return the indentation of the last event returned, if enabled with
+    /// [`Self::set_record_indentation`].
+    ///
+    /// The indentation is the column of the block structure the event belongs to:
+    ///   - For the start of a block mapping, the column of its keys.
+    ///   - For the start of a block sequence, the column of its `-` indicators. The span of the
+    ///     start of a sequence that is not indented relative to its parent mapping (as in
+    ///     `a:\n- b`) is after the first `-`, while its indentation is that of the `-`.
+    ///   - For a scalar or an alias in a block collection, the indentation of that collection.
+    ///
+    /// Other events, scalars and aliases at the root of a document and everything inside flow
+    /// collections have no indentation. The span of a node may start past its indentation, for
+    /// instance after its properties.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a:\n- b\n- c: d\n");
+    /// parser.set_record_indentation(true);
+    /// let mut indents = vec![];
+    /// while let Some(event) = parser.next_event() {
+    ///     event.unwrap();
+    ///     indents.push(parser.indentation());
+    /// }
+    /// // Stream, document, `{`, `a`, `[`, `b`, `{`, `c`, `d`, `}`, `]`, `}`, document, stream.
+    /// assert_eq!(
+    ///     indents,
+    ///     [None, None, Some(0), Some(0), Some(0), Some(0), Some(2), Some(2), Some(2)]
+    ///         .into_iter()
+    ///         .chain([None; 5])
+    ///         .collect::<Vec<_>>()
+    /// );
+    /// ```
+    #[must_use]
+    pub fn indentation(&self) -> Option<usize> {
+        self.details.indentation
+    }
+
+    /// Record the spans of the anchors and tags of nodes, retrieved with [`Self::anchor_span`]
+    /// and [`Self::tag_span`].
+    ///
+    /// The span of a node covers its content only, not its properties (e.g.: `foo` in
+    /// `&a !!str foo`). Property spans are not recorded by default.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let yaml = "&a !!str foo";
+    /// let mut parser = Parser::new_from_str(yaml);
+    /// parser.set_record_property_spans(true);
+    /// while let Some(event) = parser.next_event() {
+    ///     let (event, span) = event.unwrap();
+    ///     if event.is_scalar() {
+    ///         let anchor = parser.anchor_span().unwrap();
+    ///         let tag = parser.tag_span().unwrap();
+    ///         assert_eq!(&yaml[anchor.start.index()..anchor.end.index()], "&a");
+    ///         assert_eq!(&yaml[tag.start.index()..tag.end.index()], "!!str");
+    ///         assert_eq!(&yaml[span.start.index()..span.end.index()], "foo");
+    ///     }
+    /// }
+    /// ```
+    pub fn set_record_property_spans(&mut self, record: bool) {
+        self.flags.set(ParserFlags::RECORD_PROPERTY_SPANS, record);
+        if !record {
+            self.property_spans = (None, None);
+            self.details.anchor_span = None;
+            self.details.tag_span = None;
+            self.peeked_details.anchor_span = None;
+            self.peeked_details.tag_span = None;
+        }
+        self.update_details_requested();
+    }
+
+    /// Return the span of the anchor of the last event returned, if it is a node with an anchor
+    /// and property spans are recorded (see [`Self::set_record_property_spans`]).
+    #[must_use]
+    pub fn anchor_span(&self) -> Option<Span> {
+        self.details.anchor_span
+    }
+
+    /// Return the span of the tag of the last event returned, if it is a node with a tag and
+    /// property spans are recorded (see [`Self::set_record_property_spans`]).
+    #[must_use]
+    pub fn tag_span(&self) -> Option<Span> {
+        self.details.tag_span
+    }
+
+    /// Parse the plain scalars that are numbers, retrieved with [`Self::number`].
+    ///
+    /// This is meant for large numeric datasets. Paired with [`Self::next_event_into`], which does
+    /// not allocate a `String` per scalar, numbers are read without allocating. Scalars are
+    /// resolved according to the parser's schema (see [`SchemaVersion::parse_number`]). Only
+    /// untagged plain scalars are parsed. Numbers are not parsed by default.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, ScalarValue};
+    /// let mut parser = Parser::new_from_str("[1, 2.5, '3', x]");
+    /// parser.set_parse_numbers(true);
+    /// let mut buf = String::new();
+    /// let mut numbers = vec![];
+    /// while let Some(ev) = parser.next_event_into(&mut buf) {
+    ///     ev.unwrap();
+    ///     numbers.extend(parser.number());
+    /// }
+    /// assert_eq!(numbers, [ScalarValue::Int(1), ScalarValue::Float(2.5)]);
+    /// ```
+    pub fn set_parse_numbers(&mut self, parse: bool) {
+        self.flags.set(ParserFlags::PARSE_NUMBERS, parse);
+        if !parse {
+            self.details.number = None;
+            self.peeked_details.number = None;
+        }
+        self.update_details_requested();
+    }
+
+    /// Return the value of the last event returned, if it is a number and numbers are parsed
+    /// (see [`Self::set_parse_numbers`]).
+    #[must_use]
+    pub fn number(&self) -> Option<ScalarValue> {
+        self.details.number
+    }
+
+    /// Intern the values of scalars, retrieved with [`Self::interned_scalar`].
+    ///
+    /// This is meant for documents repeating the same values many times (e.g.: the keys of a
+    /// large configuration file). Each distinct value is allocated once and shared by all the
+    /// scalars with this value. Paired with [`Self::next_event_into`], which does not allocate a
+    /// `String` per scalar, a repeated value is read without allocating. This costs a hash lookup
+    /// per scalar. Interned values are kept until interning is disabled or the parser is dropped,
+    /// up to the limit set with [`Self::set_max_interned_bytes`]. Scalars are not interned by
+    /// default.
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("[{name: a}, {name: b}]");
+    /// parser.set_intern_scalars(true);
+    /// let mut buf = String::new();
+    /// let mut values = vec![];
+    /// while let Some(ev) = parser.next_event_into(&mut buf) {
+    ///     ev.unwrap();
+    ///     values.extend(parser.interned_scalar());
+    /// }
+    /// assert_eq!(values.len(), 4);
+    /// assert!(Arc::ptr_eq(&values[0], &values[2]));
+    /// ```
+    pub fn set_intern_scalars(&mut self, intern: bool) {
+        if !intern {
+            self.interned_scalars = None;
+            self.details.interned = None;
+            self.peeked_details.interned = None;
+        } else if self.interned_scalars.is_none() {
+            self.interned_scalars = Some(InternedScalars::default());
+        }
+        self.update_details_requested();
+    }
+
+    /// Stop interning new values once the interned values total more than `max` bytes.
+    ///
+    /// Values interned until then are still shared, while the scalars with a new value each get a
+    /// value of their own, as if they were not interned. This bounds the memory kept by
+    /// [`Self::set_intern_scalars`] on inputs with many distinct values. The limit defaults to
+    /// 1 MiB.
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("[ab, cd, ab, cd]");
+    /// parser.set_intern_scalars(true);
+    /// parser.set_max_interned_bytes(2);
+    /// let mut buf = String::new();
+    /// let mut values = vec![];
+    /// while let Some(ev) = parser.next_event_into(&mut buf) {
+    ///     ev.unwrap();
+    ///     values.extend(parser.interned_scalar());
+    /// }
+    /// assert!(Arc::ptr_eq(&values[0], &values[2]));
+    /// assert!(!Arc::ptr_eq(&values[1], &values[3]));
+    /// ```
+    pub fn set_max_interned_bytes(&mut self, max: usize) {
+        self.max_interned_bytes = max;
+    }
+
+    /// Return the interned value of the last event returned, if it is a scalar and scalars are
+    /// interned (see [`Self::set_intern_scalars`]).
+    #[must_use]
+    pub fn interned_scalar(&self) -> Option<Arc<str>> {
+        self.details.interned.clone()
+    }
+
+    /// Call `callback` on errors the parser can skip, letting it choose whether to skip them.
+    ///
+    /// By default, all errors are returned. Tools parsing YAML on a best-effort basis may instead
+    /// tolerate constructs that are invalid in a way the parser knows how to work around. The
+    /// following errors can be skipped, with the given effect:
+    ///   - A second `%YAML` directive in a document is ignored.
+    ///   - A second `%TAG` directive for the same handle in a document is ignored.
+    ///   - A tag whose named handle (e.g.: `!e!`) was not declared is kept as is, with the handle
+    ///     as its [`Tag::handle`].
+    ///   - An alias to an unknown anchor is replaced by a null scalar (`~`).
+    ///
+    /// Other errors are returned without calling `callback`. `callback` must be [`Send`] and
+    /// [`Sync`] so that the parser remains so.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser, UnsupportedAction};
+    /// let mut parser = Parser::new_from_str("[*unknown, a]");
+    /// parser.set_on_unsupported(|_| UnsupportedAction::Skip);
+    /// let scalars: Vec<_> = parser
+    ///     .filter_map(|ev| match ev.unwrap().0 {
+    ///         Event::Scalar(value, ..) => Some(value),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(scalars, ["~", "a"]);
+    /// ```
+    pub fn set_on_unsupported<F: FnMut(&ScanError) -> UnsupportedAction + Send + Sync + 'static>(
+        &mut self,
+        callback: F,
+    ) {
+        self.on_unsupported = Some(OnUnsupported(Box::new(callback)));
+    }
+
+    /// Return `error` unless the callback set with [`Self::set_on_unsupported`] skips it.
+    fn unsupported(&mut self, error: ScanError) -> Result<(), ScanError> {
+        let skip = self
+            .on_unsupported
+            .as_mut()
+            .is_some_and(|callback| (callback.0)(&error) == UnsupportedAction::Skip);
+        if skip {
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Return the number of bytes of the input the scanner has yet to read, if known.
+    ///
+    /// This is a hint meant for pre-sizing collections of events or values: it is exact for
+    /// `&str` inputs and `None` for inputs reading from an iterator. The scanner reads ahead of
+    /// the events returned, so the bytes of the next events may have been read already.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: b\n");
+    /// assert_eq!(parser.remaining_hint(), Some(5));
+    /// for event in &mut parser {
+    ///     event.unwrap();
+    /// }
+    /// assert_eq!(parser.remaining_hint(), Some(0));
+    /// ```
+    #[must_use]
+    pub fn remaining_hint(&self) -> Option<usize> {
+        self.scanner.input().remaining_hint()
+    }
+
+    /// Get the input the parser reads from.
+    ///
+    /// This allows inspecting the state of the input during or after parsing (e.g.:
+    /// [`BufferedInput::fallback_lookahead_count`]).
+    ///
+    /// [`BufferedInput::fallback_lookahead_count`]: crate::BufferedInput::fallback_lookahead_count
+    pub fn input(&self) -> &T {
+        self.scanner.input()
     }
 
     /// Try to load the next event and return it, but do not consuming it from `self`.
@@ -305,7 +1942,10 @@ impl<T: Input> Parser<T> {
             if self.stream_end_emitted {
                 return None;
             }
-            match self.next_event_impl() {
+            let details = std::mem::take(&mut self.details);
+            let next = self.next_event_impl();
+            self.peeked_details = std::mem::replace(&mut self.details, details);
+            match next {
                 Ok(token) => self.current = Some(token),
                 Err(e) => return Some(Err(e)),
             }
@@ -313,8 +1953,40 @@ impl<T: Input> Parser<T> {
         }
     }
 
+    /// Return whether parsing is complete.
+    ///
+    /// This is the case once [`Event::StreamEnd`] has been returned by [`Self::next_event`] (or
+    /// the iterator), or once loading an event returned an error. A peeked [`Event::StreamEnd`]
+    /// has not been returned yet. This lets loops driving the parser by hand stop without
+    /// checking for [`Event::StreamEnd`] themselves.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: b");
+    /// let mut count = 0;
+    /// while !parser.is_finished() {
+    ///     parser.next_event().unwrap().unwrap();
+    ///     count += 1;
+    /// }
+    /// assert_eq!(count, 8);
+    /// ```
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.stream_end_emitted || self.errored
+    }
+
     /// Try to load the next event and return it, consuming it from `self`.
     ///
+    /// Every event returned before the first error is final: it is the event that would be
+    /// returned for the same content if the rest of the input were valid. Collections and
+    /// documents that are open when the error occurs are not closed. See [`parse_partial`] to
+    /// collect those events along with the error.
+    ///
+    /// The parser should not be relied upon after an error: errors found while scanning are
+    /// returned again, while other errors may be followed by further events. The [`Iterator`]
+    /// implementation of the parser does not have this issue: once it returns an error, it only
+    /// returns `None`.
+    ///
     /// # Errors
     /// Returns `ScanError` when loading the next event fails.
     pub fn next_event(&mut self) -> Option<ParseResult> {
@@ -329,6 +2001,115 @@ impl<T: Input> Parser<T> {
         Some(tok)
     }
 
+    /// Load the next event, moving the contents of scalars into `buf`.
+    ///
+    /// This is an opt-in alternative to [`Self::next_event`] for throughput-sensitive consumers,
+    /// which avoids allocating a new `String` for every scalar. Events are the same as those
+    /// returned by [`Self::next_event`], except that the value of [`Event::Scalar`] is moved into
+    /// `buf` and left empty in the event. Anything else in `buf` is discarded.
+    ///
+    /// The allocation of `buf` is handed to the scanner, which reuses it for the next scalar it
+    /// reads. Passing the same buffer to each call thus only allocates when a scalar is larger than
+    /// any previous one.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("[a, bb, ccc]");
+    /// let mut buf = String::new();
+    /// let mut scalars = vec![];
+    /// while let Some(ev) = parser.next_event_into(&mut buf) {
+    ///     if let (Event::Scalar(..), _) = ev.unwrap() {
+    ///         scalars.push(buf.clone());
+    ///     }
+    /// }
+    /// assert_eq!(scalars, ["a", "bb", "ccc"]);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading the next event fails.
+    pub fn next_event_into(&mut self, buf: &mut String) -> Option<ParseResult> {
+        self.scanner.recycle_scalar_buffer(std::mem::take(buf));
+        let mut next = self.next_event()?;
+        if let Ok((Event::Scalar(value, ..), _)) = &mut next {
+            std::mem::swap(buf, value);
+        }
+        Some(next)
+    }
+
+    /// Iterate over the events of the stream one document at a time.
+    ///
+    /// Events are parsed lazily, as with the [`Iterator`] implementation of [`Parser`]. See
+    /// [`DocumentStream`] for details.
+    pub fn into_document_events(self) -> DocumentStream<T> {
+        DocumentStream::new(self)
+    }
+
+    /// Iterate over the keys of the mappings of the stream, skipping their values.
+    ///
+    /// See [`MappingKeys`] for which keys are yielded.
+    pub fn into_mapping_keys(self) -> MappingKeys<T> {
+        MappingKeys::new(self)
+    }
+
+    /// Parse the next document of the input and return its events and the position it ends at.
+    ///
+    /// This is meant for inputs holding a sequence of documents which are received one at a time
+    /// (e.g.: YAML exchanged over a socket). Once a document is parsed, the rest of the input can
+    /// be parsed from the returned position, possibly with a new [`Parser`] once more input is
+    /// received. Calling this function again on the same parser parses the next document.
+    ///
+    /// If the input ends before the document does (e.g.: within a flow collection or a quoted
+    /// scalar), [`OneDocument::NeedsMoreInput`] is returned. Note that, unless it is ended with
+    /// `...`, a document followed by the end of the input is complete even though more lines
+    /// could continue it. Protocols sending documents in several chunks should end documents with
+    /// `...`.
+    ///
+    /// ```
+    /// # use saphyr_parser::{OneDocument, Parser};
+    /// let input = "a: 1\n...\nb: [2,";
+    /// let OneDocument::Document(events, end) = Parser::new_from_str(input).parse_one_document()?
+    /// else {
+    ///     panic!("expected a document");
+    /// };
+    /// assert_eq!(events.len(), 6);
+    /// assert_eq!(&input[end.index()..], "\nb: [2,");
+    ///
+    /// let rest = Parser::new_from_str(&input[end.index()..]).parse_one_document()?;
+    /// assert_eq!(rest, OneDocument::NeedsMoreInput);
+    /// # Ok::<(), saphyr_parser::ScanError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `ScanError` when the document is invalid, unless the error is caused by the end of
+    /// the input.
+    pub fn parse_one_document(&mut self) -> Result<OneDocument, ScanError> {
+        let mut events = vec![];
+        loop {
+            // Whether the document ends with `...`, which then belongs to the document.
+            let explicit_end = self.current.is_none()
+                && self.state == State::DocumentEnd
+                && matches!(self.peek_token(), Ok(Token(_, TokenType::DocumentEnd)));
+            let (event, span) = match self.next_event() {
+                None => return Ok(OneDocument::EndOfStream),
+                Some(Ok(event)) => event,
+                Some(Err(_)) if self.scanner.reached_end_of_input() => {
+                    return Ok(OneDocument::NeedsMoreInput);
+                }
+                Some(Err(e)) => return Err(e),
+            };
+            match event {
+                Event::StreamStart => {}
+                Event::StreamEnd => return Ok(OneDocument::EndOfStream),
+                Event::DocumentEnd => {
+                    events.push(event);
+                    let end = if explicit_end { span.end } else { span.start };
+                    return Ok(OneDocument::Document(events, end));
+                }
+                _ => events.push(event),
+            }
+        }
+    }
+
     /// Implementation function for [`Self::next_event`] without the `Option`.
     ///
     /// [`Self::next_event`] should conform to the expectations of an [`Iterator`] and return an
@@ -336,8 +2117,113 @@ impl<T: Input> Parser<T> {
     /// undesirable.
     fn next_event_impl(&mut self) -> ParseResult {
         match self.current.take() {
-            None => self.parse(),
-            Some(v) => Ok(v),
+            None => {
+                let next = self.parse();
+                self.errored |= next.is_err();
+                if let (Some(metrics), Ok((event, _))) = (&mut self.metrics, &next) {
+                    metrics.record(event);
+                }
+                match &next {
+                    Ok((event, span)) if self.flags.contains(ParserFlags::DETAILS_REQUESTED) => {
+                        self.details = self.event_details(event, *span);
+                    }
+                    _ => {}
+                }
+                next
+            }
+            Some(v) => {
+                self.details = std::mem::take(&mut self.peeked_details);
+                Ok(v)
+            }
+        }
+    }
+
+    /// Update [`ParserFlags::DETAILS_REQUESTED`] after an option recording [`EventDetails`]
+    /// changed.
+    fn update_details_requested(&mut self) {
+        let requested = self.collection_indents.is_some()
+            || self.flags.contains(ParserFlags::RECORD_PROPERTY_SPANS)
+            || self.flags.contains(ParserFlags::PARSE_NUMBERS)
+            || self.interned_scalars.is_some()
+            || self.scanner.has_block_scalar_sink();
+        self.flags.set(ParserFlags::DETAILS_REQUESTED, requested);
+    }
+
+    /// Compute what is recorded about `event`, which was just parsed.
+    fn event_details(&mut self, event: &Event, span: Span) -> EventDetails {
+        let (anchor_span, tag_span) = match event {
+            Event::Scalar(..) | Event::SequenceStart(..) | Event::MappingStart(..) => {
+                std::mem::take(&mut self.property_spans)
+            }
+            _ => (None, None),
+        };
+        let number = match event {
+            Event::Scalar(value, TScalarStyle::Plain, _, None)
+                if self.flags.contains(ParserFlags::PARSE_NUMBERS) =>
+            {
+                self.schema.parse_number(value)
+            }
+            _ => None,
+        };
+        let interned = match (event, &mut self.interned_scalars) {
+            (Event::Scalar(value, ..), Some(interned_scalars)) => {
+                Some(interned_scalars.intern(value, self.max_interned_bytes))
+            }
+            _ => None,
+        };
+        let streamed = match event {
+            Event::Scalar(value, style, ..) if style.is_block() && value.is_empty() => {
+                self.scanner.take_streamed_block_scalar(span.start)
+            }
+            _ => false,
+        };
+        EventDetails {
+            indentation: self.record_indentation(event, span),
+            anchor_span,
+            tag_span,
+            number,
+            interned,
+            streamed,
+        }
+    }
+
+    /// Record the span of the anchor (if `anchor`) or tag of the node being parsed, if enabled.
+    fn record_property_span(&mut self, anchor: bool, span: Span) {
+        if !self.flags.contains(ParserFlags::RECORD_PROPERTY_SPANS) {
+            return;
+        }
+        if anchor {
+            self.property_spans.0 = Some(span);
+        } else {
+            self.property_spans.1 = Some(span);
+        }
+    }
+
+    /// Update the indentation of the collections we are in with the event just parsed and return
+    /// its indentation, if indentation is recorded.
+    fn record_indentation(&mut self, event: &Event, span: Span) -> Option<usize> {
+        let indents = self.collection_indents.as_mut()?;
+        match event {
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                let indent = match self.state {
+                    State::BlockSequenceFirstEntry | State::BlockMappingFirstKey => {
+                        Some(span.start.col())
+                    }
+                    // The event has an empty span after the first `-`, which is still peeked.
+                    State::IndentlessSequenceEntry => {
+                        self.token.as_ref().map(|Token(span, _)| span.start.col())
+                    }
+                    _ => None,
+                };
+                indents.push(indent);
+                indent
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                indents.pop();
+                None
+            }
+            Event::Scalar(..) | Event::Alias(_) => indents.last().copied().flatten(),
+            _ => None,
         }
     }
 
@@ -367,14 +2253,38 @@ impl<T: Input> Parser<T> {
     }
 
     fn fetch_token(&mut self) -> Token {
-        self.token
+        let token = self
+            .token
             .take()
-            .expect("fetch_token needs to be preceded by peek_token")
+            .expect("fetch_token needs to be preceded by peek_token");
+        self.queue_indicator_event(&token);
+        token
     }
 
     /// Skip the next token from the scanner.
     fn skip(&mut self) {
-        self.token = None;
+        if let Some(token) = self.token.take() {
+            self.queue_indicator_event(&token);
+        }
+    }
+
+    /// Queue an [`Event::Indicator`] if `token` is an indicator and indicator events are enabled.
+    fn queue_indicator_event(&mut self, token: &Token) {
+        if !self.flags.contains(ParserFlags::INDICATOR_EVENTS) {
+            return;
+        }
+        let Token(span, tok) = token;
+        let indicator = match tok {
+            // Implicit keys have an empty span.
+            TokenType::Key if !span.is_empty() => '?',
+            TokenType::Value => ':',
+            TokenType::BlockEntry => '-',
+            _ => return,
+        };
+        let mut end = span.start;
+        end.advance(1);
+        self.pending_events
+            .push_back((Event::Indicator(indicator), Span::new(span.start, end)));
     }
     /// Pops the top-most state and make it the current state.
     fn pop_state(&mut self) {
@@ -386,11 +2296,51 @@ impl<T: Input> Parser<T> {
     }
 
     fn parse(&mut self) -> ParseResult {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Ok(event);
+        }
         if self.state == State::End {
             return Ok((Event::StreamEnd, Span::empty(self.scanner.mark())));
         }
-        let (ev, mark) = self.state_machine()?;
-        Ok((ev, mark))
+        let event = self.state_machine()?;
+        self.check_root(&event)?;
+        if self.pending_events.is_empty() {
+            Ok(event)
+        } else {
+            self.pending_events.push_back(event);
+            Ok(self.pending_events.pop_front().unwrap())
+        }
+    }
+
+    /// Return an error if `event` starts the root node of a document and it is not of the kind
+    /// given to [`Self::expect_root`].
+    fn check_root(&mut self, (event, span): &(Event, Span)) -> Result<(), ScanError> {
+        let Some(expected) = self.expected_root else {
+            return Ok(());
+        };
+        if let Event::DocumentStart(_) = event {
+            self.at_document_root = true;
+            return Ok(());
+        }
+        if !self.at_document_root {
+            return Ok(());
+        }
+        let Some(found) = RootKind::of(event) else {
+            return Ok(());
+        };
+        self.at_document_root = false;
+        if found == expected {
+            Ok(())
+        } else {
+            Err(ScanError::new(
+                span.start,
+                format!(
+                    "expected {} at the root of the document, found {}",
+                    expected.name(),
+                    found.name()
+                ),
+            ))
+        }
     }
 
     /// Load the YAML from the stream in `self`, pushing events into `recv`.
@@ -401,11 +2351,13 @@ impl<T: Input> Parser<T> {
     /// If `multi` is set to `true`, the parser will allow parsing of multiple YAML documents
     /// inside the stream.
     ///
-    /// Note that any [`EventReceiver`] is also a [`SpannedEventReceiver`], so implementing the
-    /// former is enough to call this function.
+    /// Note that any [`EventReceiver`] is also a [`SpannedEventReceiver`], which is also a
+    /// [`ControlFlowEventReceiver`], so implementing any of them is enough to call this function.
+    /// If `recv` returns [`ControlFlow::Break`], this function returns immediately. Remaining
+    /// events can still be pulled from the parser afterwards.
     /// # Errors
     /// Returns `ScanError` when loading fails.
-    pub fn load<R: SpannedEventReceiver>(
+    pub fn load<R: ControlFlowEventReceiver>(
         &mut self,
         recv: &mut R,
         multi: bool,
@@ -418,72 +2370,95 @@ impl<T: Input> Parser<T> {
                     "did not find expected <stream-start>",
                 ));
             }
-            recv.on_event(ev, span);
+            if recv.on_event(ev, span).is_break() {
+                return Ok(());
+            }
         }
 
         if self.scanner.stream_ended() {
             // XXX has parsed?
-            recv.on_event(Event::StreamEnd, Span::empty(self.scanner.mark()));
+            // This is the last event; there is nothing left to stop.
+            let _ = recv.on_event(Event::StreamEnd, Span::empty(self.scanner.mark()));
             return Ok(());
         }
         loop {
             let (ev, span) = self.next_event_impl()?;
             if ev == Event::StreamEnd {
-                recv.on_event(ev, span);
+                let _ = recv.on_event(ev, span);
                 return Ok(());
             }
-            // clear anchors before a new document
-            self.anchors.clear();
-            self.load_document(ev, span, recv)?;
-            if !multi {
+            if self.load_document(ev, span, recv)?.is_break() || !multi {
                 break;
             }
         }
         Ok(())
     }
 
-    fn load_document<R: SpannedEventReceiver>(
+    fn load_document<R: ControlFlowEventReceiver>(
         &mut self,
         first_ev: Event,
         span: Span,
         recv: &mut R,
-    ) -> Result<(), ScanError> {
+    ) -> Result<ControlFlow<()>, ScanError> {
         if !matches!(first_ev, Event::DocumentStart(_)) {
             return Err(ScanError::new_str(
                 span.start,
                 "did not find expected <document-start>",
             ));
         }
-        recv.on_event(first_ev, span);
+        if recv.on_event(first_ev, span).is_break() {
+            return Ok(ControlFlow::Break(()));
+        }
 
-        let (ev, span) = self.next_event_impl()?;
-        self.load_node(ev, span, recv)?;
+        let Some((ev, span)) = self.next_node_event(recv)? else {
+            return Ok(ControlFlow::Break(()));
+        };
+        if self.load_node(ev, span, recv)?.is_break() {
+            return Ok(ControlFlow::Break(()));
+        }
 
         // DOCUMENT-END is expected.
         let (ev, mark) = self.next_event_impl()?;
         assert_eq!(ev, Event::DocumentEnd);
-        recv.on_event(ev, mark);
+        Ok(recv.on_event(ev, mark))
+    }
 
-        Ok(())
+    /// Return the next event for [`Self::load`], passing any [`Event::Indicator`] to `recv`.
+    ///
+    /// Return `None` if `recv` asked to stop.
+    fn next_node_event<R: ControlFlowEventReceiver>(
+        &mut self,
+        recv: &mut R,
+    ) -> Result<Option<(Event, Span)>, ScanError> {
+        loop {
+            let (ev, span) = self.next_event_impl()?;
+            if !matches!(ev, Event::Indicator(_)) {
+                return Ok(Some((ev, span)));
+            }
+            if recv.on_event(ev, span).is_break() {
+                return Ok(None);
+            }
+        }
     }
 
-    fn load_node<R: SpannedEventReceiver>(
+    fn load_node<R: ControlFlowEventReceiver>(
         &mut self,
         first_ev: Event,
         span: Span,
         recv: &mut R,
-    ) -> Result<(), ScanError> {
+    ) -> Result<ControlFlow<()>, ScanError> {
         match first_ev {
-            Event::Alias(..) | Event::Scalar(..) => {
-                recv.on_event(first_ev, span);
-                Ok(())
-            }
+            Event::Alias(..) | Event::Scalar(..) => Ok(recv.on_event(first_ev, span)),
             Event::SequenceStart(..) => {
-                recv.on_event(first_ev, span);
+                if recv.on_event(first_ev, span).is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
                 self.load_sequence(recv)
             }
             Event::MappingStart(..) => {
-                recv.on_event(first_ev, span);
+                if recv.on_event(first_ev, span).is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
                 self.load_mapping(recv)
             }
             _ => {
@@ -493,37 +2468,57 @@ impl<T: Input> Parser<T> {
         }
     }
 
-    fn load_mapping<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> Result<(), ScanError> {
-        let (mut key_ev, mut key_mark) = self.next_event_impl()?;
+    fn load_mapping<R: ControlFlowEventReceiver>(
+        &mut self,
+        recv: &mut R,
+    ) -> Result<ControlFlow<()>, ScanError> {
+        let Some((mut key_ev, mut key_mark)) = self.next_node_event(recv)? else {
+            return Ok(ControlFlow::Break(()));
+        };
         while key_ev != Event::MappingEnd {
             // key
-            self.load_node(key_ev, key_mark, recv)?;
+            if self.load_node(key_ev, key_mark, recv)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
 
             // value
-            let (ev, mark) = self.next_event_impl()?;
-            self.load_node(ev, mark, recv)?;
+            let Some((ev, mark)) = self.next_node_event(recv)? else {
+                return Ok(ControlFlow::Break(()));
+            };
+            if self.load_node(ev, mark, recv)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
 
             // next event
-            let (ev, mark) = self.next_event_impl()?;
+            let Some((ev, mark)) = self.next_node_event(recv)? else {
+                return Ok(ControlFlow::Break(()));
+            };
             key_ev = ev;
             key_mark = mark;
         }
-        recv.on_event(key_ev, key_mark);
-        Ok(())
+        Ok(recv.on_event(key_ev, key_mark))
     }
 
-    fn load_sequence<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> Result<(), ScanError> {
-        let (mut ev, mut mark) = self.next_event_impl()?;
+    fn load_sequence<R: ControlFlowEventReceiver>(
+        &mut self,
+        recv: &mut R,
+    ) -> Result<ControlFlow<()>, ScanError> {
+        let Some((mut ev, mut mark)) = self.next_node_event(recv)? else {
+            return Ok(ControlFlow::Break(()));
+        };
         while ev != Event::SequenceEnd {
-            self.load_node(ev, mark, recv)?;
+            if self.load_node(ev, mark, recv)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
 
             // next event
-            let (next_ev, next_mark) = self.next_event_impl()?;
+            let Some((next_ev, next_mark)) = self.next_node_event(recv)? else {
+                return Ok(ControlFlow::Break(()));
+            };
             ev = next_ev;
             mark = next_mark;
         }
-        recv.on_event(ev, mark);
-        Ok(())
+        Ok(recv.on_event(ev, mark))
     }
 
     fn state_machine(&mut self) -> ParseResult {
@@ -583,6 +2578,14 @@ impl<T: Input> Parser<T> {
     }
 
     fn document_start(&mut self, implicit: bool) -> ParseResult {
+        if self.flags.contains(ParserFlags::FRONTMATTER_MODE) {
+            let mark = self.scanner.mark();
+            if !self.scanner.next_token_is_document_start()? {
+                // No front matter. Do not scan the rest of the input, which may not be YAML.
+                self.state = State::End;
+                return Ok((Event::StreamEnd, Span::empty(mark)));
+            }
+        }
         while let TokenType::DocumentEnd = self.peek_token()?.1 {
             self.skip();
         }
@@ -617,34 +2620,38 @@ impl<T: Input> Parser<T> {
 
     fn parser_process_directives(&mut self) -> Result<(), ScanError> {
         let mut version_directive_received = false;
+        let mut tags = HashMap::new();
+        self.document_version = None;
         loop {
-            let mut tags = HashMap::new();
             match self.peek_token()? {
-                Token(span, TokenType::VersionDirective(_, _)) => {
+                Token(span, TokenType::VersionDirective(major, minor)) => {
                     // XXX parsing with warning according to spec
                     //if major != 1 || minor > 2 {
                     //    return Err(ScanError::new_str(tok.0,
                     //        "found incompatible YAML document"));
                     //}
                     if version_directive_received {
-                        return Err(ScanError::new_str(
-                            span.start,
-                            "duplicate version directive",
-                        ));
+                        let error = ScanError::new_str(span.start, "duplicate version directive");
+                        self.unsupported(error)?;
+                    } else {
+                        self.document_version = Some((*major, *minor));
                     }
                     version_directive_received = true;
                 }
                 Token(mark, TokenType::TagDirective(handle, prefix)) => {
                     if tags.contains_key(handle) {
-                        return Err(ScanError::new_str(mark.start, "the TAG directive must only be given at most once per handle in the same document"));
+                        let error = ScanError::new_str(mark.start, "the TAG directive must only be given at most once per handle in the same document");
+                        self.unsupported(error)?;
+                    } else {
+                        tags.insert(handle.clone(), prefix.clone());
                     }
-                    tags.insert(handle.to_string(), prefix.to_string());
                 }
                 _ => break,
             }
-            self.tags = tags;
             self.skip();
         }
+        // Tags kept from previous documents (see `keep_tags`) are overridden by new directives.
+        self.tags.extend(tags);
         Ok(())
     }
 
@@ -693,36 +2700,81 @@ impl<T: Input> Parser<T> {
             Token(span, _) => span,
         };
 
-        if !self.keep_tags {
+        if !self.flags.contains(ParserFlags::KEEP_TAGS) {
             self.tags.clear();
         }
-        if explicit_end {
-            self.state = State::ImplicitDocumentStart;
+        // Anchors are scoped to their document.
+        if !self.flags.contains(ParserFlags::KEEP_ANCHORS) {
+            self.anchors.clear();
+            self.anchor_bytes = 0;
+        }
+        self.document_end_cause = Some(if explicit_end {
+            DocumentEndCause::ExplicitDots
+        } else if matches!(self.peek_token()?.1, TokenType::DocumentStart) {
+            DocumentEndCause::NextDocumentMarker
         } else {
-            if let Token(span, TokenType::VersionDirective(..) | TokenType::TagDirective(..)) =
-                *self.peek_token()?
-            {
+            DocumentEndCause::Eof
+        });
+        if self.flags.contains(ParserFlags::FRONTMATTER_MODE) {
+            if self.document_end_cause == Some(DocumentEndCause::Eof) {
                 return Err(ScanError::new_str(
                     span.start,
-                    "missing explicit document end marker before directive",
+                    "while parsing a front matter, did not find the closing '---' or '...'",
                 ));
             }
+            // Do not scan the rest of the input, which may not be YAML.
+            self.state = State::End;
+        } else if explicit_end {
+            self.state = State::ImplicitDocumentStart;
+        } else {
+            match *self.peek_token()? {
+                Token(_, TokenType::DocumentStart | TokenType::StreamEnd) => {}
+                Token(span, TokenType::VersionDirective(..) | TokenType::TagDirective(..)) => {
+                    return Err(ScanError::new_str(
+                        span.start,
+                        "missing explicit document end marker before directive",
+                    ));
+                }
+                // The root node ended but the document did not (e.g.: `[1, 2] extra`).
+                Token(span, _) => {
+                    return Err(ScanError::new_str(
+                        span.start,
+                        "trailing content after the root node of a document is not allowed",
+                    ));
+                }
+            }
             self.state = State::DocumentStart;
         }
 
         Ok((Event::DocumentEnd, span))
     }
 
-    fn register_anchor(&mut self, name: String, _: &Span) -> usize {
+    fn register_anchor(&mut self, name: String, span: &Span) -> Result<usize, ScanError> {
+        if self.flags.contains(ParserFlags::FORBID_ANCHORS) {
+            return Err(ScanError::new_str(
+                span.start,
+                "while parsing node, found an anchor but anchors are forbidden",
+            ));
+        }
         // anchors can be overridden/reused
         // if self.anchors.contains_key(name) {
         //     return Err(ScanError::new_str(*mark,
         //         "while parsing anchor, found duplicated anchor"));
         // }
+        if !self.anchors.contains_key(&name) {
+            let anchor_bytes = self.anchor_bytes + name.len();
+            if self.max_anchor_bytes.is_some_and(|max| anchor_bytes > max) {
+                return Err(ScanError::new_str(
+                    span.start,
+                    "while parsing anchor, anchor names exceed the maximum total size",
+                ));
+            }
+            self.anchor_bytes = anchor_bytes;
+        }
         let new_id = self.anchor_id_count;
         self.anchor_id_count += 1;
         self.anchors.insert(name, new_id);
-        new_id
+        Ok(new_id)
     }
 
     fn parse_node(&mut self, block: bool, indentless_sequence: bool) -> ParseResult {
@@ -732,12 +2784,19 @@ impl<T: Input> Parser<T> {
             Token(_, TokenType::Alias(_)) => {
                 self.pop_state();
                 if let Token(span, TokenType::Alias(name)) = self.fetch_token() {
+                    if self.flags.contains(ParserFlags::FORBID_ANCHORS) {
+                        return Err(ScanError::new_str(
+                            span.start,
+                            "while parsing node, found an alias but anchors are forbidden",
+                        ));
+                    }
                     match self.anchors.get(&name) {
                         None => {
-                            return Err(ScanError::new_str(
+                            self.unsupported(ScanError::new_str(
                                 span.start,
                                 "while parsing node, found unknown anchor",
-                            ))
+                            ))?;
+                            return Ok((Event::empty_scalar(), span));
                         }
                         Some(id) => return Ok((Event::Alias(*id), span)),
                     }
@@ -746,9 +2805,12 @@ impl<T: Input> Parser<T> {
             }
             Token(_, TokenType::Anchor(_)) => {
                 if let Token(span, TokenType::Anchor(name)) = self.fetch_token() {
-                    anchor_id = self.register_anchor(name, &span);
+                    anchor_id = self.register_anchor(name, &span)?;
+                    self.record_property_span(true, span);
                     if let TokenType::Tag(..) = self.peek_token()?.1 {
-                        if let TokenType::Tag(handle, suffix) = self.fetch_token().1 {
+                        if let Token(tag_span, TokenType::Tag(handle, suffix)) = self.fetch_token()
+                        {
+                            self.record_property_span(false, tag_span);
                             tag = Some(self.resolve_tag(span, &handle, suffix)?);
                         } else {
                             unreachable!()
@@ -760,10 +2822,12 @@ impl<T: Input> Parser<T> {
             }
             Token(mark, TokenType::Tag(..)) => {
                 if let TokenType::Tag(handle, suffix) = self.fetch_token().1 {
+                    self.record_property_span(false, mark);
                     tag = Some(self.resolve_tag(mark, &handle, suffix)?);
                     if let TokenType::Anchor(_) = &self.peek_token()?.1 {
                         if let Token(mark, TokenType::Anchor(name)) = self.fetch_token() {
-                            anchor_id = self.register_anchor(name, &mark);
+                            anchor_id = self.register_anchor(name, &mark)?;
+                            self.record_property_span(true, mark);
                         } else {
                             unreachable!()
                         }
@@ -775,9 +2839,9 @@ impl<T: Input> Parser<T> {
             _ => {}
         }
         match *self.peek_token()? {
-            Token(mark, TokenType::BlockEntry) if indentless_sequence => {
+            Token(span, TokenType::BlockEntry) if indentless_sequence => {
                 self.state = State::IndentlessSequenceEntry;
-                Ok((Event::SequenceStart(anchor_id, tag), mark))
+                Ok((Event::SequenceStart(anchor_id, tag), Span::empty(span.end)))
             }
             Token(_, TokenType::Scalar(..)) => {
                 self.pop_state();
@@ -830,7 +2894,7 @@ impl<T: Input> Parser<T> {
                 {
                     self.state = State::BlockMappingValue;
                     // empty scalar
-                    Ok((Event::empty_scalar(), mark))
+                    Ok((Event::empty_scalar(), Span::empty(mark.start)))
                 } else {
                     self.push_state(State::BlockMappingValue);
                     self.parse_node(true, true)
@@ -839,7 +2903,7 @@ impl<T: Input> Parser<T> {
             // XXX(chenyh): libyaml failed to parse spec 1.2, ex8.18
             Token(mark, TokenType::Value) => {
                 self.state = State::BlockMappingValue;
-                Ok((Event::empty_scalar(), mark))
+                Ok((Event::empty_scalar(), Span::empty(mark.start)))
             }
             Token(mark, TokenType::BlockEnd) => {
                 self.pop_state();
@@ -862,7 +2926,7 @@ impl<T: Input> Parser<T> {
                 {
                     self.state = State::BlockMappingKey;
                     // empty scalar
-                    Ok((Event::empty_scalar(), mark))
+                    Ok((Event::empty_scalar(), Span::empty(mark.start)))
                 } else {
                     self.push_state(State::BlockMappingKey);
                     self.parse_node(true, true)
@@ -871,7 +2935,7 @@ impl<T: Input> Parser<T> {
             Token(mark, _) => {
                 self.state = State::BlockMappingKey;
                 // empty scalar
-                Ok((Event::empty_scalar(), mark))
+                Ok((Event::empty_scalar(), Span::empty(mark.start)))
             }
         }
     }
@@ -880,6 +2944,7 @@ impl<T: Input> Parser<T> {
         if first {
             let _ = self.peek_token()?;
             self.skip();
+            self.flow_item_counts.push(0);
         }
         let span: Span = {
             match *self.peek_token()? {
@@ -895,6 +2960,17 @@ impl<T: Input> Parser<T> {
                         }
                     }
 
+                    let Token(span, ref tok) = *self.peek_token()?;
+                    if matches!(tok, TokenType::FlowEntry) {
+                        return Err(ScanError::new_str(
+                            span.start,
+                            "while parsing a flow mapping, found an empty entry",
+                        ));
+                    }
+                    if !matches!(tok, TokenType::FlowMappingEnd) {
+                        self.count_flow_item(span.start)?;
+                    }
+
                     match *self.peek_token()? {
                         Token(_, TokenType::Key) => {
                             self.skip();
@@ -927,6 +3003,7 @@ impl<T: Input> Parser<T> {
 
         self.pop_state();
         self.skip();
+        self.flow_item_counts.pop();
         Ok((Event::MappingEnd, span))
     }
 
@@ -963,11 +3040,13 @@ impl<T: Input> Parser<T> {
             let _ = self.peek_token()?;
             //self.marks.push(tok.0);
             self.skip();
+            self.flow_item_counts.push(0);
         }
         match *self.peek_token()? {
             Token(mark, TokenType::FlowSequenceEnd) => {
                 self.pop_state();
                 self.skip();
+                self.flow_item_counts.pop();
                 return Ok((Event::SequenceEnd, mark));
             }
             Token(_, TokenType::FlowEntry) if !first => {
@@ -985,20 +3064,51 @@ impl<T: Input> Parser<T> {
             Token(mark, TokenType::FlowSequenceEnd) => {
                 self.pop_state();
                 self.skip();
+                self.flow_item_counts.pop();
                 Ok((Event::SequenceEnd, mark))
             }
+            Token(span, TokenType::FlowEntry) => {
+                if !self.flags.contains(ParserFlags::ALLOW_EMPTY_FLOW_ENTRIES) {
+                    return Err(ScanError::new_str(
+                        span.start,
+                        "while parsing a flow sequence, found an empty entry",
+                    ));
+                }
+                // Leave the `,` to be skipped as the end of the empty entry.
+                self.count_flow_item(span.start)?;
+                self.state = State::FlowSequenceEntry;
+                Ok((Event::empty_scalar(), Span::empty(span.start)))
+            }
             Token(mark, TokenType::Key) => {
+                self.count_flow_item(mark.start)?;
                 self.state = State::FlowSequenceEntryMappingKey;
                 self.skip();
                 Ok((Event::MappingStart(0, None), mark))
             }
-            _ => {
+            Token(mark, _) => {
+                self.count_flow_item(mark.start)?;
                 self.push_state(State::FlowSequenceEntry);
                 self.parse_node(false, false)
             }
         }
     }
 
+    /// Count an item starting at `mark` in the innermost flow collection.
+    ///
+    /// Return an error if the collection now exceeds [`Self::max_flow_items`].
+    fn count_flow_item(&mut self, mark: Marker) -> Result<(), ScanError> {
+        if let Some(count) = self.flow_item_counts.last_mut() {
+            *count += 1;
+            if self.max_flow_items.is_some_and(|max| *count > max) {
+                return Err(ScanError::new_str(
+                    mark,
+                    "flow collection exceeds the maximum number of items",
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn indentless_sequence_entry(&mut self) -> ParseResult {
         match *self.peek_token()? {
             Token(_, TokenType::BlockEntry) => (),
@@ -1008,16 +3118,21 @@ impl<T: Input> Parser<T> {
             }
         }
         self.skip();
-        if let Token(
-            mark,
-            TokenType::BlockEntry | TokenType::Key | TokenType::Value | TokenType::BlockEnd,
-        ) = *self.peek_token()?
-        {
-            self.state = State::IndentlessSequenceEntry;
-            Ok((Event::empty_scalar(), mark))
-        } else {
-            self.push_state(State::IndentlessSequenceEntry);
-            self.parse_node(true, false)
+        match *self.peek_token()? {
+            // A `-` token spans the indicator and the whitespace following it. The empty scalar
+            // is located after them.
+            Token(span, TokenType::BlockEntry) => {
+                self.state = State::IndentlessSequenceEntry;
+                Ok((Event::empty_scalar(), Span::empty(span.end)))
+            }
+            Token(span, TokenType::Key | TokenType::Value | TokenType::BlockEnd) => {
+                self.state = State::IndentlessSequenceEntry;
+                Ok((Event::empty_scalar(), span))
+            }
+            _ => {
+                self.push_state(State::IndentlessSequenceEntry);
+                self.parse_node(true, false)
+            }
         }
     }
 
@@ -1036,11 +3151,13 @@ impl<T: Input> Parser<T> {
             }
             Token(_, TokenType::BlockEntry) => {
                 self.skip();
-                if let Token(mark, TokenType::BlockEntry | TokenType::BlockEnd) =
+                if let Token(span, TokenType::BlockEntry | TokenType::BlockEnd) =
                     *self.peek_token()?
                 {
+                    // The empty scalar is located after the `-` and its whitespace. `BlockEnd`
+                    // tokens have an empty span.
                     self.state = State::BlockSequenceEntry;
-                    Ok((Event::empty_scalar(), mark))
+                    Ok((Event::empty_scalar(), Span::empty(span.end)))
                 } else {
                     self.push_state(State::BlockSequenceEntry);
                     self.parse_node(true, false)
@@ -1094,7 +3211,7 @@ impl<T: Input> Parser<T> {
     }
 
     /// Resolve a tag from the handle and the suffix.
-    fn resolve_tag(&self, span: Span, handle: &str, suffix: String) -> Result<Tag, ScanError> {
+    fn resolve_tag(&mut self, span: Span, handle: &str, suffix: String) -> Result<Tag, ScanError> {
         if handle == "!!" {
             // "!!" is a shorthand for "tag:yaml.org,2002:". However, that default can be
             // overridden.
@@ -1109,7 +3226,7 @@ impl<T: Input> Parser<T> {
             // "!" introduces a local tag. Local tags may have their prefix overridden.
             match self.tags.get("") {
                 Some(prefix) => Ok(Tag {
-                    handle: prefix.to_string(),
+                    handle: prefix.clone(),
                     suffix,
                 }),
                 None => Ok(Tag {
@@ -1122,7 +3239,13 @@ impl<T: Input> Parser<T> {
             let prefix = self.tags.get(handle);
             if let Some(prefix) = prefix {
                 Ok(Tag {
-                    handle: prefix.to_string(),
+                    handle: prefix.clone(),
+                    suffix,
+                })
+            } else if let Some(base) = self.tag_base.as_ref().filter(|_| handle == "!") {
+                // A local tag with no `%TAG !` directive resolves against the base, if any.
+                Ok(Tag {
+                    handle: base.clone(),
                     suffix,
                 })
             } else {
@@ -1131,13 +3254,12 @@ impl<T: Input> Parser<T> {
                 // If the handle is of the form "!foo!", this cannot be a local handle and we need
                 // to error.
                 if handle.len() >= 2 && handle.starts_with('!') && handle.ends_with('!') {
-                    Err(ScanError::new_str(span.start, "the handle wasn't declared"))
-                } else {
-                    Ok(Tag {
-                        handle: handle.to_string(),
-                        suffix,
-                    })
+                    self.unsupported(ScanError::new_str(span.start, "the handle wasn't declared"))?;
                 }
+                Ok(Tag {
+                    handle: handle.to_string(),
+                    suffix,
+                })
             }
         }
     }
@@ -1146,14 +3268,149 @@ impl<T: Input> Parser<T> {
 impl<T: Input> Iterator for Parser<T> {
     type Item = Result<(Event, Span), ScanError>;
 
+    /// Load the next event, as [`Parser::next_event`] does.
+    ///
+    /// The iterator is fused: it returns `None` after [`Event::StreamEnd`] and after the first
+    /// error, so that consumers cannot loop over the same error or get events from a parser in an
+    /// inconsistent state. This includes errors returned by [`Parser::peek`] or
+    /// [`Parser::next_event`], as for [`Parser::is_finished`].
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: b: c");
+    /// assert!(parser.by_ref().any(|ev| ev.is_err()));
+    /// assert!(parser.next().is_none());
+    /// ```
     fn next(&mut self) -> Option<Self::Item> {
+        if self.errored {
+            return None;
+        }
         self.next_event()
     }
 }
 
+impl<T: Input> FusedIterator for Parser<T> {}
+
+/// The values of the scalars interned by a [`Parser`], see [`Parser::set_intern_scalars`].
+#[derive(Debug, Default)]
+struct InternedScalars {
+    /// The distinct values interned so far.
+    values: HashSet<Arc<str>>,
+    /// The total length of [`Self::values`], in bytes.
+    bytes: usize,
+}
+
+impl InternedScalars {
+    /// Return the interned value equal to `value`, adding it if it fits within `max_bytes`.
+    fn intern(&mut self, value: &str, max_bytes: usize) -> Arc<str> {
+        if let Some(shared) = self.values.get(value) {
+            return shared.clone();
+        }
+        let shared: Arc<str> = Arc::from(value);
+        if self.bytes.saturating_add(value.len()) <= max_bytes {
+            self.bytes += value.len();
+            self.values.insert(shared.clone());
+        }
+        shared
+    }
+}
+
+/// Check whether `input` is a valid YAML stream.
+///
+/// The whole stream is parsed and events are discarded as soon as they are emitted. This is
+/// cheaper than collecting events: the values of scalars are read into a single buffer which is
+/// reused throughout the stream.
+///
+/// ```
+/// # use saphyr_parser::validate;
+/// assert!(validate("a: [b, c]\n").is_ok());
+/// assert_eq!(
+///     validate("a: b: c\n").unwrap_err().info(),
+///     "mapping values are not allowed in this context"
+/// );
+/// ```
+///
+/// # Errors
+/// Returns the first `ScanError` encountered while parsing `input`.
+pub fn validate(input: &str) -> Result<(), ScanError> {
+    let mut parser = Parser::new_from_str(input);
+    let mut buf = String::new();
+    while let Some(event) = parser.next_event_into(&mut buf) {
+        event?;
+    }
+    Ok(())
+}
+
+/// Count the documents of the YAML stream `input`.
+///
+/// The whole stream is parsed, as with [`validate`], so that the count is only returned if the
+/// stream is valid. A stream without content (e.g.: empty or only comments) has no document, while
+/// a lone `---` starts an empty document.
+///
+/// ```
+/// # use saphyr_parser::count_documents;
+/// assert_eq!(count_documents("").unwrap(), 0);
+/// assert_eq!(count_documents("a: 1\n---\nb: 2\n").unwrap(), 2);
+/// assert!(count_documents("a: 1\n---\nb: c: d\n").is_err());
+/// ```
+///
+/// # Errors
+/// Returns the first `ScanError` encountered while parsing `input`.
+pub fn count_documents(input: &str) -> Result<usize, ScanError> {
+    let mut parser = Parser::new_from_str(input);
+    let mut buf = String::new();
+    let mut count = 0;
+    while let Some(event) = parser.next_event_into(&mut buf) {
+        if let (Event::DocumentStart(_), _) = event? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Parse `input` up to its first error, returning the events before it and the error, if any.
+///
+/// This is meant for tools that still want to make use of a document with a mistake (e.g.: to
+/// show the structure of a large configuration file with one bad line). The events are those
+/// returned by [`Parser::next_event`] before the error, which are final. The parser does not try
+/// to recover: nothing after the error is parsed.
+///
+/// ```
+/// # use saphyr_parser::parse_partial;
+/// let (events, error) = parse_partial("a: 1\nb: c: d\n");
+/// let scalars: Vec<_> = events
+///     .iter()
+///     .filter_map(|event| event.as_scalar().map(|(value, ..)| value))
+///     .collect();
+/// assert_eq!(scalars, ["a", "1", "b", "c"]);
+/// assert_eq!(error.unwrap().marker().line(), 2);
+/// ```
+#[must_use]
+pub fn parse_partial(input: &str) -> (Vec<Event>, Option<ScanError>) {
+    let mut events = vec![];
+    for event in Parser::new_from_str(input) {
+        match event {
+            Ok((event, _)) => events.push(event),
+            Err(error) => return (events, Some(error)),
+        }
+    }
+    (events, None)
+}
+
 #[cfg(test)]
 mod test {
-    use super::{Event, Parser};
+    use std::ops::ControlFlow;
+
+    use super::{ControlFlowEventReceiver, Event, Parser};
+    use crate::Span;
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_event_size() {
+        // `Event`s are moved around a lot. Any growth should be deliberate.
+        assert_eq!(std::mem::size_of::<Event>(), 88);
+        assert_eq!(std::mem::size_of::<(Event, Span)>(), 112);
+    }
 
     #[test]
     fn test_peek_eq_parse() {
@@ -1206,4 +3463,50 @@ baz: "qux"
         }
         panic!("Test failed, did not encounter error")
     }
+
+    #[test]
+    fn test_load_stops_early() {
+        /// Collects events until a scalar with the given value is found.
+        struct FindScalar {
+            needle: &'static str,
+            events: Vec<Event>,
+        }
+
+        impl ControlFlowEventReceiver for FindScalar {
+            fn on_event(&mut self, ev: Event, _span: Span) -> ControlFlow<()> {
+                let found = matches!(&ev, Event::Scalar(v, ..) if v == self.needle);
+                self.events.push(ev);
+                if found {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }
+        }
+
+        let s = "
+a: [1, 2]
+b: 3
+---
+c: 4
+";
+        let mut recv = FindScalar {
+            needle: "2",
+            events: vec![],
+        };
+        let mut p = Parser::new_from_str(s);
+        p.load(&mut recv, true).unwrap();
+        assert_eq!(recv.events.len(), 7);
+        assert!(matches!(recv.events.last(), Some(Event::Scalar(v, ..)) if v == "2"));
+        // The parser can resume where the receiver stopped.
+        assert_eq!(p.next_event().unwrap().unwrap().0, Event::SequenceEnd);
+
+        // A needle that is never found consumes the whole stream.
+        let mut recv = FindScalar {
+            needle: "z",
+            events: vec![],
+        };
+        Parser::new_from_str(s).load(&mut recv, true).unwrap();
+        assert_eq!(recv.events.last(), Some(&Event::StreamEnd));
+    }
 }
@@ -9,14 +9,15 @@
 #![allow(clippy::cast_possible_wrap)]
 #![allow(clippy::cast_sign_loss)]
 
-use std::{char, collections::VecDeque, error::Error, fmt};
+use std::{char, collections::VecDeque, error::Error, fmt, sync::Arc};
 
 use crate::{
     char_traits::{
-        as_hex, is_anchor_char, is_blank_or_breakz, is_break, is_breakz, is_flow, is_hex,
-        is_tag_char, is_uri_char,
+        as_hex, is_anchor_char, is_blank_or_breakz, is_bom, is_break, is_breakz, is_flow, is_hex,
+        is_printable, is_tag_char, is_uri_char, is_z,
     },
     input::{Input, SkipTabs},
+    stack::SmallStack,
 };
 
 /// The encoding of the input. Currently, only UTF-8 is supported.
@@ -26,10 +27,37 @@ pub enum TEncoding {
     Utf8,
 }
 
+/// How the line breaks of multi-line plain scalars are interpreted.
+///
+/// See [`Parser::set_plain_scalar_fold`].
+///
+/// [`Parser::set_plain_scalar_fold`]: crate::Parser::set_plain_scalar_fold
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
+pub enum FoldMode {
+    /// Fold a single line break into a space, as the YAML specification requires.
+    #[default]
+    SpecFold,
+    /// Keep every line break as a `\n`.
+    PreserveNewlines,
+}
+
 /// The style as which the scalar was written in the YAML document.
 #[derive(Clone, Copy, PartialEq, Debug, Eq)]
 pub enum TScalarStyle {
     /// A YAML plain scalar.
+    ///
+    /// Plain scalars may span multiple lines. In a block context, continuation lines must be
+    /// indented more than the collection the scalar is in. The following rules apply:
+    ///   - Leading and trailing whitespace of each line is stripped. A single line break is folded
+    ///     into a space (`a: b\n  c` is `b c`). Each empty line that follows is kept as a `\n`.
+    ///   - `#` starts a comment only if preceded by whitespace. A comment ends the scalar: `b#c`
+    ///     is part of the scalar, but in `a: b #c\n  d`, `d` is not a continuation of `b`, and is
+    ///     rejected.
+    ///   - `:` ends the scalar only if followed by whitespace (`b:c` is part of the scalar). The
+    ///     scalar is then an implicit key, which cannot span multiple lines: `a: b\n  c: d` is
+    ///     rejected.
+    ///   - A line that is not indented enough ends the scalar. In `a: b\nc:`, `c` is a new key.
+    ///   - A document marker (`---` or `...`) at the start of a line ends the scalar.
     Plain,
     /// A YAML single quoted scalar.
     SingleQuoted,
@@ -42,41 +70,131 @@ pub enum TScalarStyle {
     Folded,
 }
 
+impl TScalarStyle {
+    /// Return whether the style is single-quoted or double-quoted.
+    #[must_use]
+    pub fn is_quoted(self) -> bool {
+        matches!(self, Self::SingleQuoted | Self::DoubleQuoted)
+    }
+
+    /// Return whether the style is literal or folded.
+    #[must_use]
+    pub fn is_block(self) -> bool {
+        matches!(self, Self::Literal | Self::Folded)
+    }
+
+    /// Return whether some characters must be escaped to appear in a scalar of this style.
+    ///
+    /// In double-quoted scalars, `"` and `\` must be escaped with a `\`. In single-quoted
+    /// scalars, `'` must be doubled. Other styles have no escaping mechanism: values that cannot
+    /// be written as-is cannot be written in these styles at all.
+    #[must_use]
+    pub fn requires_escaping(self) -> bool {
+        self.is_quoted()
+    }
+
+    /// Return the indicator that starts a scalar of this style, or `None` for plain scalars.
+    ///
+    /// ```
+    /// # use saphyr_parser::TScalarStyle;
+    /// assert_eq!(TScalarStyle::Literal.as_indicator_char(), Some('|'));
+    /// assert_eq!(TScalarStyle::Plain.as_indicator_char(), None);
+    /// ```
+    #[must_use]
+    pub fn as_indicator_char(self) -> Option<char> {
+        match self {
+            Self::Plain => None,
+            Self::SingleQuoted => Some('\''),
+            Self::DoubleQuoted => Some('"'),
+            Self::Literal => Some('|'),
+            Self::Folded => Some('>'),
+        }
+    }
+}
+
 /// A location in a yaml document.
+///
+/// Positions are stored as 32-bit integers to keep markers (and thus [`Span`]s and events) small.
+/// This limits inputs to 4GiB (or 4 billion lines or columns). Positions past that limit saturate
+/// at `u32::MAX`.
 #[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
 pub struct Marker {
-    /// The index (in chars) in the input string.
-    index: usize,
+    /// The index (in bytes) in the input string.
+    index: u32,
     /// The line (1-indexed).
-    line: usize,
-    /// The column (1-indexed).
-    col: usize,
+    line: u32,
+    /// The column (0-indexed, in characters).
+    col: u32,
 }
 
 impl Marker {
     /// Create a new [`Marker`] at the given position.
+    ///
+    /// Values that do not fit in 32 bits are saturated to `u32::MAX`.
+    ///
+    /// ```
+    /// # use saphyr_parser::Marker;
+    /// let mark = Marker::new(7, 2, 3);
+    /// assert_eq!((mark.index(), mark.line(), mark.col()), (7, 2, 3));
+    /// ```
     #[must_use]
     pub fn new(index: usize, line: usize, col: usize) -> Marker {
-        Marker { index, line, col }
+        Marker {
+            index: saturating_u32(index),
+            line: saturating_u32(line),
+            col: saturating_u32(col),
+        }
     }
 
     /// Return the index (in bytes) of the marker in the source.
     #[must_use]
     pub fn index(&self) -> usize {
-        self.index
+        self.index as usize
     }
 
-    /// Return the line of the marker in the source.
+    /// Return the line (1-indexed) of the marker in the source.
     #[must_use]
     pub fn line(&self) -> usize {
-        self.line
+        self.line as usize
     }
 
-    /// Return the column of the marker in the source.
+    /// Return the column (0-indexed) of the marker in the source.
+    ///
+    /// Columns count characters (Unicode scalar values), not bytes. A tab counts as a single
+    /// character.
     #[must_use]
     pub fn col(&self) -> usize {
-        self.col
+        self.col as usize
+    }
+
+    /// Move the marker `count` single-byte characters forward on the current line.
+    #[inline]
+    pub(crate) fn advance(&mut self, count: usize) {
+        let count = saturating_u32(count);
+        self.index = self.index.saturating_add(count);
+        self.col = self.col.saturating_add(count);
+    }
+
+    /// Move the marker `n_chars` characters spanning `n_bytes` bytes forward on the current line.
+    #[inline]
+    pub(crate) fn advance_multibyte(&mut self, n_chars: usize, n_bytes: usize) {
+        self.index = self.index.saturating_add(saturating_u32(n_bytes));
+        self.col = self.col.saturating_add(saturating_u32(n_chars));
     }
+
+    /// Move the marker past a line break character, to the start of the next line.
+    #[inline]
+    fn advance_line(&mut self) {
+        self.index = self.index.saturating_add(1);
+        self.line = self.line.saturating_add(1);
+        self.col = 0;
+    }
+}
+
+/// Convert a position to its 32-bit representation, saturating if it does not fit.
+#[inline]
+fn saturating_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
 }
 
 /// A range of locations in a Yaml document.
@@ -90,16 +208,43 @@ pub struct Span {
 
 impl Span {
     /// Create a new [`Span`] for the given range.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Marker, Span};
+    /// let yaml = "key: value";
+    /// let span = Span::new(Marker::new(5, 1, 5), Marker::new(10, 1, 10));
+    /// assert_eq!(&yaml[span.start.index()..span.end.index()], "value");
+    /// assert_eq!(span.len(), 5);
+    /// ```
     #[must_use]
     pub fn new(start: Marker, end: Marker) -> Span {
         Span { start, end }
     }
 
+    /// Return the length of the span, in bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.end.index().saturating_sub(self.start.index())
+    }
+
+    /// Return whether the span contains no characters.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Create a empty [`Span`] at a given location.
     ///
     /// An empty span doesn't contain any characters, but its position may still be meaningful.
     /// For example, for an indented sequence [`SequenceEnd`] has a location but an empty span.
     ///
+    /// ```
+    /// # use saphyr_parser::{Marker, Span};
+    /// let span = Span::empty(Marker::new(4, 1, 4));
+    /// assert!(span.is_empty());
+    /// assert_eq!(span.start, span.end);
+    /// ```
+    ///
     /// [`SequenceEnd`]: crate::Event::SequenceEnd
     #[must_use]
     pub fn empty(mark: Marker) -> Span {
@@ -110,20 +255,49 @@ impl Span {
     }
 }
 
+/// Details about an indicator that was found where another construct forbids it.
+///
+/// The indicator is located at the [marker] of the error. `other` is the construct that forbids
+/// it. For instance, in `a: b: c`, the second `:` is found at column 4 in the value of the first
+/// `:`, at column 1.
+///
+/// [marker]: ScanError::marker
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Conflict {
+    /// The indicator that was found (e.g.: `:`).
+    pub found: char,
+    /// The indicator of the construct that forbids it (e.g.: `:` or `[`).
+    pub other: char,
+    /// The position of `other` in the source.
+    pub other_mark: Marker,
+}
+
 /// An error that occurred while scanning.
-#[derive(Clone, PartialEq, Debug, Eq)]
+#[derive(Clone, Debug)]
 pub struct ScanError {
     /// The position at which the error happened in the source.
     mark: Marker,
     /// Human-readable details about the error.
     info: String,
+    /// The underlying error that caused this one, if any.
+    ///
+    /// This is set when the error does not originate from the scanner itself (e.g.: the input
+    /// failed to be read). It is stored behind an [`Arc`] so that [`ScanError`] can stay [`Clone`].
+    source: Option<Arc<dyn Error + Send + Sync + 'static>>,
+    /// The constructs that conflicted, if the error is caused by a misplaced indicator.
+    conflict: Option<Conflict>,
 }
 
 impl ScanError {
     /// Create a new error from a location and an error string.
     #[must_use]
     pub fn new(loc: Marker, info: String) -> ScanError {
-        ScanError { mark: loc, info }
+        ScanError {
+            mark: loc,
+            info,
+            source: None,
+            conflict: None,
+        }
     }
 
     /// Convenience alias for string slices.
@@ -132,9 +306,35 @@ impl ScanError {
         ScanError {
             mark: loc,
             info: info.to_owned(),
+            source: None,
+            conflict: None,
+        }
+    }
+
+    /// Create a new error from a location, an error string and the error that caused it.
+    ///
+    /// The underlying error is returned by [`Error::source`], where callers can downcast it.
+    #[must_use]
+    pub fn new_with_source(
+        loc: Marker,
+        info: String,
+        source: Box<dyn Error + Send + Sync + 'static>,
+    ) -> ScanError {
+        ScanError {
+            mark: loc,
+            info,
+            source: Some(Arc::from(source)),
+            conflict: None,
         }
     }
 
+    /// Attach details about the constructs that conflicted to the error.
+    #[must_use]
+    pub fn with_conflict(mut self, conflict: Conflict) -> ScanError {
+        self.conflict = Some(conflict);
+        self
+    }
+
     /// Return the marker pointing to the error in the source.
     #[must_use]
     pub fn marker(&self) -> &Marker {
@@ -146,11 +346,41 @@ impl ScanError {
     pub fn info(&self) -> &str {
         self.info.as_ref()
     }
+
+    /// Return the constructs that conflicted, if the error is caused by a misplaced indicator.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Conflict, Marker, Parser};
+    /// let err = Parser::new_from_str("a: b: c").find_map(Result::err).unwrap();
+    /// assert_eq!(err.marker().col(), 4);
+    /// assert_eq!(
+    ///     err.conflict(),
+    ///     Some(&Conflict {
+    ///         found: ':',
+    ///         other: ':',
+    ///         other_mark: Marker::new(1, 1, 1),
+    ///     })
+    /// );
+    /// ```
+    #[must_use]
+    pub fn conflict(&self) -> Option<&Conflict> {
+        self.conflict.as_ref()
+    }
 }
 
+/// Errors are compared by location and description only. Their sources and conflicts, if any, are
+/// ignored.
+impl PartialEq for ScanError {
+    fn eq(&self, other: &Self) -> bool {
+        self.mark == other.mark && self.info == other.info
+    }
+}
+
+impl Eq for ScanError {}
+
 impl Error for ScanError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+        self.source.as_deref().map(|e| e as &(dyn Error + 'static))
     }
 }
 
@@ -160,9 +390,9 @@ impl fmt::Display for ScanError {
             formatter,
             "{} at byte {} line {} column {}",
             self.info,
-            self.mark.index,
-            self.mark.line,
-            self.mark.col + 1,
+            self.mark.index(),
+            self.mark.line(),
+            u64::from(self.mark.col) + 1,
         )
     }
 }
@@ -271,7 +501,7 @@ pub struct Token(pub Span, pub TokenType);
 ///
 /// In the second document however, reaching the EOF would stale the [`SimpleKey`] and no
 /// [`TokenType::Key`] would be emitted by the scanner.
-#[derive(Clone, PartialEq, Debug, Eq)]
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
 struct SimpleKey {
     /// Whether the token this [`SimpleKey`] refers to may still be a key.
     ///
@@ -317,7 +547,8 @@ impl SimpleKey {
 }
 
 /// An indentation level on the stack of indentations.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default)]
+#[allow(clippy::struct_field_names)]
 struct Indent {
     /// The former indentation level.
     indent: isize,
@@ -339,6 +570,10 @@ struct Indent {
     /// `BlockEnd` when this indentation block ends would generate one `BlockEnd` per entry in the
     /// sequence, although we must have exactly one to end the sequence.
     needs_block_end: bool,
+    /// Whether the innermost block collection is a block mapping at this indentation level.
+    ///
+    /// Levels that do not start a block share the value of the level they were added after.
+    mapping: bool,
 }
 
 /// The knowledge we have about an implicit mapping.
@@ -362,7 +597,7 @@ struct Indent {
 ///
 /// [`FlowMappingStart`]: TokenType::FlowMappingStart
 /// [`FlowMappingEnd`]: TokenType::FlowMappingEnd
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum ImplicitMappingState {
     /// It is possible there is an implicit mapping.
     ///
@@ -375,6 +610,47 @@ enum ImplicitMappingState {
     Inside,
 }
 
+/// The number of bytes of block scalar content buffered before they are handed to the sink.
+///
+/// See [`Scanner::set_block_scalar_sink`]. Lines are never split: a chunk ends at the end of the
+/// line that makes it reach this size, or at the end of the scalar.
+const BLOCK_SCALAR_CHUNK_LEN: usize = 64 * 1024;
+
+/// The header of a block scalar, see [`Parser::set_record_block_scalar_headers`].
+///
+/// [`Parser::set_record_block_scalar_headers`]: crate::Parser::set_record_block_scalar_headers
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BlockScalarHeader {
+    /// The range of the header, from the `|` or `>` indicator to the last indicator after it.
+    ///
+    /// The header precedes the span of the scalar, which starts at its content.
+    pub span: Span,
+    /// The indentation indicator (from 1 to 9), if any.
+    pub indentation_indicator: Option<u8>,
+    /// The chomping of the scalar, [`Chomping::Clip`] if the header has no chomping indicator.
+    pub chomping: Chomping,
+    /// The column the content lines of the scalar start at.
+    ///
+    /// This is the indentation of the parent node plus the indentation indicator if there is one,
+    /// or the indentation detected from the first non-empty line otherwise.
+    pub indent: usize,
+}
+
+/// A callback receiving the content of block scalars, see [`Scanner::set_block_scalar_sink`].
+pub(crate) struct BlockScalarSink(Box<dyn FnMut(&str) + Send + Sync>);
+
+impl fmt::Debug for BlockScalarSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BlockScalarSink")
+    }
+}
+
+// A sink must not keep the scanner from being sent to or shared with other threads.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Scanner<crate::input::str::StrInput<'static>>>();
+};
+
 /// The YAML scanner.
 ///
 /// This corresponds to the low-level interface when reading YAML. The scanner emits token as they
@@ -410,6 +686,87 @@ pub struct Scanner<T> {
     /// In some flow contexts, the value of a mapping is allowed to be adjacent to the `:`. When it
     /// is, the index at which the `:` may be must be stored in `adjacent_value_allowed_at`.
     adjacent_value_allowed_at: usize,
+    /// The index of the content following a block indicator (`-`, `?`, `:`) it is separated from
+    /// by tabs, if any.
+    ///
+    /// Tabs are valid separation, but a block collection cannot start after them on the same line
+    /// since it would use the tab as indentation. A simple key starting at that index would start
+    /// an implicit block mapping and must be rejected. With `»` denoting a tab:
+    /// ```text
+    /// -»key: value # ko, `key: value` would be indented with a tab.
+    /// -»value # ok, `value` is a plain scalar.
+    /// ```
+    tab_separated_content_at: Option<usize>,
+    /// Whether a byte order mark may start the current line.
+    ///
+    /// This is the case at the start of the stream and after a document end marker (`...`), until
+    /// the next token. A byte order mark may also precede a document start marker (`---`).
+    bom_allowed: bool,
+    /// Whether a `:` in a flow context is a value indicator even if not followed by a space.
+    ///
+    /// See [`Self::set_json_mode`].
+    json_mode: bool,
+    /// How the line breaks of multi-line plain scalars are interpreted.
+    ///
+    /// See [`Self::set_plain_scalar_fold`].
+    plain_scalar_fold: FoldMode,
+    /// Whether control characters that YAML forbids are accepted in scalars.
+    ///
+    /// See [`Self::set_allow_control_chars`].
+    allow_control_chars: bool,
+    /// See [`Self::set_error_on_tab_anywhere`].
+    error_on_tab: bool,
+    /// Characters other than spaces and tabs that separate tokens.
+    ///
+    /// See [`Self::set_extra_whitespace`].
+    extra_whitespace: Vec<char>,
+    /// Whether to record the spans of the trivia found between tokens.
+    ///
+    /// See [`Self::set_record_trivia`].
+    record_trivia: bool,
+    /// The spans of the trivia recorded since the last call to [`Self::take_trivia`].
+    trivia: Vec<Span>,
+    /// The callback the content of block scalars is handed to instead of their token, if any.
+    ///
+    /// See [`Self::set_block_scalar_sink`].
+    block_scalar_sink: Option<BlockScalarSink>,
+    /// The starts of the block scalars whose content was handed to the sink, in source order.
+    ///
+    /// Entries are removed by [`Self::take_streamed_block_scalar`].
+    streamed_block_scalars: VecDeque<Marker>,
+    /// Whether to record the whitespace stripped from the end of the lines of plain scalars.
+    ///
+    /// See [`Self::set_record_trailing_whitespace`].
+    record_trailing_whitespace: bool,
+    /// The spans of the trailing whitespace recorded since the last call to
+    /// [`Self::take_trailing_whitespace`].
+    trailing_whitespace: Vec<Span>,
+    /// Whether to record the headers of block scalars.
+    ///
+    /// See [`Self::set_record_block_scalar_headers`].
+    record_block_scalar_headers: bool,
+    /// The headers of the block scalars recorded since the last call to
+    /// [`Self::take_block_scalar_headers`].
+    block_scalar_headers: Vec<BlockScalarHeader>,
+    /// The maximum number of characters a line may contain, if any.
+    ///
+    /// See [`Self::set_max_line_length`].
+    max_line_length: Option<usize>,
+    /// See [`Self::set_max_total_bytes`].
+    max_total_bytes: Option<usize>,
+    /// The maximum number of flow collections that may be nested, if any.
+    ///
+    /// See [`Self::set_max_flow_depth`].
+    max_flow_depth: Option<usize>,
+    /// The maximum number of possible simple keys that may be pending at once, if any.
+    ///
+    /// See [`Self::set_max_simple_keys`].
+    max_simple_keys: Option<usize>,
+    /// The end of the first line that exceeded [`Self::max_line_length`], if any.
+    ///
+    /// Line breaks are consumed in functions that cannot fail. We record the overlong line there
+    /// and report it at the next call to [`Self::check_line_length`].
+    overlong_line_end: Option<Marker>,
     /// Whether a simple key could potentially start at the current position.
     ///
     /// Simple keys are the opposite of complex keys which are keys starting with `?`.
@@ -418,12 +775,15 @@ pub struct Scanner<T> {
     ///
     /// Refer to the documentation of [`SimpleKey`] for a more in-depth explanation of what they
     /// are.
-    simple_keys: Vec<SimpleKey>,
+    simple_keys: SmallStack<SimpleKey, 16>,
     /// The current indentation level.
     indent: isize,
     /// List of all block indentation levels we are in (except the current one).
-    indents: Vec<Indent>,
-    /// Level of nesting of flow sequences.
+    indents: SmallStack<Indent, 16>,
+    /// Level of nesting of flow collections.
+    ///
+    /// Flow collections cannot be nested more than [`u8::MAX`] levels deep. Opening one more errors
+    /// in [`Self::increase_flow_level`]. Block collections are not subject to this limit.
     flow_level: u8,
     /// The number of tokens that have been returned from the scanner.
     ///
@@ -452,10 +812,30 @@ pub struct Scanner<T> {
     ///
     /// [`Possible`]: ImplicitMappingState::Possible
     /// [`Inside`]: ImplicitMappingState::Inside
-    implicit_flow_mapping_states: Vec<ImplicitMappingState>,
+    implicit_flow_mapping_states: SmallStack<ImplicitMappingState, 16>,
     buf_leading_break: String,
     buf_trailing_breaks: String,
     buf_whitespaces: String,
+    /// A buffer given back through [`Self::recycle_scalar_buffer`], used for the next scalar.
+    recycled_scalar_buffer: String,
+    /// The last block indicator (`-`, `?`, `:`) we have fetched, and its position.
+    ///
+    /// An indicator on the same line as this one may be forbidden by it (e.g.: `a: b: c`).
+    last_block_indicator: Option<(char, Marker)>,
+    /// The indentation of the last block mapping closed by [`Self::unroll_indent`], and where it
+    /// was closed.
+    ///
+    /// A key on that line indented less than that mapping but more than the enclosing block
+    /// collection was likely meant to be in that mapping.
+    closed_mapping: Option<(Marker, usize)>,
+    /// The start of the last continuation line of the last plain scalar, if it had any and started
+    /// on the line of a `:` indicator.
+    ///
+    /// A `:` on that line that is not allowed there is likely that of a key indented more than its
+    /// siblings, which was read as a continuation of the previous value.
+    plain_scalar_last_line: Option<Marker>,
+    /// The opening indicator (`[` or `{`) and position of each flow collection we are in.
+    flow_collection_starts: Vec<(char, Marker)>,
 }
 
 impl<T: Input> Iterator for Scanner<T> {
@@ -497,23 +877,378 @@ impl<T: Input> Scanner<T> {
             stream_start_produced: false,
             stream_end_produced: false,
             adjacent_value_allowed_at: 0,
+            tab_separated_content_at: None,
+            bom_allowed: true,
+            json_mode: false,
+            plain_scalar_fold: FoldMode::SpecFold,
+            allow_control_chars: false,
+            error_on_tab: false,
+            extra_whitespace: Vec::new(),
+            record_trivia: false,
+            trivia: Vec::new(),
+            block_scalar_sink: None,
+            streamed_block_scalars: VecDeque::new(),
+            record_trailing_whitespace: false,
+            trailing_whitespace: Vec::new(),
+            record_block_scalar_headers: false,
+            block_scalar_headers: Vec::new(),
+            max_line_length: None,
+            max_total_bytes: None,
+            max_flow_depth: None,
+            max_simple_keys: None,
+            overlong_line_end: None,
             simple_key_allowed: true,
-            simple_keys: Vec::new(),
+            simple_keys: SmallStack::new(),
             indent: -1,
-            indents: Vec::new(),
+            indents: SmallStack::new(),
             flow_level: 0,
             tokens_parsed: 0,
             token_available: false,
             leading_whitespace: true,
             flow_mapping_started: false,
-            implicit_flow_mapping_states: vec![],
+            implicit_flow_mapping_states: SmallStack::new(),
 
             buf_leading_break: String::new(),
             buf_trailing_breaks: String::new(),
             buf_whitespaces: String::new(),
+            recycled_scalar_buffer: String::new(),
+            last_block_indicator: None,
+            closed_mapping: None,
+            plain_scalar_last_line: None,
+            flow_collection_starts: Vec::new(),
+        }
+    }
+
+    /// Scan the input as if its first line started at column `indent`.
+    ///
+    /// The following lines are not offset. This has no effect if scanning has already started.
+    pub fn set_base_indent(&mut self, indent: usize) {
+        if !self.stream_start_produced {
+            self.mark = Marker::new(0, 1, indent);
+        }
+    }
+
+    /// Scan the input as if it started at `mark`, which must be at the start of a line.
+    ///
+    /// This is used to resume scanning a stream part of which was dropped. This has no effect if
+    /// scanning has already started.
+    #[cfg(feature = "async")]
+    pub(crate) fn set_start_mark(&mut self, mark: Marker) {
+        debug_assert_eq!(mark.col(), 0);
+        if !self.stream_start_produced {
+            self.mark = mark;
         }
     }
 
+    /// Treat any `:` in a flow context as a value indicator, as JSON does.
+    ///
+    /// YAML requires a space after the `:` of a plain key: `{a:1}` is a mapping with a single `a:1`
+    /// key. With JSON mode enabled, `:` ends a plain scalar in a flow context and `{a:1}` maps `a`
+    /// to `1`. Plain scalars in flow contexts can hence no longer contain a `:` (e.g.: `[a:b]` is
+    /// `[{a: b}]`). Block contexts are not affected.
+    pub fn set_json_mode(&mut self, json_mode: bool) {
+        self.json_mode = json_mode;
+    }
+
+    /// Choose how the line breaks of multi-line plain scalars are interpreted.
+    ///
+    /// With [`FoldMode::PreserveNewlines`], a single line break between two lines of a plain
+    /// scalar is kept as a `\n` instead of being folded into a space. This is not compliant with
+    /// the YAML specification. Other scalar styles are not affected.
+    pub fn set_plain_scalar_fold(&mut self, fold: FoldMode) {
+        self.plain_scalar_fold = fold;
+    }
+
+    /// Accept control characters that YAML forbids (e.g.: `\x07`).
+    ///
+    /// By default, characters outside of the `c-printable` production of the YAML specification
+    /// are rejected in scalars, comments, anchors, tags and directives. This only applies to raw
+    /// characters: escape sequences in double-quoted scalars may always produce control
+    /// characters.
+    pub fn set_allow_control_chars(&mut self, allow: bool) {
+        self.allow_control_chars = allow;
+    }
+
+    /// Error on any tab in the input, wherever YAML allows it.
+    pub fn set_error_on_tab_anywhere(&mut self, error: bool) {
+        self.error_on_tab = error;
+    }
+
+    /// Treat `chars` as whitespace separating tokens, like spaces.
+    ///
+    /// They are never indentation: at the start of a line, they are content.
+    pub fn set_extra_whitespace(&mut self, chars: &[char]) {
+        self.extra_whitespace = chars.to_vec();
+    }
+
+    /// Record the spans of the trivia found between tokens.
+    ///
+    /// Trivia are the whitespace, line breaks, indentation and comments that separate tokens. Each
+    /// contiguous run of trivia is recorded as a single [`Span`], which can be retrieved with
+    /// [`Self::take_trivia`].
+    pub fn set_record_trivia(&mut self, record: bool) {
+        self.record_trivia = record;
+    }
+
+    /// Return the spans of the trivia recorded since the last call, in source order.
+    ///
+    /// Trivia are only recorded if enabled with [`Self::set_record_trivia`].
+    pub fn take_trivia(&mut self) -> Vec<Span> {
+        std::mem::take(&mut self.trivia)
+    }
+
+    /// Hand the content of block scalars to `sink` in chunks rather than storing it in their token.
+    ///
+    /// The tokens of block scalars then have an empty value. The chunks of a block scalar are
+    /// handed to `sink` while it is scanned, before its token is returned.
+    pub fn set_block_scalar_sink<F: FnMut(&str) + Send + Sync + 'static>(&mut self, sink: F) {
+        self.block_scalar_sink = Some(BlockScalarSink(Box::new(sink)));
+    }
+
+    /// Return whether a block scalar sink was set with [`Self::set_block_scalar_sink`].
+    pub(crate) fn has_block_scalar_sink(&self) -> bool {
+        self.block_scalar_sink.is_some()
+    }
+
+    /// Hand `string` to the block scalar sink, if any, once it holds at least `min_len` bytes.
+    ///
+    /// Return whether `string` was handed to the sink.
+    fn flush_block_scalar(&mut self, string: &mut String, min_len: usize) -> bool {
+        if let Some(sink) = &mut self.block_scalar_sink {
+            if !string.is_empty() && string.len() >= min_len {
+                (sink.0)(string);
+                string.clear();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Return whether the content of the block scalar token starting at `start` was handed to the
+    /// block scalar sink.
+    ///
+    /// Block scalar tokens must be checked in the order they were scanned. Entries for the tokens
+    /// preceding `start` which were not checked are discarded.
+    pub(crate) fn take_streamed_block_scalar(&mut self, start: Marker) -> bool {
+        while let Some(front) = self.streamed_block_scalars.front() {
+            if front.index() > start.index() {
+                break;
+            }
+            let found = *front == start;
+            self.streamed_block_scalars.pop_front();
+            if found {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Record the whitespace stripped from the end of the lines of plain scalars.
+    ///
+    /// Plain scalars do not include the whitespace that ends their lines. Each run of such
+    /// whitespace is recorded as a [`Span`], which can be retrieved with
+    /// [`Self::take_trailing_whitespace`]. Whitespace before a comment is not recorded.
+    pub fn set_record_trailing_whitespace(&mut self, record: bool) {
+        self.record_trailing_whitespace = record;
+    }
+
+    /// Return the spans of the trailing whitespace recorded since the last call, in source order.
+    ///
+    /// Trailing whitespace is only recorded if enabled with
+    /// [`Self::set_record_trailing_whitespace`].
+    pub fn take_trailing_whitespace(&mut self) -> Vec<Span> {
+        std::mem::take(&mut self.trailing_whitespace)
+    }
+
+    /// Record the header of each block scalar, with its indicators and content indentation.
+    ///
+    /// Headers can be retrieved with [`Self::take_block_scalar_headers`].
+    pub fn set_record_block_scalar_headers(&mut self, record: bool) {
+        self.record_block_scalar_headers = record;
+    }
+
+    /// Return the headers of the block scalars recorded since the last call, in source order.
+    ///
+    /// Headers are only recorded if enabled with [`Self::set_record_block_scalar_headers`].
+    pub fn take_block_scalar_headers(&mut self) -> Vec<BlockScalarHeader> {
+        std::mem::take(&mut self.block_scalar_headers)
+    }
+
+    /// Record the trivia skipped since `start`, if enabled.
+    ///
+    /// Trivia skipped right after the previously recorded ones extend them.
+    #[inline]
+    fn record_trivia_since(&mut self, start: Marker) {
+        if !self.record_trivia || start.index() == self.mark.index() {
+            return;
+        }
+        match self.trivia.last_mut() {
+            Some(last) if last.end.index() == start.index() => last.end = self.mark,
+            _ => self.trivia.push(Span::new(start, self.mark)),
+        }
+    }
+
+    /// Error if a line contains more than `max` characters.
+    ///
+    /// Line breaks are not counted. Scalars are checked after each character, other tokens between
+    /// tokens.
+    pub fn set_max_line_length(&mut self, max: usize) {
+        self.max_line_length = Some(max);
+    }
+
+    /// Error once more than `max` bytes of the input have been consumed.
+    ///
+    /// Scalars are checked after each character, other tokens between tokens.
+    pub fn set_max_total_bytes(&mut self, max: usize) {
+        self.max_total_bytes = Some(max);
+    }
+
+    /// Error if more than `max` flow collections are nested.
+    ///
+    /// Flow collections may never be nested more than 255 levels deep, whatever `max`.
+    pub fn set_max_flow_depth(&mut self, max: usize) {
+        self.max_flow_depth = Some(max);
+    }
+
+    /// Error if more than `max` possible simple keys are pending at once.
+    ///
+    /// There is at most one possible simple key per flow level, plus one for the block context.
+    /// Without this limit, their number is bounded by the maximum flow depth.
+    pub fn set_max_simple_keys(&mut self, max: usize) {
+        self.max_simple_keys = Some(max);
+    }
+
+    /// Return an error if the current line, or a line we already consumed, is too long.
+    #[inline]
+    fn check_line_length(&self) -> ScanResult {
+        let Some(max) = self.max_line_length else {
+            return Ok(());
+        };
+        if let Some(mark) = self.overlong_line_end {
+            return Err(ScanError::new_str(
+                mark,
+                "line exceeds the maximum line length",
+            ));
+        }
+        if self.mark.col() > max {
+            return Err(ScanError::new_str(
+                self.mark,
+                "line exceeds the maximum line length",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Return an error if `mark` lies past the end of a line of [`Self::max_line_length`]
+    /// characters, or past [`Self::max_total_bytes`] bytes of input.
+    ///
+    /// Scalars are checked with this after each character, so that an overlong line or input is
+    /// reported where it crosses the limit rather than once the whole scalar has been buffered.
+    #[inline]
+    fn check_limits_at(&self, mark: Marker) -> ScanResult {
+        if self.max_line_length.is_some_and(|max| mark.col() > max) {
+            return Err(ScanError::new_str(
+                mark,
+                "line exceeds the maximum line length",
+            ));
+        }
+        if self.max_total_bytes.is_some_and(|max| mark.index() > max) {
+            return Err(ScanError::new_str(
+                mark,
+                "input exceeds the maximum total size",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Return how many single-byte characters may be consumed on the current line before
+    /// [`Self::check_limits_at`] fails.
+    #[inline]
+    fn bytes_before_limits(&self) -> usize {
+        let line_room = self
+            .max_line_length
+            .map_or(usize::MAX, |max| max.saturating_sub(self.mark.col()));
+        let total_room = self
+            .max_total_bytes
+            .map_or(usize::MAX, |max| max.saturating_sub(self.mark.index()));
+        line_room.min(total_room)
+    }
+
+    /// Return an error if more than [`Self::max_total_bytes`] bytes of the input were consumed.
+    #[inline]
+    fn check_total_bytes(&self) -> ScanResult {
+        if self
+            .max_total_bytes
+            .is_some_and(|max| self.mark.index() > max)
+        {
+            return Err(ScanError::new_str(
+                self.mark,
+                "input exceeds the maximum total size",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Return whether `c` is a control character, or a tab, that must be rejected.
+    #[inline]
+    fn is_forbidden_char(&self, c: char) -> bool {
+        (!self.allow_control_chars && !is_printable(c)) || (self.error_on_tab && c == '\t')
+    }
+
+    /// Return an error if the next character is a control character that must be rejected.
+    ///
+    /// This is called before reporting an unexpected character, where a control character would
+    /// end a token. The next character must have been looked ahead.
+    #[inline]
+    fn check_control_char(&self) -> ScanResult {
+        let c = self.input.peek();
+        if !self.allow_control_chars && !is_printable(c) && !is_z(c) {
+            return Err(Self::forbidden_char_error(self.mark, c));
+        }
+        Ok(())
+    }
+
+    /// Return an error if the next character is a tab and tabs are forbidden.
+    ///
+    /// The next character must have been looked ahead.
+    #[inline]
+    fn check_tab(&self) -> ScanResult {
+        if self.error_on_tab && self.input.peek() == '\t' {
+            return Err(Self::forbidden_char_error(self.mark, '\t'));
+        }
+        Ok(())
+    }
+
+    /// Create an error for the forbidden control character (or tab) `c` found at `mark`.
+    fn forbidden_char_error(mark: Marker, c: char) -> ScanError {
+        if c == '\t' {
+            return ScanError::new_str(mark, "found a tab, but tabs are forbidden");
+        }
+        ScanError::new(
+            mark,
+            format!(
+                "found control character U+{:04X}, which is not allowed in YAML",
+                c as u32
+            ),
+        )
+    }
+
+    /// Give back a buffer whose allocation may be reused for the contents of a future scalar.
+    ///
+    /// Only the largest buffer is kept.
+    pub fn recycle_scalar_buffer(&mut self, mut buffer: String) {
+        if buffer.capacity() > self.recycled_scalar_buffer.capacity() {
+            buffer.clear();
+            self.recycled_scalar_buffer = buffer;
+        }
+    }
+
+    /// Return an empty buffer for the contents of a scalar, reusing a recycled one if any.
+    #[inline]
+    fn take_scalar_buffer(&mut self) -> String {
+        std::mem::take(&mut self.recycled_scalar_buffer)
+    }
+
     /// Get a copy of the last error that was encountered, if any.
     ///
     /// This does not clear the error state and further calls to [`Self::get_error`] will return (a
@@ -524,31 +1259,58 @@ impl<T: Input> Scanner<T> {
     }
 
     /// Consume the next character. It is assumed the next character is a blank.
+    ///
+    /// This may also be used for other single-byte characters when `self.leading_whitespace` does
+    /// not matter.
     #[inline]
     fn skip_blank(&mut self) {
         self.input.skip();
 
-        self.mark.index += 1;
-        self.mark.col += 1;
+        self.mark.advance(1);
+    }
+
+    /// Return whether `c` is one of the characters set with [`Self::set_extra_whitespace`].
+    #[inline]
+    fn is_extra_whitespace(&self, c: char) -> bool {
+        !self.extra_whitespace.is_empty() && self.extra_whitespace.contains(&c)
+    }
+
+    /// Return whether the next character is extra whitespace separating tokens.
+    ///
+    /// Extra whitespace at the start of a line is not whitespace, as it would be indentation.
+    #[inline]
+    fn next_is_extra_whitespace(&self) -> bool {
+        // Check for an empty set first, so that the next character is only decoded if needed.
+        !self.extra_whitespace.is_empty()
+            && !self.leading_whitespace
+            && self.extra_whitespace.contains(&self.input.peek())
+    }
+
+    /// Consume the next character, which is extra whitespace.
+    #[inline]
+    fn skip_extra_whitespace(&mut self) {
+        let n_bytes = self.input.peek().len_utf8();
+        self.input.skip();
+        self.mark.advance_multibyte(1, n_bytes);
     }
 
     /// Consume the next character. It is assumed the next character is not a blank.
     #[inline]
     fn skip_non_blank(&mut self) {
+        let n_bytes = self.input.peek().len_utf8();
         self.input.skip();
 
-        self.mark.index += 1;
-        self.mark.col += 1;
+        self.mark.advance_multibyte(1, n_bytes);
         self.leading_whitespace = false;
     }
 
-    /// Consume the next characters. It is assumed none of the next characters are blanks.
+    /// Consume the next characters. It is assumed they are single-byte characters and none of
+    /// them is a blank.
     #[inline]
     fn skip_n_non_blank(&mut self, count: usize) {
         self.input.skip_n(count);
 
-        self.mark.index += count;
-        self.mark.col += count;
+        self.mark.advance(count);
         self.leading_whitespace = false;
     }
 
@@ -557,9 +1319,7 @@ impl<T: Input> Scanner<T> {
     fn skip_nl(&mut self) {
         self.input.skip();
 
-        self.mark.index += 1;
-        self.mark.col = 0;
-        self.mark.line += 1;
+        self.mark.advance_line();
         self.leading_whitespace = true;
     }
 
@@ -567,25 +1327,66 @@ impl<T: Input> Scanner<T> {
     #[inline]
     fn skip_linebreak(&mut self) {
         if self.input.next_2_are('\r', '\n') {
+            self.record_line_end();
             // While technically not a blank, this does not matter as `self.leading_whitespace`
             // will be reset by `skip_nl`.
             self.skip_blank();
             self.skip_nl();
         } else if self.input.next_is_break() {
+            self.record_line_end();
             self.skip_nl();
         }
     }
 
+    /// Record the end of the current line if it is too long.
+    ///
+    /// This must be called before consuming a line break.
+    #[inline]
+    fn record_line_end(&mut self) {
+        if self.overlong_line_end.is_none()
+            && self
+                .max_line_length
+                .is_some_and(|max| self.mark.col() > max)
+        {
+            self.overlong_line_end = Some(self.mark);
+        }
+    }
+
     /// Return whether the [`TokenType::StreamStart`] event has been emitted.
     #[inline]
     pub fn stream_started(&self) -> bool {
         self.stream_start_produced
     }
 
-    /// Return whether the [`TokenType::StreamEnd`] event has been emitted.
-    #[inline]
-    pub fn stream_ended(&self) -> bool {
-        self.stream_end_produced
+    /// Return whether the [`TokenType::StreamEnd`] event has been emitted.
+    #[inline]
+    pub fn stream_ended(&self) -> bool {
+        self.stream_end_produced
+    }
+
+    /// Skip whitespace and comments and return whether a document start marker (`---`) follows.
+    ///
+    /// This does not produce any token. It must be called where a token may start.
+    ///
+    /// # Errors
+    /// This function returns an error if a tabulation is encountered where there should not be
+    /// one.
+    pub fn next_token_is_document_start(&mut self) -> Result<bool, ScanError> {
+        self.skip_to_next_token()?;
+        self.input.lookahead(4);
+        Ok(self.mark.col() == 0 && self.input.next_is_document_start())
+    }
+
+    /// Return whether scanning reached the end of the input.
+    ///
+    /// This is the case once the [`TokenType::StreamEnd`] token has been returned, or if an error
+    /// was found after consuming the whole input (e.g.: in an unterminated quoted scalar).
+    pub fn reached_end_of_input(&mut self) -> bool {
+        if self.stream_end_produced {
+            return true;
+        }
+        self.input.lookahead(1);
+        self.error.is_some() && self.input.next_is_z()
     }
 
     /// Get the current position in the input stream.
@@ -594,6 +1395,12 @@ impl<T: Input> Scanner<T> {
         self.mark
     }
 
+    /// Get the input the scanner reads from.
+    #[inline]
+    pub fn input(&self) -> &T {
+        &self.input
+    }
+
     // Read and consume a line break (either `\r`, `\n` or `\r\n`).
     //
     // A `\n` is pushed into `s`.
@@ -615,6 +1422,7 @@ impl<T: Input> Scanner<T> {
         let c = self.input.peek();
         let nc = self.input.peek_nth(1);
         debug_assert!(is_break(c));
+        self.record_line_end();
         if c == '\r' && nc == '\n' {
             self.skip_blank();
         }
@@ -648,6 +1456,9 @@ impl<T: Input> Scanner<T> {
             return Ok(());
         }
         self.skip_to_next_token()?;
+        self.check_line_length()?;
+        self.check_total_bytes()?;
+        self.bom_allowed = false;
 
         debug_print!(
             "  \x1B[38;5;244m\u{2192} fetch_next_token after whitespace {:?} {:?}\x1B[m",
@@ -658,7 +1469,7 @@ impl<T: Input> Scanner<T> {
         self.stale_simple_keys()?;
 
         let mark = self.mark;
-        self.unroll_indent(mark.col as isize);
+        self.unroll_indent(mark.col() as isize);
 
         self.input.lookahead(4);
 
@@ -667,7 +1478,7 @@ impl<T: Input> Scanner<T> {
             return Ok(());
         }
 
-        if self.mark.col == 0 {
+        if self.mark.col() == 0 {
             if self.input.next_char_is('%') {
                 return self.fetch_directive();
             } else if self.input.next_is_document_start() {
@@ -681,27 +1492,31 @@ impl<T: Input> Scanner<T> {
                         "invalid content after document end marker",
                     ));
                 }
+                self.bom_allowed = true;
                 return Ok(());
             }
         }
 
-        if (self.mark.col as isize) < self.indent {
+        if (self.mark.col() as isize) < self.indent {
             return Err(ScanError::new_str(self.mark, "invalid indentation"));
         }
 
         let c = self.input.peek();
         let nc = self.input.peek_nth(1);
+        let nc_is_blankz = is_blank_or_breakz(nc) || self.is_extra_whitespace(nc);
         match c {
             '[' => self.fetch_flow_collection_start(TokenType::FlowSequenceStart),
             '{' => self.fetch_flow_collection_start(TokenType::FlowMappingStart),
             ']' => self.fetch_flow_collection_end(TokenType::FlowSequenceEnd),
             '}' => self.fetch_flow_collection_end(TokenType::FlowMappingEnd),
             ',' => self.fetch_flow_entry(),
-            '-' if is_blank_or_breakz(nc) => self.fetch_block_entry(),
-            '?' if is_blank_or_breakz(nc) => self.fetch_key(),
-            ':' if is_blank_or_breakz(nc) => self.fetch_value(),
+            '-' if nc_is_blankz => self.fetch_block_entry(),
+            '?' if nc_is_blankz => self.fetch_key(),
+            ':' if nc_is_blankz => self.fetch_value(),
             ':' if self.flow_level > 0
-                && (is_flow(nc) || self.mark.index == self.adjacent_value_allowed_at) =>
+                && (self.json_mode
+                    || is_flow(nc)
+                    || self.mark.index() == self.adjacent_value_allowed_at) =>
             {
                 self.fetch_flow_value()
             }
@@ -717,14 +1532,16 @@ impl<T: Input> Scanner<T> {
             '\'' => self.fetch_flow_scalar(true),
             '"' => self.fetch_flow_scalar(false),
             // plain scalar
-            '-' if !is_blank_or_breakz(nc) => self.fetch_plain_scalar(),
-            ':' | '?' if !is_blank_or_breakz(nc) && self.flow_level == 0 => {
-                self.fetch_plain_scalar()
-            }
-            '%' | '@' | '`' => Err(ScanError::new(
+            '-' if !nc_is_blankz => self.fetch_plain_scalar(),
+            ':' | '?' if !nc_is_blankz && self.flow_level == 0 => self.fetch_plain_scalar(),
+            // The `c-reserved` indicators, which the YAML specification keeps for future use.
+            '@' | '`' => Err(ScanError::new(
                 self.mark,
-                format!("unexpected character: `{c}'"),
+                format!(
+                    "the reserved indicator `{c}' cannot start a plain scalar, it must be quoted"
+                ),
             )),
+            '%' => Err(ScanError::new_str(self.mark, "unexpected character: `%'")),
             _ => self.fetch_plain_scalar(),
         }
     }
@@ -738,7 +1555,21 @@ impl<T: Input> Scanner<T> {
         }
 
         if !self.token_available {
-            self.fetch_more_tokens()?;
+            let fetched = self.fetch_more_tokens();
+            // If we reached the end of the input, it may be because it failed to be read rather
+            // than because it ended. The tokens we fetched or the error we found then stem from
+            // the truncation.
+            self.input.lookahead(1);
+            if self.input.next_is_z() {
+                if let Some(source) = self.input.take_error() {
+                    return Err(ScanError::new_with_source(
+                        self.mark,
+                        format!("error reading input: {source}"),
+                        source,
+                    ));
+                }
+            }
+            fetched?;
         }
         let Some(t) = self.tokens.pop_front() else {
             return Err(ScanError::new_str(
@@ -768,7 +1599,7 @@ impl<T: Input> Scanner<T> {
                 // Stale potential keys that we know won't be keys.
                 self.stale_simple_keys()?;
                 // If our next token to be emitted may be a key, fetch more context.
-                for sk in &self.simple_keys {
+                for sk in self.simple_keys.iter() {
                     if sk.possible && sk.token_number == self.tokens_parsed {
                         need_more = true;
                         break;
@@ -786,6 +1617,28 @@ impl<T: Input> Scanner<T> {
         Ok(())
     }
 
+    /// Skip the byte order mark at the current position.
+    ///
+    /// A byte order mark may only start the stream or a line between documents. It is not counted
+    /// in columns, so that the indentation of the line it starts is unaffected.
+    ///
+    /// # Errors
+    /// This function returns an error if the byte order mark is not allowed here.
+    fn skip_bom(&mut self) -> ScanResult {
+        self.input.lookahead(5);
+        let before_document_start = (1..=3).all(|i| self.input.peek_nth(i) == '-')
+            && is_blank_or_breakz(self.input.peek_nth(4));
+        if self.mark.col() != 0 || !(self.bom_allowed || before_document_start) {
+            return Err(ScanError::new_str(
+                self.mark,
+                "a byte order mark may only appear at the start of the stream or of a document",
+            ));
+        }
+        self.input.skip();
+        self.mark.advance_multibyte(0, '\u{FEFF}'.len_utf8());
+        Ok(())
+    }
+
     /// Mark simple keys that can no longer be keys as such.
     ///
     /// This function sets `possible` to `false` to each key that, now we have more context, we
@@ -794,11 +1647,15 @@ impl<T: Input> Scanner<T> {
     /// # Errors
     /// This function returns an error if one of the key we would stale was required to be a key.
     fn stale_simple_keys(&mut self) -> ScanResult {
-        for sk in &mut self.simple_keys {
+        // Simple keys only go stale outside of flow constructs, where there is a single one.
+        if self.flow_level > 0 {
+            return Ok(());
+        }
+        if let Some(sk) = self.simple_keys.last_mut() {
+            // If not in a flow construct, simple keys cannot span multiple lines.
             if sk.possible
-                // If not in a flow construct, simple keys cannot span multiple lines.
-                && self.flow_level == 0
-                    && (sk.mark.line < self.mark.line || sk.mark.index + 1024 < self.mark.index)
+                && (sk.mark.line() < self.mark.line()
+                    || sk.mark.col().saturating_add(1024) < self.mark.col())
             {
                 if sk.required {
                     return Err(ScanError::new_str(self.mark, "simple key expect ':'"));
@@ -815,9 +1672,11 @@ impl<T: Input> Scanner<T> {
     /// This function returns an error if a tabulation is encountered where there should not be
     /// one.
     fn skip_to_next_token(&mut self) -> ScanResult {
+        let start = self.mark;
         loop {
-            // TODO(chenyh) BOM
             match self.input.look_ch() {
+                c if is_bom(c) => self.skip_bom()?,
+                '\t' if self.error_on_tab => self.check_tab()?,
                 // Tabs may not be used as indentation.
                 // "Indentation" only exists as long as a block is started, but does not exist
                 // inside of flow-style constructs. Tabs are allowed as part of leading
@@ -826,7 +1685,7 @@ impl<T: Input> Scanner<T> {
                 // indented. Also, tabs are allowed anywhere in it if it has no content.
                 '\t' if self.is_within_block()
                     && self.leading_whitespace
-                    && (self.mark.col as isize) < self.indent =>
+                    && (self.mark.col() as isize) < self.indent =>
                 {
                     self.skip_ws_to_eol(SkipTabs::Yes)?;
                     // If we have content on that line with a tab, return an error.
@@ -845,23 +1704,28 @@ impl<T: Input> Scanner<T> {
                         self.allow_simple_key();
                     }
                 }
-                '#' => {
-                    let comment_length = self.input.skip_while_non_breakz();
-                    self.mark.index += comment_length;
-                    self.mark.col += comment_length;
-                }
+                '#' => self.skip_comment()?,
+                _ if self.next_is_extra_whitespace() => self.skip_extra_whitespace(),
                 _ => break,
             }
         }
+        self.record_trivia_since(start);
         Ok(())
     }
 
     /// Skip over YAML whitespace (` `, `\n`, `\r`).
     ///
+    /// Tabs are skipped as well if they are on the same line as the indicator preceding the
+    /// whitespace. See [`Self::check_tab_separated_content`] for the restrictions that apply to
+    /// the content following them.
+    ///
     /// # Errors
     /// This function returns an error if no whitespace was found.
     fn skip_yaml_whitespace(&mut self) -> ScanResult {
         let mut need_whitespace = true;
+        let mut found_tabs = false;
+        let mut crossed_line = false;
+        let start = self.mark;
         loop {
             match self.input.look_ch() {
                 ' ' => {
@@ -869,6 +1733,13 @@ impl<T: Input> Scanner<T> {
 
                     need_whitespace = false;
                 }
+                '\t' if !crossed_line => {
+                    self.check_tab()?;
+                    self.skip_blank();
+
+                    found_tabs = true;
+                    need_whitespace = false;
+                }
                 '\n' | '\r' => {
                     self.input.lookahead(2);
                     self.skip_linebreak();
@@ -876,28 +1747,130 @@ impl<T: Input> Scanner<T> {
                         self.allow_simple_key();
                     }
                     need_whitespace = false;
+                    crossed_line = true;
                 }
-                '#' => {
-                    let comment_length = self.input.skip_while_non_breakz();
-                    self.mark.index += comment_length;
-                    self.mark.col += comment_length;
+                '#' => self.skip_comment()?,
+                _ if self.next_is_extra_whitespace() => {
+                    self.skip_extra_whitespace();
+
+                    need_whitespace = false;
                 }
                 _ => break,
             }
         }
+        self.record_trivia_since(start);
 
         if need_whitespace {
             Err(ScanError::new_str(self.mark(), "expected whitespace"))
+        } else if found_tabs && !crossed_line {
+            self.check_tab_separated_content()
         } else {
             Ok(())
         }
     }
 
+    /// Check the content following a block indicator (`-`, `?`, `:`) it is separated from by tabs.
+    ///
+    /// Tabs are valid separation between a block indicator and its content, as in `-\tfoo` or
+    /// `key:\tvalue`. However, tabs cannot be used as indentation and the content therefore cannot
+    /// be a block collection starting on the same line. This function errors if another block
+    /// indicator follows and records the position of the content so that [`Self::fetch_value`]
+    /// can reject it if it turns out to be an implicit key.
+    ///
+    /// This function must be called after the separation has been skipped. It does nothing in
+    /// flow contexts, where there is no indentation.
+    fn check_tab_separated_content(&mut self) -> ScanResult {
+        if self.flow_level > 0 {
+            return Ok(());
+        }
+        self.input.lookahead(2);
+        if self.input.next_is_breakz() {
+            return Ok(());
+        }
+        if matches!(self.input.peek(), '-' | '?' | ':')
+            && is_blank_or_breakz(self.input.peek_nth(1))
+        {
+            return Err(ScanError::new_str(
+                self.mark,
+                "block collections cannot be started after a tab on the same line",
+            ));
+        }
+        self.tab_separated_content_at = Some(self.mark.index());
+        Ok(())
+    }
+
+    /// Skip whitespace and comments at most up to the end of the line.
     fn skip_ws_to_eol(&mut self, skip_tabs: SkipTabs) -> Result<SkipTabs, ScanError> {
-        let (n_bytes, result) = self.input.skip_ws_to_eol(skip_tabs);
-        self.mark.col += n_bytes;
-        self.mark.index += n_bytes;
-        result.map_err(|msg| ScanError::new_str(self.mark, msg))
+        let start = self.mark;
+        // Stop at tabs to report them, if they are forbidden.
+        let skip_tabs = if self.error_on_tab {
+            SkipTabs::No
+        } else {
+            skip_tabs
+        };
+        let (n_blanks, result) = self.input.skip_ws_to_eol(skip_tabs);
+        self.mark.advance(n_blanks);
+        self.check_tab()?;
+        let mut result = result.map_err(|msg| ScanError::new_str(self.mark, msg))?;
+        // Extra whitespace counts as spaces, and may separate a comment.
+        while self.next_is_extra_whitespace() {
+            self.skip_extra_whitespace();
+            if self.input.look_ch() == '#' {
+                result = SkipTabs::Result(result.found_tabs(), true);
+                break;
+            }
+            let (n_blanks, next) = self.input.skip_ws_to_eol(skip_tabs);
+            self.mark.advance(n_blanks);
+            let found_tabs = next.is_ok_and(SkipTabs::found_tabs);
+            result = SkipTabs::Result(result.found_tabs() || found_tabs, true);
+        }
+        if self.input.look_ch() == '#' {
+            self.skip_comment()?;
+        }
+        self.record_trivia_since(start);
+        Ok(result)
+    }
+
+    /// Skip characters up to the end of the line, e.g. a comment.
+    ///
+    /// # Errors
+    /// Return an error if a forbidden control character or tab is found.
+    fn skip_comment(&mut self) -> ScanResult {
+        if self.error_on_tab || !self.allow_control_chars {
+            // Skip printable characters in bulk and only look at the others one by one.
+            loop {
+                let (n_chars, n_bytes) = self.input.skip_while_printable();
+                self.mark.advance_multibyte(n_chars, n_bytes);
+                let c = self.input.look_ch();
+                if is_breakz(c) {
+                    return Ok(());
+                }
+                if self.is_forbidden_char(c) {
+                    return Err(Self::forbidden_char_error(self.mark, c));
+                }
+                self.input.skip();
+                self.mark.advance_multibyte(1, c.len_utf8());
+            }
+        }
+        let (n_chars, n_bytes) = self.input.skip_while_non_breakz();
+        self.mark.advance_multibyte(n_chars, n_bytes);
+        Ok(())
+    }
+
+    /// Skip blanks, e.g. between the parts of a directive.
+    ///
+    /// # Errors
+    /// Return an error if tabs are forbidden and one is found.
+    fn skip_while_blank(&mut self) -> ScanResult {
+        if self.error_on_tab {
+            while self.input.look_ch() == ' ' {
+                self.skip_blank();
+            }
+            return self.check_tab();
+        }
+        let n_blanks = self.input.skip_while_blank();
+        self.mark.advance(n_blanks);
+        Ok(())
     }
 
     fn fetch_stream_start(&mut self) {
@@ -916,12 +1889,12 @@ impl<T: Input> Scanner<T> {
         // force new line
         if self.mark.col != 0 {
             self.mark.col = 0;
-            self.mark.line += 1;
+            self.mark.line = self.mark.line.saturating_add(1);
         }
 
         // If the stream ended, we won't have more context. We can stall all the simple keys we
         // had. If one was required, however, that was an error and we must propagate it.
-        for sk in &mut self.simple_keys {
+        for sk in self.simple_keys.iter_mut() {
             if sk.required && sk.possible {
                 return Err(ScanError::new_str(self.mark, "simple key expected"));
             }
@@ -960,9 +1933,7 @@ impl<T: Input> Scanner<T> {
             // XXX This should be a warning instead of an error
             _ => {
                 // skip current line
-                let line_len = self.input.skip_while_non_breakz();
-                self.mark.index += line_len;
-                self.mark.col += line_len;
+                self.skip_comment()?;
                 // XXX return an empty TagDirective token
                 Token(
                     Span::new(start_mark, self.mark),
@@ -980,6 +1951,7 @@ impl<T: Input> Scanner<T> {
             self.skip_linebreak();
             Ok(tok)
         } else {
+            self.check_control_char()?;
             Err(ScanError::new_str(
                 start_mark,
                 "while scanning a directive, did not find expected comment or line break",
@@ -988,13 +1960,12 @@ impl<T: Input> Scanner<T> {
     }
 
     fn scan_version_directive_value(&mut self, mark: &Marker) -> Result<Token, ScanError> {
-        let n_blanks = self.input.skip_while_blank();
-        self.mark.index += n_blanks;
-        self.mark.col += n_blanks;
+        self.skip_while_blank()?;
 
         let major = self.scan_version_directive_number(mark)?;
 
         if self.input.peek() != '.' {
+            self.check_control_char()?;
             return Err(ScanError::new_str(
                 *mark,
                 "while scanning a YAML directive, did not find expected digit or '.' character",
@@ -1015,8 +1986,7 @@ impl<T: Input> Scanner<T> {
         let mut string = String::new();
 
         let n_chars = self.input.fetch_while_is_alpha(&mut string);
-        self.mark.index += n_chars;
-        self.mark.col += n_chars;
+        self.mark.advance(n_chars);
 
         if string.is_empty() {
             return Err(ScanError::new_str(
@@ -1026,6 +1996,7 @@ impl<T: Input> Scanner<T> {
         }
 
         if !is_blank_or_breakz(self.input.peek()) {
+            self.check_control_char()?;
             return Err(ScanError::new_str(
                 start_mark,
                 "while scanning a directive, found unexpected non-alphabetical character",
@@ -1061,16 +2032,26 @@ impl<T: Input> Scanner<T> {
     }
 
     fn scan_tag_directive_value(&mut self, mark: &Marker) -> Result<Token, ScanError> {
-        let n_blanks = self.input.skip_while_blank();
-        self.mark.index += n_blanks;
-        self.mark.col += n_blanks;
+        self.skip_while_blank()?;
 
+        // A handle is `!`, `!!` or `!name!`, followed by whitespace.
+        if self.input.look_ch() != '!' {
+            return Err(Self::malformed_tag_handle_error(*mark));
+        }
         let handle = self.scan_tag_handle(true, mark)?;
+        if !is_blank_or_breakz(self.input.look_ch()) {
+            self.check_control_char()?;
+            return Err(Self::malformed_tag_handle_error(*mark));
+        }
 
-        let n_blanks = self.input.skip_while_blank();
-        self.mark.index += n_blanks;
-        self.mark.col += n_blanks;
+        self.skip_while_blank()?;
 
+        if is_breakz(self.input.look_ch()) || self.input.peek() == '#' {
+            return Err(ScanError::new_str(
+                *mark,
+                "while parsing a tag directive, did not find expected tag prefix",
+            ));
+        }
         let prefix = self.scan_tag_prefix(mark)?;
 
         self.input.lookahead(1);
@@ -1081,6 +2062,7 @@ impl<T: Input> Scanner<T> {
                 TokenType::TagDirective(handle, prefix),
             ))
         } else {
+            self.check_control_char()?;
             Err(ScanError::new_str(
                 *mark,
                 "while scanning TAG, did not find expected whitespace or line break",
@@ -1089,7 +2071,7 @@ impl<T: Input> Scanner<T> {
     }
 
     fn fetch_tag(&mut self) -> ScanResult {
-        self.save_simple_key();
+        self.save_simple_key()?;
         self.disallow_simple_key();
 
         let tok = self.scan_tag()?;
@@ -1137,6 +2119,7 @@ impl<T: Input> Scanner<T> {
                 TokenType::Tag(handle, suffix),
             ))
         } else {
+            self.check_control_char()?;
             Err(ScanError::new_str(
                 start_mark,
                 "while scanning a tag, did not find expected whitespace or line break",
@@ -1157,8 +2140,7 @@ impl<T: Input> Scanner<T> {
         self.skip_non_blank();
 
         let n_chars = self.input.fetch_while_is_alpha(&mut string);
-        self.mark.index += n_chars;
-        self.mark.col += n_chars;
+        self.mark.advance(n_chars);
 
         // Check if the trailing character is '!' and copy it.
         if self.input.peek() == '!' {
@@ -1168,14 +2150,21 @@ impl<T: Input> Scanner<T> {
             // It's either the '!' tag or not really a tag handle.  If it's a %TAG
             // directive, it's an error.  If it's a tag token, it must be a part of
             // URI.
-            return Err(ScanError::new_str(
-                *mark,
-                "while parsing a tag directive, did not find expected '!'",
-            ));
+            self.check_control_char()?;
+            return Err(Self::malformed_tag_handle_error(*mark));
         }
         Ok(string)
     }
 
+    /// Return the error for a `%TAG` directive starting at `mark` whose handle is not `!`, `!!`
+    /// or `!name!`.
+    fn malformed_tag_handle_error(mark: Marker) -> ScanError {
+        ScanError::new_str(
+            mark,
+            "while parsing a tag directive, found malformed tag handle (expected `!', `!!' or `!name!')",
+        )
+    }
+
     /// Scan for a tag prefix (6.8.2.2).
     ///
     /// There are 2 kinds of tag prefixes:
@@ -1234,6 +2223,7 @@ impl<T: Input> Scanner<T> {
         }
 
         if self.input.peek() != '>' {
+            self.check_control_char()?;
             return Err(ScanError::new_str(
                 *start_mark,
                 "while scanning a verbatim tag, did not find the expected '>'",
@@ -1341,7 +2331,7 @@ impl<T: Input> Scanner<T> {
     }
 
     fn fetch_anchor(&mut self, alias: bool) -> ScanResult {
-        self.save_simple_key();
+        self.save_simple_key()?;
         self.disallow_simple_key();
 
         let tok = self.scan_anchor(alias)?;
@@ -1357,7 +2347,11 @@ impl<T: Input> Scanner<T> {
 
         self.skip_non_blank();
         while is_anchor_char(self.input.look_ch()) {
-            string.push(self.input.peek());
+            let c = self.input.peek();
+            if self.is_forbidden_char(c) {
+                return Err(Self::forbidden_char_error(self.mark, c));
+            }
+            string.push(c);
             self.skip_non_blank();
         }
 
@@ -1375,10 +2369,15 @@ impl<T: Input> Scanner<T> {
 
     fn fetch_flow_collection_start(&mut self, tok: TokenType) -> ScanResult {
         // The indicators '[' and '{' may start a simple key.
-        self.save_simple_key();
+        self.save_simple_key()?;
 
         self.roll_one_col_indent();
-        self.increase_flow_level()?;
+        let indicator = if tok == TokenType::FlowMappingStart {
+            '{'
+        } else {
+            '['
+        };
+        self.increase_flow_level(indicator)?;
 
         self.allow_simple_key();
 
@@ -1421,7 +2420,7 @@ impl<T: Input> Scanner<T> {
         // - [ {a: b}:value ]
         // ```
         if self.flow_level > 0 {
-            self.adjacent_value_allowed_at = self.mark.index;
+            self.adjacent_value_allowed_at = self.mark.index();
         }
 
         self.tokens
@@ -1447,12 +2446,23 @@ impl<T: Input> Scanner<T> {
         Ok(())
     }
 
-    fn increase_flow_level(&mut self) -> ScanResult {
+    /// Enter the flow collection opened by `indicator` (`[` or `{`) at the current position.
+    fn increase_flow_level(&mut self, indicator: char) -> ScanResult {
+        if self
+            .max_flow_depth
+            .is_some_and(|max| usize::from(self.flow_level) >= max)
+        {
+            return Err(ScanError::new_str(
+                self.mark,
+                "flow collections exceed the maximum nesting depth",
+            ));
+        }
         self.simple_keys.push(SimpleKey::new(Marker::new(0, 0, 0)));
         self.flow_level = self
             .flow_level
             .checked_add(1)
             .ok_or_else(|| ScanError::new_str(self.mark, "recursion limit exceeded"))?;
+        self.flow_collection_starts.push((indicator, self.mark));
         Ok(())
     }
 
@@ -1460,9 +2470,52 @@ impl<T: Input> Scanner<T> {
         if self.flow_level > 0 {
             self.flow_level -= 1;
             self.simple_keys.pop().unwrap();
+            self.flow_collection_starts.pop();
+        }
+    }
+
+    /// Create an error for the block indicator `found` at `mark`, which is not allowed there.
+    ///
+    /// If it follows another block indicator on the same line, that indicator is reported as the
+    /// construct it conflicts with.
+    fn misplaced_block_indicator(&self, found: char, mark: Marker, info: &str) -> ScanError {
+        let error = ScanError::new_str(mark, info);
+        match self.last_block_indicator {
+            Some((other, other_mark)) if other_mark.line() == mark.line() => {
+                error.with_conflict(Conflict {
+                    found,
+                    other,
+                    other_mark,
+                })
+            }
+            _ => error,
         }
     }
 
+    /// Create an error for the block indicator `found` at `mark`, found at the start of a line less
+    /// indented than the innermost flow collection, which is thus not closed.
+    ///
+    /// The opening indicator of the flow collection is reported as the construct it conflicts with.
+    /// If it is unknown, the error has no conflict.
+    fn unclosed_flow_error(&self, found: char, mark: Marker) -> ScanError {
+        let Some(&(other, other_mark)) = self.flow_collection_starts.last() else {
+            return ScanError::new_str(mark, "unclosed flow collection");
+        };
+        let kind = if other == '[' { "sequence" } else { "mapping" };
+        ScanError::new(
+            mark,
+            format!(
+                "flow {kind} opened at line {} is not closed before block content",
+                other_mark.line()
+            ),
+        )
+        .with_conflict(Conflict {
+            found,
+            other,
+            other_mark,
+        })
+    }
+
     /// Push the `Block*` token(s) and skip over the `-`.
     ///
     /// Add an indentation level and push a `BlockSequenceStart` token if needed, then push a
@@ -1470,15 +2523,24 @@ impl<T: Input> Scanner<T> {
     /// This function only skips over the `-` and does not fetch the entry value.
     fn fetch_block_entry(&mut self) -> ScanResult {
         if self.flow_level > 0 {
+            if (self.mark.col() as isize) <= self.indent {
+                return Err(self.unclosed_flow_error('-', self.mark));
+            }
             // - * only allowed in block
-            return Err(ScanError::new_str(
-                self.mark,
-                r#""-" is only valid inside a block"#,
-            ));
+            let error = ScanError::new_str(self.mark, r#""-" is only valid inside a block"#);
+            return Err(match self.flow_collection_starts.last() {
+                Some(&(other, other_mark)) => error.with_conflict(Conflict {
+                    found: '-',
+                    other,
+                    other_mark,
+                }),
+                None => error,
+            });
         }
         // Check if we are allowed to start a new entry.
         if !self.simple_key_allowed {
-            return Err(ScanError::new_str(
+            return Err(self.misplaced_block_indicator(
+                '-',
                 self.mark,
                 "block sequence entries are not allowed in this context",
             ));
@@ -1486,7 +2548,7 @@ impl<T: Input> Scanner<T> {
 
         // ???, fixes test G9HC.
         if let Some(Token(span, TokenType::Anchor(..) | TokenType::Tag(..))) = self.tokens.back() {
-            if self.mark.col == 0 && span.start.col == 0 && self.indent > -1 {
+            if self.mark.col() == 0 && span.start.col() == 0 && self.indent > -1 {
                 return Err(ScanError::new_str(
                     span.start,
                     "invalid indentation for anchor",
@@ -1496,18 +2558,13 @@ impl<T: Input> Scanner<T> {
 
         // Skip over the `-`.
         let mark = self.mark;
+        self.last_block_indicator = Some(('-', mark));
         self.skip_non_blank();
 
         // generate BLOCK-SEQUENCE-START if indented
-        self.roll_indent(mark.col, None, TokenType::BlockSequenceStart, mark);
-        let found_tabs = self.skip_ws_to_eol(SkipTabs::Yes)?.found_tabs();
-        self.input.lookahead(2);
-        if found_tabs && self.input.next_char_is('-') && is_blank_or_breakz(self.input.peek_nth(1))
-        {
-            return Err(ScanError::new_str(
-                self.mark,
-                "'-' must be followed by a valid YAML whitespace",
-            ));
+        self.roll_indent(mark.col(), None, TokenType::BlockSequenceStart, mark);
+        if self.skip_ws_to_eol(SkipTabs::Yes)?.found_tabs() {
+            self.check_tab_separated_content()?;
         }
 
         self.skip_ws_to_eol(SkipTabs::No)?;
@@ -1519,8 +2576,9 @@ impl<T: Input> Scanner<T> {
         self.remove_simple_key()?;
         self.allow_simple_key();
 
+        // The token spans from the `-` to its content.
         self.tokens
-            .push_back(Token(Span::empty(self.mark), TokenType::BlockEntry));
+            .push_back(Token(Span::new(mark, self.mark), TokenType::BlockEntry));
 
         Ok(())
     }
@@ -1529,6 +2587,8 @@ impl<T: Input> Scanner<T> {
         self.unroll_indent(-1);
         self.remove_simple_key()?;
         self.disallow_simple_key();
+        // Errors in the next document must not point at indicators of this one.
+        self.last_block_indicator = None;
 
         let mark = self.mark;
 
@@ -1539,9 +2599,11 @@ impl<T: Input> Scanner<T> {
     }
 
     fn fetch_block_scalar(&mut self, literal: bool) -> ScanResult {
-        self.save_simple_key();
+        self.save_simple_key()?;
         self.allow_simple_key();
         let tok = self.scan_block_scalar(literal)?;
+        // Lines following the content of the scalar are trivia.
+        self.record_trivia_since(tok.0.end);
 
         self.tokens.push_back(tok);
         Ok(())
@@ -1561,7 +2623,7 @@ impl<T: Input> Scanner<T> {
             TScalarStyle::Folded
         };
 
-        let mut string = String::new();
+        let mut string = self.take_scalar_buffer();
         let mut leading_break = String::new();
         let mut trailing_breaks = String::new();
         let mut chomping_break = String::new();
@@ -1609,6 +2671,7 @@ impl<T: Input> Scanner<T> {
             }
         }
 
+        let header_end = self.mark;
         self.skip_ws_to_eol(SkipTabs::Yes)?;
 
         // Check if we are at the end of the line.
@@ -1642,9 +2705,19 @@ impl<T: Input> Scanner<T> {
 
         // Scan the leading line breaks and determine the indentation level if needed.
         if indent == 0 {
-            self.skip_block_scalar_first_line_indent(&mut indent, &mut trailing_breaks);
+            self.skip_block_scalar_first_line_indent(&mut indent, &mut trailing_breaks)?;
         } else {
-            self.skip_block_scalar_indent(indent, &mut trailing_breaks);
+            self.skip_block_scalar_indent(indent, &mut trailing_breaks)?;
+        }
+
+        if self.record_block_scalar_headers {
+            self.block_scalar_headers.push(BlockScalarHeader {
+                span: Span::new(start_mark, header_end),
+                #[allow(clippy::cast_possible_truncation)]
+                indentation_indicator: (increment > 0).then_some(increment as u8),
+                chomping,
+                indent,
+            });
         }
 
         // We have an end-of-stream with no content, e.g.:
@@ -1652,27 +2725,30 @@ impl<T: Input> Scanner<T> {
         // - |+
         // ```
         if self.input.next_is_z() {
-            let contents = match chomping {
+            let mut contents = match chomping {
                 // We strip trailing linebreaks. Nothing remain.
                 Chomping::Strip => String::new(),
                 // There was no newline after the chomping indicator.
-                _ if self.mark.line == start_mark.line() => String::new(),
-                // We clip lines, and there was a newline after the chomping indicator.
-                // All other breaks are ignored.
-                Chomping::Clip => chomping_break,
+                _ if self.mark.line() == start_mark.line() => String::new(),
+                // We clip lines. Without content, there is no final line break to keep and all
+                // other breaks are ignored.
+                Chomping::Clip => String::new(),
                 // We keep lines. There was a newline after the chomping indicator but nothing
                 // else.
                 Chomping::Keep if trailing_breaks.is_empty() => chomping_break,
                 // Otherwise, the newline after chomping is ignored.
                 Chomping::Keep => trailing_breaks,
             };
+            if self.flush_block_scalar(&mut contents, 0) {
+                self.streamed_block_scalars.push_back(start_mark);
+            }
             return Ok(Token(
                 Span::new(start_mark, self.mark),
                 TokenType::Scalar(style, contents),
             ));
         }
 
-        if self.mark.col < indent && (self.mark.col as isize) > self.indent {
+        if self.mark.col() < indent && (self.mark.col() as isize) > self.indent {
             return Err(ScanError::new_str(
                 self.mark,
                 "wrongly indented line in block scalar",
@@ -1681,7 +2757,8 @@ impl<T: Input> Scanner<T> {
 
         let mut line_buffer = String::with_capacity(100);
         let start_mark = self.mark;
-        while self.mark.col == indent && !self.input.next_is_z() {
+        let mut streamed = false;
+        while self.mark.col() == indent && !self.input.next_is_z() {
             if indent == 0 {
                 self.input.lookahead(4);
                 if self.input.next_is_document_end() {
@@ -1706,7 +2783,8 @@ impl<T: Input> Scanner<T> {
 
             leading_blank = self.input.next_is_blank();
 
-            self.scan_block_scalar_content_line(&mut string, &mut line_buffer);
+            self.scan_block_scalar_content_line(&mut string, &mut line_buffer)?;
+            streamed |= self.flush_block_scalar(&mut string, BLOCK_SCALAR_CHUNK_LEN);
 
             // break on EOF
             self.input.lookahead(2);
@@ -1717,7 +2795,7 @@ impl<T: Input> Scanner<T> {
             self.read_break(&mut leading_break);
 
             // Eat the following indentation spaces and line breaks.
-            self.skip_block_scalar_indent(indent, &mut trailing_breaks);
+            self.skip_block_scalar_indent(indent, &mut trailing_breaks)?;
         }
 
         // Chomp the tail.
@@ -1725,8 +2803,12 @@ impl<T: Input> Scanner<T> {
             string.push_str(&leading_break);
             // If we had reached an eof but the last character wasn't an end-of-line, check if the
             // last line was indented at least as the rest of the scalar, then we need to consider
-            // there is a newline.
-            if self.input.next_is_z() && self.mark.col >= indent.max(1) {
+            // there is a newline. When clipping, this only applies if the last line has content:
+            // an empty last line (e.g.: ` `) follows a line break we already kept.
+            if self.input.next_is_z()
+                && self.mark.col() >= indent.max(1)
+                && (chomping == Chomping::Keep || leading_break.is_empty())
+            {
                 string.push('\n');
             }
         }
@@ -1734,6 +2816,10 @@ impl<T: Input> Scanner<T> {
         if chomping == Chomping::Keep {
             string.push_str(&trailing_breaks);
         }
+        streamed |= self.flush_block_scalar(&mut string, 0);
+        if streamed {
+            self.streamed_block_scalars.push_back(start_mark);
+        }
 
         Ok(Token(
             Span::new(start_mark, self.mark),
@@ -1750,16 +2836,29 @@ impl<T: Input> Scanner<T> {
     ///
     /// This function assumed the first character to read is the first content character in the
     /// line. This function does not consume the line break character(s) after the line.
-    fn scan_block_scalar_content_line(&mut self, string: &mut String, line_buffer: &mut String) {
+    ///
+    /// # Errors
+    /// Return an error if a forbidden control character is found.
+    fn scan_block_scalar_content_line(
+        &mut self,
+        string: &mut String,
+        line_buffer: &mut String,
+    ) -> ScanResult {
         // Start by evaluating characters in the buffer.
         while !self.input.buf_is_empty() && !self.input.next_is_breakz() {
-            string.push(self.input.peek());
+            let c = self.input.peek();
+            if self.is_forbidden_char(c) {
+                return Err(Self::forbidden_char_error(self.mark, c));
+            }
+            string.push(c);
             // We may technically skip non-blank characters. However, the only distinction is
             // to determine what is leading whitespace and what is not. Here, we read the
             // contents of the line until either eof or a linebreak. We know we will not read
             // `self.leading_whitespace` until the end of the line, where it will be reset.
-            // This allows us to call a slightly less expensive function.
-            self.skip_blank();
+            // This allows us to skip characters without updating it.
+            self.input.skip();
+            self.mark.advance_multibyte(1, c.len_utf8());
+            self.check_limits_at(self.mark)?;
         }
 
         // All characters that were in the buffer were consumed. We need to check if more
@@ -1770,36 +2869,50 @@ impl<T: Input> Scanner<T> {
             // characters are appended here as their real size (1B for ascii, or up to 4 bytes for
             // UTF-8). We can then use the internal `line_buffer` `Vec` to push data into `string`
             // (using `String::push_str`).
+            let mut mark = self.mark;
             while let Some(c) = self.input.raw_read_non_breakz_ch() {
+                if self.is_forbidden_char(c) {
+                    line_buffer.clear();
+                    return Err(Self::forbidden_char_error(mark, c));
+                }
                 line_buffer.push(c);
+                mark.advance_multibyte(1, c.len_utf8());
+                if let Err(e) = self.check_limits_at(mark) {
+                    line_buffer.clear();
+                    return Err(e);
+                }
             }
 
             // We need to manually update our position; we haven't called a `skip` function.
-            self.mark.col += line_buffer.len();
-            self.mark.index += line_buffer.len();
+            self.mark = mark;
 
             // We can now append our bytes to our `string`.
-            string.reserve(line_buffer.as_bytes().len());
+            string.reserve(line_buffer.len());
             string.push_str(line_buffer);
             // This clears the _contents_ without touching the _capacity_.
             line_buffer.clear();
         }
+        Ok(())
     }
 
     /// Skip the block scalar indentation and empty lines.
-    fn skip_block_scalar_indent(&mut self, indent: usize, breaks: &mut String) {
+    ///
+    /// # Errors
+    /// Return an error if the empty lines exceed the limits set on the scanner.
+    fn skip_block_scalar_indent(&mut self, indent: usize, breaks: &mut String) -> ScanResult {
         loop {
             // Consume all spaces. Tabs cannot be used as indentation.
             if indent < self.input.bufmaxlen() - 2 {
                 self.input.lookahead(self.input.bufmaxlen());
-                while self.mark.col < indent && self.input.peek() == ' ' {
+                while self.mark.col() < indent && self.input.peek() == ' ' {
                     self.skip_blank();
                 }
             } else {
+                self.input.lookahead_exceeded(indent + 2);
                 loop {
                     self.input.lookahead(self.input.bufmaxlen());
                     while !self.input.buf_is_empty()
-                        && self.mark.col < indent
+                        && self.mark.col() < indent
                         && self.input.peek() == ' '
                     {
                         self.skip_blank();
@@ -1807,7 +2920,7 @@ impl<T: Input> Scanner<T> {
                     // If we reached our indent, we can break. We must also break if we have
                     // reached content or EOF; that is, the buffer is not empty and the next
                     // character is not a space.
-                    if self.mark.col == indent
+                    if self.mark.col() == indent
                         || (!self.input.buf_is_empty() && self.input.peek() != ' ')
                     {
                         break;
@@ -1819,33 +2932,44 @@ impl<T: Input> Scanner<T> {
             // If our current line is empty, skip over the break and continue looping.
             if self.input.next_is_break() {
                 self.read_break(breaks);
+                self.check_limits_at(self.mark)?;
             } else {
                 // Otherwise, we have a content line. Return control.
                 break;
             }
         }
+        Ok(())
     }
 
     /// Determine the indentation level for a block scalar from the first line of its contents.
     ///
     /// The function skips over whitespace-only lines and sets `indent` to the the longest
     /// whitespace line that was encountered.
-    fn skip_block_scalar_first_line_indent(&mut self, indent: &mut usize, breaks: &mut String) {
+    ///
+    /// # Errors
+    /// Return an error if the whitespace lines exceed the limits set on the scanner.
+    fn skip_block_scalar_first_line_indent(
+        &mut self,
+        indent: &mut usize,
+        breaks: &mut String,
+    ) -> ScanResult {
         let mut max_indent = 0;
         loop {
             // Consume all spaces. Tabs cannot be used as indentation.
             while self.input.look_ch() == ' ' {
                 self.skip_blank();
+                self.check_limits_at(self.mark)?;
             }
 
-            if self.mark.col > max_indent {
-                max_indent = self.mark.col;
+            if self.mark.col() > max_indent {
+                max_indent = self.mark.col();
             }
 
             if self.input.next_is_break() {
                 // If our current line is empty, skip over the break and continue looping.
                 self.input.lookahead(2);
                 self.read_break(breaks);
+                self.check_limits_at(self.mark)?;
             } else {
                 // Otherwise, we have a content line. Return control.
                 break;
@@ -1864,10 +2988,11 @@ impl<T: Input> Scanner<T> {
         if self.indent > 0 {
             *indent = (*indent).max(1);
         }
+        Ok(())
     }
 
     fn fetch_flow_scalar(&mut self, single: bool) -> ScanResult {
-        self.save_simple_key();
+        self.save_simple_key()?;
         self.disallow_simple_key();
 
         let tok = self.scan_flow_scalar(single)?;
@@ -1875,7 +3000,7 @@ impl<T: Input> Scanner<T> {
         // From spec: To ensure JSON compatibility, if a key inside a flow mapping is JSON-like,
         // YAML allows the following value to be specified adjacent to the “:”.
         self.skip_to_next_token()?;
-        self.adjacent_value_allowed_at = self.mark.index;
+        self.adjacent_value_allowed_at = self.mark.index();
 
         self.tokens.push_back(tok);
         Ok(())
@@ -1885,7 +3010,7 @@ impl<T: Input> Scanner<T> {
     fn scan_flow_scalar(&mut self, single: bool) -> Result<Token, ScanError> {
         let start_mark = self.mark;
 
-        let mut string = String::new();
+        let mut string = self.take_scalar_buffer();
         let mut leading_break = String::new();
         let mut trailing_breaks = String::new();
         let mut whitespaces = String::new();
@@ -1898,7 +3023,7 @@ impl<T: Input> Scanner<T> {
             /* Check for a document indicator. */
             self.input.lookahead(4);
 
-            if self.mark.col == 0 && self.input.next_is_document_indicator() {
+            if self.mark.col() == 0 && self.input.next_is_document_indicator() {
                 return Err(ScanError::new_str(
                     start_mark,
                     "while scanning a quoted scalar, found unexpected document indicator",
@@ -1912,7 +3037,7 @@ impl<T: Input> Scanner<T> {
                 ));
             }
 
-            if (self.mark.col as isize) < self.indent {
+            if (self.mark.col() as isize) < self.indent {
                 return Err(ScanError::new_str(
                     start_mark,
                     "invalid indentation in quoted scalar",
@@ -1924,7 +3049,6 @@ impl<T: Input> Scanner<T> {
                 single,
                 &mut string,
                 &mut leading_blanks,
-                &start_mark,
             )?;
 
             match self.input.look_ch() {
@@ -1936,9 +3060,10 @@ impl<T: Input> Scanner<T> {
             // Consume blank characters.
             while self.input.next_is_blank() || self.input.next_is_break() {
                 if self.input.next_is_blank() {
+                    self.check_tab()?;
                     // Consume a space or a tab character.
                     if leading_blanks {
-                        if self.input.peek() == '\t' && (self.mark.col as isize) < self.indent {
+                        if self.input.peek() == '\t' && (self.mark.col() as isize) < self.indent {
                             return Err(ScanError::new_str(
                                 self.mark,
                                 "tab cannot be used as indentation",
@@ -1960,6 +3085,7 @@ impl<T: Input> Scanner<T> {
                         leading_blanks = true;
                     }
                 }
+                self.check_limits_at(self.mark)?;
                 self.input.lookahead(1);
             }
 
@@ -1996,7 +3122,7 @@ impl<T: Input> Scanner<T> {
             c if is_breakz(c) => {}
             // ':' can be encountered if our scalar is a key.
             // Outside of flow contexts, keys cannot span multiple lines
-            ':' if self.flow_level == 0 && start_mark.line == self.mark.line => {}
+            ':' if self.flow_level == 0 && start_mark.line() == self.mark.line() => {}
             // Inside a flow context, this is allowed.
             ':' if self.flow_level > 0 => {}
             _ => {
@@ -2031,7 +3157,6 @@ impl<T: Input> Scanner<T> {
         single: bool,
         string: &mut String,
         leading_blanks: &mut bool,
-        start_mark: &Marker,
     ) -> Result<(), ScanError> {
         self.input.lookahead(2);
         while !is_blank_or_breakz(self.input.peek()) {
@@ -2052,15 +3177,24 @@ impl<T: Input> Scanner<T> {
                     *leading_blanks = true;
                     break;
                 }
+                // An escaped tab is still a tab.
+                '\\' if !single && self.error_on_tab && self.input.peek_nth(1) == '\t' => {
+                    self.skip_non_blank();
+                    return Err(Self::forbidden_char_error(self.mark, '\t'));
+                }
                 // Check for an escape sequence.
                 '\\' if !single => {
-                    string.push(self.resolve_flow_scalar_escape_sequence(start_mark)?);
+                    string.push(self.resolve_flow_scalar_escape_sequence()?);
+                }
+                c if self.is_forbidden_char(c) => {
+                    return Err(Self::forbidden_char_error(self.mark, c));
                 }
                 c => {
                     string.push(c);
                     self.skip_non_blank();
                 }
             }
+            self.check_limits_at(self.mark)?;
             self.input.lookahead(2);
         }
         Ok(())
@@ -2071,11 +3205,10 @@ impl<T: Input> Scanner<T> {
     /// `self.input.peek()` must point to the `\` starting the escape sequence.
     ///
     /// # Errors
-    /// Return an error if an invalid escape sequence is found.
-    fn resolve_flow_scalar_escape_sequence(
-        &mut self,
-        start_mark: &Marker,
-    ) -> Result<char, ScanError> {
+    /// Return an error if an invalid or truncated escape sequence is found. The error points at
+    /// the `\` starting the escape sequence.
+    fn resolve_flow_scalar_escape_sequence(&mut self) -> Result<char, ScanError> {
+        let escape_mark = self.mark;
         let mut code_length = 0usize;
         let mut ret = '\0';
 
@@ -2104,9 +3237,13 @@ impl<T: Input> Scanner<T> {
             'x' => code_length = 2,
             'u' => code_length = 4,
             'U' => code_length = 8,
+            c if is_z(c) => {
+                self.skip_non_blank();
+                return Err(Self::truncated_escape_error(escape_mark));
+            }
             _ => {
                 return Err(ScanError::new_str(
-                    *start_mark,
+                    escape_mark,
                     "while parsing a quoted scalar, found unknown escape character",
                 ))
             }
@@ -2119,9 +3256,13 @@ impl<T: Input> Scanner<T> {
             let mut value = 0u32;
             for i in 0..code_length {
                 let c = self.input.peek_nth(i);
+                if is_z(c) {
+                    self.skip_n_non_blank(i);
+                    return Err(Self::truncated_escape_error(escape_mark));
+                }
                 if !is_hex(c) {
                     return Err(ScanError::new_str(
-                        *start_mark,
+                        escape_mark,
                         "while parsing a quoted scalar, did not find expected hexadecimal number",
                     ));
                 }
@@ -2130,7 +3271,7 @@ impl<T: Input> Scanner<T> {
 
             let Some(ch) = char::from_u32(value) else {
                 return Err(ScanError::new_str(
-                    *start_mark,
+                    escape_mark,
                     "while parsing a quoted scalar, found invalid Unicode character escape code",
                 ));
             };
@@ -2141,8 +3282,19 @@ impl<T: Input> Scanner<T> {
         Ok(ret)
     }
 
+    /// Return the error for an escape sequence starting at `escape_mark` cut by the end of input.
+    ///
+    /// The escape sequence must have been consumed, so that the scanner is at the end of the input
+    /// (see [`Self::reached_end_of_input`]).
+    fn truncated_escape_error(escape_mark: Marker) -> ScanError {
+        ScanError::new_str(
+            escape_mark,
+            "while parsing a quoted scalar, found unexpected end of stream in escape sequence",
+        )
+    }
+
     fn fetch_plain_scalar(&mut self) -> ScanResult {
-        self.save_simple_key();
+        self.save_simple_key()?;
         self.disallow_simple_key();
 
         let tok = self.scan_plain_scalar()?;
@@ -2151,28 +3303,58 @@ impl<T: Input> Scanner<T> {
         Ok(())
     }
 
+    /// Check whether the next characters may be part of a plain scalar.
+    ///
+    /// This function assumes we are not given a blankz character.
+    #[allow(clippy::inline_always)]
+    #[inline(always)]
+    fn next_can_be_plain_scalar(&self) -> bool {
+        let in_flow = self.flow_level > 0;
+        if !self.input.next_can_be_plain_scalar(in_flow) {
+            return false;
+        }
+        match self.input.peek() {
+            ':' => {
+                !((in_flow && self.json_mode) || self.is_extra_whitespace(self.input.peek_nth(1)))
+            }
+            // A byte order mark ends a plain scalar; it cannot be part of one.
+            c => !is_bom(c),
+        }
+    }
+
     /// Scan for a plain scalar.
     ///
     /// Plain scalars are the most readable but restricted style. They may span multiple lines in
-    /// some contexts.
+    /// some contexts. See [`TScalarStyle::Plain`] for the rules.
     #[allow(clippy::too_many_lines)]
     fn scan_plain_scalar(&mut self) -> Result<Token, ScanError> {
         self.unroll_non_block_indents();
         let indent = self.indent + 1;
         let start_mark = self.mark;
 
-        if self.flow_level > 0 && (start_mark.col as isize) < indent {
+        if self.flow_level > 0 && (start_mark.col() as isize) < indent {
             return Err(ScanError::new_str(
                 start_mark,
                 "invalid indentation in flow construct",
             ));
         }
 
-        let mut string = String::with_capacity(32);
+        let mut string = self.take_scalar_buffer();
+        string.reserve(32);
+        self.plain_scalar_last_line = None;
+        let is_same_line_value = self
+            .last_block_indicator
+            .is_some_and(|(c, mark)| c == ':' && mark.line() == start_mark.line());
         self.buf_whitespaces.clear();
         self.buf_leading_break.clear();
         self.buf_trailing_breaks.clear();
         let mut end_mark = self.mark;
+        // These do not change while scanning the scalar; hoist them out of the per-character loops.
+        let check_chars = self.error_on_tab || !self.allow_control_chars;
+        let check_limits = self.max_line_length.is_some() || self.max_total_bytes.is_some();
+        // Extra whitespace may be any character, which rules out fetching ASCII characters in bulk.
+        let fetch_in_bulk = self.extra_whitespace.is_empty();
+        let in_flow = self.flow_level > 0;
 
         loop {
             self.input.lookahead(4);
@@ -2188,16 +3370,24 @@ impl<T: Input> Scanner<T> {
             }
 
             if !self.input.next_is_blank_or_breakz()
-                && self.input.next_can_be_plain_scalar(self.flow_level > 0)
+                && !self.next_is_extra_whitespace()
+                && self.next_can_be_plain_scalar()
             {
                 if self.leading_whitespace {
+                    if is_same_line_value && self.mark.line() != start_mark.line() {
+                        self.plain_scalar_last_line = Some(self.mark);
+                    }
                     if self.buf_leading_break.is_empty() {
                         string.push_str(&self.buf_leading_break);
                         string.push_str(&self.buf_trailing_breaks);
                         self.buf_trailing_breaks.clear();
                         self.buf_leading_break.clear();
                     } else {
-                        if self.buf_trailing_breaks.is_empty() {
+                        if self.plain_scalar_fold == FoldMode::PreserveNewlines {
+                            string.push_str(&self.buf_leading_break);
+                            string.push_str(&self.buf_trailing_breaks);
+                            self.buf_trailing_breaks.clear();
+                        } else if self.buf_trailing_breaks.is_empty() {
                             string.push(' ');
                         } else {
                             string.push_str(&self.buf_trailing_breaks);
@@ -2212,26 +3402,52 @@ impl<T: Input> Scanner<T> {
                 }
 
                 // We can unroll the first iteration of the loop.
-                string.push(self.input.peek());
+                let c = self.input.peek();
+                if check_chars && !c.is_ascii_graphic() && self.is_forbidden_char(c) {
+                    return Err(Self::forbidden_char_error(self.mark, c));
+                }
+                string.push(c);
                 self.skip_non_blank();
                 string.reserve(self.input.bufmaxlen());
 
                 // Add content non-blank characters to the scalar.
                 let mut end = false;
                 while !end {
+                    // Most characters are visible ASCII ones we can take without looking further.
+                    // Stop before the limits are exceeded to report them at the right position.
+                    if fetch_in_bulk {
+                        let max = self.bytes_before_limits();
+                        let n_chars = self
+                            .input
+                            .fetch_while_plain_ascii(&mut string, in_flow, max);
+                        self.mark.advance(n_chars);
+                    }
                     // Fill the buffer once and process all characters in the buffer until the next
                     // fetch. Note that `next_can_be_plain_scalar` needs 2 lookahead characters,
                     // hence the `for` loop looping `self.input.bufmaxlen() - 1` times.
                     self.input.lookahead(self.input.bufmaxlen());
                     for _ in 0..self.input.bufmaxlen() - 1 {
                         if self.input.next_is_blank_or_breakz()
-                            || !self.input.next_can_be_plain_scalar(self.flow_level > 0)
+                            || self.next_is_extra_whitespace()
+                            || !self.next_can_be_plain_scalar()
                         {
                             end = true;
                             break;
                         }
-                        string.push(self.input.peek());
+                        let c = self.input.peek();
+                        // Visible ASCII characters are never forbidden.
+                        if check_chars && !c.is_ascii_graphic() && self.is_forbidden_char(c) {
+                            return Err(Self::forbidden_char_error(self.mark, c));
+                        }
+                        string.push(c);
                         self.skip_non_blank();
+                        if check_limits {
+                            self.check_limits_at(self.mark)?;
+                        }
+                        // Get back to fetching characters in bulk.
+                        if fetch_in_bulk {
+                            break;
+                        }
                     }
                 }
                 end_mark = self.mark;
@@ -2241,18 +3457,25 @@ impl<T: Input> Scanner<T> {
             //  - We reach eof
             //  - We reach ": "
             //  - We find a flow character in a flow context
-            if !(self.input.next_is_blank() || self.input.next_is_break()) {
+            if !(self.input.next_is_blank()
+                || self.input.next_is_break()
+                || self.next_is_extra_whitespace())
+            {
                 break;
             }
 
             // Process blank characters.
             self.input.lookahead(1);
-            while self.input.next_is_blank_or_break() {
-                if self.input.next_is_blank() {
+            while self.input.next_is_blank_or_break() || self.next_is_extra_whitespace() {
+                if self.next_is_extra_whitespace() {
+                    self.buf_whitespaces.push(self.input.peek());
+                    self.skip_extra_whitespace();
+                } else if self.input.next_is_blank() {
+                    self.check_tab()?;
                     if !self.leading_whitespace {
                         self.buf_whitespaces.push(self.input.peek());
                         self.skip_blank();
-                    } else if (self.mark.col as isize) < indent && self.input.peek() == '\t' {
+                    } else if (self.mark.col() as isize) < indent && self.input.peek() == '\t' {
                         // Tabs in an indentation columns are allowed if and only if the line is
                         // empty. Skip to the end of the line.
                         self.skip_ws_to_eol(SkipTabs::Yes)?;
@@ -2266,28 +3489,64 @@ impl<T: Input> Scanner<T> {
                         self.skip_blank();
                     }
                 } else {
+                    self.input.lookahead(2);
                     // Check if it is a first line break
                     if self.leading_whitespace {
                         self.skip_break();
                         self.buf_trailing_breaks.push('\n');
                     } else {
+                        if self.record_trailing_whitespace && !self.buf_whitespaces.is_empty() {
+                            self.trailing_whitespace
+                                .push(Span::new(end_mark, self.mark));
+                        }
                         self.buf_whitespaces.clear();
                         self.skip_break();
                         self.buf_leading_break.push('\n');
                         self.leading_whitespace = true;
                     }
                 }
+                if check_limits {
+                    self.check_limits_at(self.mark)?;
+                }
                 self.input.lookahead(1);
             }
 
             // check indentation level
-            if self.flow_level == 0 && (self.mark.col as isize) < indent {
-                break;
+            if (self.mark.col() as isize) < indent {
+                if self.flow_level == 0 {
+                    break;
+                }
+                // A block indicator less indented than the flow collection is not a continuation
+                // of the scalar, but block content following a flow collection that is not closed.
+                self.input.lookahead(2);
+                if matches!(self.input.peek(), '-' | '?' | ':')
+                    && is_blank_or_breakz(self.input.peek_nth(1))
+                {
+                    return Err(self.unclosed_flow_error(self.input.peek(), self.mark));
+                }
             }
         }
 
         if self.leading_whitespace {
             self.allow_simple_key();
+        } else if self.record_trailing_whitespace
+            && !self.buf_whitespaces.is_empty()
+            && self.input.next_is_z()
+        {
+            self.trailing_whitespace
+                .push(Span::new(end_mark, self.mark));
+        }
+
+        // The whitespace we skipped is part of the scalar, except after its last character.
+        if self.record_trivia {
+            while self
+                .trivia
+                .last()
+                .is_some_and(|t| t.start.index() >= start_mark.index())
+            {
+                self.trivia.pop();
+            }
+            self.record_trivia_since(end_mark);
         }
 
         Ok(Token(
@@ -2301,13 +3560,15 @@ impl<T: Input> Scanner<T> {
         if self.flow_level == 0 {
             // Check if we are allowed to start a new key (not necessarily simple).
             if !self.simple_key_allowed {
-                return Err(ScanError::new_str(
+                return Err(self.misplaced_block_indicator(
+                    '?',
                     self.mark,
                     "mapping keys are not allowed in this context",
                 ));
             }
+            self.last_block_indicator = Some(('?', start_mark));
             self.roll_indent(
-                start_mark.col,
+                start_mark.col(),
                 None,
                 TokenType::BlockMappingStart,
                 start_mark,
@@ -2359,7 +3620,10 @@ impl<T: Input> Scanner<T> {
         // The last line is for YAMLs like `[a:]`. The ':' is followed by a ']' (which is a
         // flow character), but the ']' is not the value. The value is an invisible empty
         // space which is represented as null ('~').
-        if self.mark.index != self.adjacent_value_allowed_at && (nc == '[' || nc == '{') {
+        if !self.json_mode
+            && self.mark.index() != self.adjacent_value_allowed_at
+            && (nc == '[' || nc == '{')
+        {
             return Err(ScanError::new_str(
                 self.mark,
                 "':' may not precede any of `[{` in flow mapping",
@@ -2371,7 +3635,7 @@ impl<T: Input> Scanner<T> {
 
     /// Fetch a value from a mapping (after a `:`).
     fn fetch_value(&mut self) -> ScanResult {
-        let sk = self.simple_keys.last().unwrap().clone();
+        let sk = *self.simple_keys.last().unwrap();
         let start_mark = self.mark;
         let is_implicit_flow_mapping = self.flow_level > 0 && !self.flow_mapping_started;
         if is_implicit_flow_mapping {
@@ -2380,22 +3644,27 @@ impl<T: Input> Scanner<T> {
 
         // Skip over ':'.
         self.skip_non_blank();
-        if self.input.look_ch() == '\t'
-            && !self.skip_ws_to_eol(SkipTabs::Yes)?.has_valid_yaml_ws()
-            && (self.input.peek() == '-' || self.input.next_is_alpha())
-        {
-            return Err(ScanError::new_str(
-                self.mark,
-                "':' must be followed by a valid YAML whitespace",
-            ));
+        if self.flow_level == 0 && self.skip_ws_to_eol(SkipTabs::Yes)?.found_tabs() {
+            self.check_tab_separated_content()?;
         }
 
         if sk.possible {
+            // A key separated by tabs from a block indicator on the same line would start a block
+            // mapping indented with tabs.
+            if self.flow_level == 0 && self.tab_separated_content_at == Some(sk.mark.index()) {
+                return Err(ScanError::new_str(
+                    sk.mark,
+                    "block collections cannot be started after a tab on the same line",
+                ));
+            }
+            if self.flow_level == 0 {
+                self.check_key_indentation(sk.mark)?;
+            }
             // insert simple key
             let tok = Token(Span::empty(sk.mark), TokenType::Key);
             self.insert_token(sk.token_number - self.tokens_parsed, tok);
             if is_implicit_flow_mapping {
-                if sk.mark.line < start_mark.line {
+                if sk.mark.line() < start_mark.line() {
                     return Err(ScanError::new_str(
                         start_mark,
                         "illegal placement of ':' indicator",
@@ -2409,7 +3678,7 @@ impl<T: Input> Scanner<T> {
 
             // Add the BLOCK-MAPPING-START token if needed.
             self.roll_indent(
-                sk.mark.col,
+                sk.mark.col(),
                 Some(sk.token_number),
                 TokenType::BlockMappingStart,
                 sk.mark,
@@ -2426,14 +3695,18 @@ impl<T: Input> Scanner<T> {
             // The ':' indicator follows a complex key.
             if self.flow_level == 0 {
                 if !self.simple_key_allowed {
-                    return Err(ScanError::new_str(
+                    if let Some(err) = self.overindented_key_error(start_mark) {
+                        return Err(err);
+                    }
+                    return Err(self.misplaced_block_indicator(
+                        ':',
                         start_mark,
                         "mapping values are not allowed in this context",
                     ));
                 }
 
                 self.roll_indent(
-                    start_mark.col,
+                    start_mark.col(),
                     None,
                     TokenType::BlockMappingStart,
                     start_mark,
@@ -2447,12 +3720,71 @@ impl<T: Input> Scanner<T> {
                 self.disallow_simple_key();
             }
         }
+        if self.flow_level == 0 {
+            self.last_block_indicator = Some((':', start_mark));
+        }
         self.tokens
             .push_back(Token(Span::empty(start_mark), TokenType::Value));
 
         Ok(())
     }
 
+    /// Return the indentation of the innermost block collection, if it is a block mapping.
+    fn block_mapping_indent(&self) -> Option<usize> {
+        let level = self.indents.last()?;
+        let indent = if level.needs_block_end {
+            self.indent
+        } else {
+            level.indent
+        };
+        level.mapping.then_some(indent as usize)
+    }
+
+    /// Error if the block mapping key at `key` is indented less than the keys of the mapping
+    /// closed on its line, but more than the enclosing block collection.
+    fn check_key_indentation(&self, key: Marker) -> ScanResult {
+        let Some((closed_at, expected)) = self.closed_mapping else {
+            return Ok(());
+        };
+        let found = key.col();
+        // The indentation of the enclosing block collection, past the one added after its last
+        // indicator.
+        let enclosing = match self.indents.last() {
+            Some(level) if !level.needs_block_end => level.indent,
+            _ => self.indent,
+        };
+        if closed_at.line() == key.line() && found < expected && (found as isize) > enclosing {
+            return Err(ScanError::new(
+                key,
+                format!(
+                    "mapping key is less indented than its siblings (expected {expected} spaces, \
+                     found {found})"
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Return an error for a `:` at `mark` that is not allowed, if it likely follows a key
+    /// indented more than its siblings.
+    ///
+    /// Such a key is read as a continuation of the plain scalar before it (e.g.: `b` in
+    /// `a: 1\n b: 2` continues `1`).
+    fn overindented_key_error(&self, mark: Marker) -> Option<ScanError> {
+        let key = self.plain_scalar_last_line?;
+        let expected = self.block_mapping_indent()?;
+        let found = key.col();
+        (key.line() == mark.line() && found > expected).then(|| {
+            ScanError::new(
+                key,
+                format!(
+                    "mapping key is more indented than its siblings (expected {expected} spaces, \
+                     found {found})"
+                ),
+            )
+        })
+    }
+
     /// Add an indentation level to the stack with the given block token, if needed.
     ///
     /// An indentation level is added only if:
@@ -2479,6 +3811,7 @@ impl<T: Input> Scanner<T> {
             self.indents.push(Indent {
                 indent: self.indent,
                 needs_block_end: true,
+                mapping: tok == TokenType::BlockMappingStart,
             });
             self.indent = col as isize;
             let tokens_parsed = self.tokens_parsed;
@@ -2500,6 +3833,9 @@ impl<T: Input> Scanner<T> {
         }
         while self.indent > col {
             let indent = self.indents.pop().unwrap();
+            if indent.needs_block_end && indent.mapping {
+                self.closed_mapping = Some((self.mark, self.indent as usize));
+            }
             self.indent = indent.indent;
             if indent.needs_block_end {
                 self.tokens
@@ -2514,10 +3850,14 @@ impl<T: Input> Scanner<T> {
     /// An indentation is not added if we are inside a flow level or if the last indent is already
     /// a non-block indent.
     fn roll_one_col_indent(&mut self) {
-        if self.flow_level == 0 && self.indents.last().map_or(false, |x| x.needs_block_end) {
+        if let Some(&last) = self.indents.last().filter(|x| x.needs_block_end) {
+            if self.flow_level > 0 {
+                return;
+            }
             self.indents.push(Indent {
                 indent: self.indent,
                 needs_block_end: false,
+                mapping: last.mapping,
             });
             self.indent += 1;
         }
@@ -2535,10 +3875,13 @@ impl<T: Input> Scanner<T> {
     }
 
     /// Mark the next token to be inserted as a potential simple key.
-    fn save_simple_key(&mut self) {
+    ///
+    /// # Errors
+    /// This function returns an error if this exceeds [`Self::max_simple_keys`].
+    fn save_simple_key(&mut self) -> ScanResult {
         if self.simple_key_allowed {
             let required = self.flow_level == 0
-                && self.indent == (self.mark.col as isize)
+                && self.indent == (self.mark.col() as isize)
                 && self.indents.last().unwrap().needs_block_end;
             let mut sk = SimpleKey::new(self.mark);
             sk.possible = true;
@@ -2547,7 +3890,17 @@ impl<T: Input> Scanner<T> {
 
             self.simple_keys.pop();
             self.simple_keys.push(sk);
+
+            if let Some(max) = self.max_simple_keys {
+                if self.simple_keys.iter().filter(|sk| sk.possible).count() > max {
+                    return Err(ScanError::new_str(
+                        self.mark,
+                        "possible simple keys exceed the maximum",
+                    ));
+                }
+            }
         }
+        Ok(())
     }
 
     fn remove_simple_key(&mut self) -> ScanResult {
@@ -2585,7 +3938,7 @@ impl<T: Input> Scanner<T> {
 /// Chomping, how final line breaks and trailing empty lines are interpreted.
 ///
 /// See YAML spec 8.1.1.2.
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Chomping {
     /// The final line break and any trailing empty lines are excluded.
     Strip,
@@ -2597,9 +3950,86 @@ pub enum Chomping {
 
 #[cfg(test)]
 mod test {
+    #[test]
+    fn test_scalar_style_helpers() {
+        use super::TScalarStyle;
+
+        let styles = [
+            TScalarStyle::Plain,
+            TScalarStyle::SingleQuoted,
+            TScalarStyle::DoubleQuoted,
+            TScalarStyle::Literal,
+            TScalarStyle::Folded,
+        ];
+        let quoted: Vec<_> = styles.iter().map(|s| s.is_quoted()).collect();
+        assert_eq!(quoted, [false, true, true, false, false]);
+        let block: Vec<_> = styles.iter().map(|s| s.is_block()).collect();
+        assert_eq!(block, [false, false, false, true, true]);
+        let escaping: Vec<_> = styles.iter().map(|s| s.requires_escaping()).collect();
+        assert_eq!(escaping, [false, true, true, false, false]);
+        let indicators: Vec<_> = styles.iter().map(|s| s.as_indicator_char()).collect();
+        assert_eq!(
+            indicators,
+            [None, Some('\''), Some('"'), Some('|'), Some('>')]
+        );
+    }
+
     #[test]
     fn test_is_anchor_char() {
         use super::is_anchor_char;
         assert!(is_anchor_char('x'));
     }
+
+    #[test]
+    fn test_scan_error_source() {
+        use super::{Marker, ScanError};
+        use std::error::Error;
+
+        let io_error = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "truncated");
+        let error = ScanError::new_with_source(
+            Marker::new(3, 1, 3),
+            "failed to read input".to_owned(),
+            Box::new(io_error),
+        );
+        assert_eq!(error.info(), "failed to read input");
+        assert_eq!(
+            error.to_string(),
+            "failed to read input at byte 3 line 1 column 4"
+        );
+        let source = error.source().unwrap();
+        let io_error = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io_error.kind(), std::io::ErrorKind::UnexpectedEof);
+        // Clones share the same source.
+        assert!(error.clone().source().is_some());
+
+        let error = ScanError::new_str(Marker::new(3, 1, 3), "failed to read input");
+        assert!(error.source().is_none());
+    }
+
+    #[test]
+    fn test_marker_u32_boundary() {
+        use super::{Marker, ScanError, Span};
+        assert_eq!(std::mem::size_of::<Marker>(), 12);
+        assert_eq!(std::mem::size_of::<Span>(), 24);
+
+        let max = u32::MAX as usize;
+        let mut mark = Marker::new(max - 1, 1, max - 1);
+        mark.advance(1);
+        assert_eq!((mark.index(), mark.line(), mark.col()), (max, 1, max));
+        // Positions past 4GiB saturate rather than wrap around.
+        mark.advance(1);
+        assert_eq!((mark.index(), mark.line(), mark.col()), (max, 1, max));
+        mark.advance_line();
+        assert_eq!((mark.index(), mark.line(), mark.col()), (max, 2, 0));
+        mark.advance_multibyte(max, max);
+        assert_eq!((mark.index(), mark.line(), mark.col()), (max, 2, max));
+        assert_eq!(Marker::new(max + 1, max + 1, 0).line(), max);
+
+        // Columns are displayed 1-indexed without overflowing.
+        let error = ScanError::new_str(Marker::new(max, max, max), "error");
+        assert_eq!(
+            error.to_string(),
+            "error at byte 4294967295 line 4294967295 column 4294967296"
+        );
+    }
 }
@@ -0,0 +1,433 @@
+//! Resolution of the type of plain scalars, depending on the YAML schema, and decoding of
+//! `!!binary` scalars.
+
+use crate::{Marker, ScanError};
+
+/// The schema used to recognize booleans and null among plain scalars.
+///
+/// The parser emits all scalars as strings. Which plain scalars are booleans or null depends on
+/// the schema the loader follows:
+///
+/// | Schema      | Null                                 | Booleans                                    |
+/// |-------------|--------------------------------------|---------------------------------------------|
+/// | [`Core1_2`] | `null`, `Null`, `NULL`, `~`, empty   | `true`, `false` (also capitalized or upper) |
+/// | [`Json`]    | `null`, `~`, empty                   | `true`, `false`                             |
+/// | [`Yaml1_1`] | `null`, `Null`, `NULL`, `~`, empty   | Core, plus `y`, `yes`, `on`, `n`, `no`, `off` (also capitalized or upper) |
+///
+/// The parser reports empty nodes (e.g.: the value in `a:`) as `~`. `~` and empty scalars are
+/// hence null in all schemas.
+///
+/// [`Core1_2`]: SchemaVersion::Core1_2
+/// [`Json`]: SchemaVersion::Json
+/// [`Yaml1_1`]: SchemaVersion::Yaml1_1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchemaVersion {
+    /// The YAML 1.2 core schema.
+    #[default]
+    Core1_2,
+    /// The YAML 1.2 JSON schema.
+    Json,
+    /// The YAML 1.1 types, where `yes`, `no`, `on`, `off`, `y` and `n` are booleans.
+    Yaml1_1,
+}
+
+/// A plain scalar resolved as a boolean or null.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolvedScalar {
+    /// The scalar is null.
+    Null,
+    /// The scalar is a boolean.
+    Bool(bool),
+}
+
+/// The type a plain scalar resolves to, see [`SchemaVersion::resolve_type`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarType {
+    /// The scalar is null.
+    Null,
+    /// The scalar is a boolean.
+    Bool,
+    /// The scalar is an integer.
+    Int,
+    /// The scalar is a floating-point number.
+    Float,
+    /// The scalar is a string.
+    Str,
+}
+
+/// The value of a plain scalar that is a number, see [`SchemaVersion::parse_number`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScalarValue {
+    /// An integer.
+    Int(i64),
+    /// A floating-point number.
+    Float(f64),
+}
+
+impl SchemaVersion {
+    /// Resolve the value of a plain scalar as a boolean or null.
+    ///
+    /// Return `None` if the scalar is neither (e.g.: it is a string or a number).
+    ///
+    /// ```
+    /// # use saphyr_parser::{ResolvedScalar, SchemaVersion};
+    /// assert_eq!(SchemaVersion::Core1_2.resolve_bool_or_null("on"), None);
+    /// assert_eq!(
+    ///     SchemaVersion::Yaml1_1.resolve_bool_or_null("on"),
+    ///     Some(ResolvedScalar::Bool(true))
+    /// );
+    /// ```
+    #[must_use]
+    pub fn resolve_bool_or_null(self, value: &str) -> Option<ResolvedScalar> {
+        match (self, value) {
+            (_, "" | "~" | "null") | (Self::Core1_2 | Self::Yaml1_1, "Null" | "NULL") => {
+                Some(ResolvedScalar::Null)
+            }
+            (_, "true")
+            | (Self::Core1_2 | Self::Yaml1_1, "True" | "TRUE")
+            | (Self::Yaml1_1, "y" | "Y" | "yes" | "Yes" | "YES" | "on" | "On" | "ON") => {
+                Some(ResolvedScalar::Bool(true))
+            }
+            (_, "false")
+            | (Self::Core1_2 | Self::Yaml1_1, "False" | "FALSE")
+            | (Self::Yaml1_1, "n" | "N" | "no" | "No" | "NO" | "off" | "Off" | "OFF") => {
+                Some(ResolvedScalar::Bool(false))
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolve the type of a plain scalar.
+    ///
+    /// Plain scalars that are not strings are a common source of mistakes in configuration files
+    /// (e.g.: `NO` as a country code is `false` in YAML 1.1, `1.10` as a version is `1.1`). This
+    /// lets linters warn about them. Integers and floats are recognized as follows:
+    ///
+    /// | Schema      | Integers                                    | Floats                                   |
+    /// |-------------|---------------------------------------------|------------------------------------------|
+    /// | [`Core1_2`] | `12`, `-12`, `0o14`, `0xC`                  | `1.5`, `-.5`, `1e3`, `.inf`, `.nan`      |
+    /// | [`Json`]    | `12`, `-12`                                 | `1.5`, `-1.5e3`                          |
+    /// | [`Yaml1_1`] | Core, plus `0b1100`, `014`, `1_000`, `3:25` | `1.5`, `1.5e+3`, `1_000.5`, `3:25.5`, `.inf`, `.nan` |
+    ///
+    /// [`Core1_2`]: SchemaVersion::Core1_2
+    /// [`Json`]: SchemaVersion::Json
+    /// [`Yaml1_1`]: SchemaVersion::Yaml1_1
+    ///
+    /// ```
+    /// # use saphyr_parser::{ScalarType, SchemaVersion};
+    /// assert_eq!(SchemaVersion::Core1_2.resolve_type("NO"), ScalarType::Str);
+    /// assert_eq!(SchemaVersion::Yaml1_1.resolve_type("NO"), ScalarType::Bool);
+    /// assert_eq!(SchemaVersion::Core1_2.resolve_type("0x1e3"), ScalarType::Int);
+    /// ```
+    #[must_use]
+    pub fn resolve_type(self, value: &str) -> ScalarType {
+        match self.resolve_bool_or_null(value) {
+            Some(ResolvedScalar::Null) => ScalarType::Null,
+            Some(ResolvedScalar::Bool(_)) => ScalarType::Bool,
+            None if self.is_int(value) => ScalarType::Int,
+            None if self.is_float(value) => ScalarType::Float,
+            None => ScalarType::Str,
+        }
+    }
+
+    /// Return whether `value` is an integer in this schema.
+    fn is_int(self, value: &str) -> bool {
+        match self {
+            Self::Core1_2 => is_core_int(value),
+            Self::Json => is_json_int(value),
+            Self::Yaml1_1 => is_yaml11_int(value),
+        }
+    }
+
+    /// Return whether `value` is a float in this schema.
+    fn is_float(self, value: &str) -> bool {
+        match self {
+            Self::Core1_2 => is_core_float(value),
+            Self::Json => is_json_float(value),
+            Self::Yaml1_1 => is_yaml11_float(value),
+        }
+    }
+
+    /// Parse a plain scalar that resolves to an integer or a float (see [`Self::resolve_type`]).
+    ///
+    /// Return `None` for scalars of other types and for integers that do not fit in an `i64`.
+    /// Numbers are parsed without allocating, except for YAML 1.1 floats containing `_`.
+    ///
+    /// ```
+    /// # use saphyr_parser::{ScalarValue, SchemaVersion};
+    /// assert_eq!(SchemaVersion::Core1_2.parse_number("0x1F"), Some(ScalarValue::Int(31)));
+    /// assert_eq!(SchemaVersion::Core1_2.parse_number("-1.5"), Some(ScalarValue::Float(-1.5)));
+    /// assert_eq!(SchemaVersion::Yaml1_1.parse_number("1:30"), Some(ScalarValue::Int(90)));
+    /// assert_eq!(SchemaVersion::Core1_2.parse_number("abc"), None);
+    /// ```
+    #[must_use]
+    pub fn parse_number(self, value: &str) -> Option<ScalarValue> {
+        match self.resolve_type(value) {
+            ScalarType::Int => self.parse_int(value).map(ScalarValue::Int),
+            ScalarType::Float => parse_float(value).map(ScalarValue::Float),
+            _ => None,
+        }
+    }
+
+    /// Parse `value`, which must be an integer in this schema.
+    fn parse_int(self, value: &str) -> Option<i64> {
+        let negative = value.starts_with('-');
+        let digits = strip_sign(value);
+        let (radix, digits) = if let Some(hex) = digits.strip_prefix("0x") {
+            (16, hex)
+        } else if let Some(octal) = digits.strip_prefix("0o") {
+            (8, octal)
+        } else if let Some(binary) = digits.strip_prefix("0b") {
+            (2, binary)
+        } else if self == Self::Yaml1_1 && digits.len() > 1 && digits.starts_with('0') {
+            (8, &digits[1..])
+        } else {
+            (10, digits)
+        };
+        if digits.contains(':') {
+            parse_sexagesimal(digits, negative)
+        } else {
+            accumulate_digits(digits, radix, negative)
+        }
+    }
+}
+
+/// Parse `digits` in `radix`, ignoring `_`.
+///
+/// Negative numbers are accumulated as such, so that `i64::MIN` does not overflow.
+fn accumulate_digits(digits: &str, radix: u32, negative: bool) -> Option<i64> {
+    digits
+        .chars()
+        .filter(|&c| c != '_')
+        .try_fold(0i64, |acc, c| {
+            let digit = i64::from(c.to_digit(radix)?);
+            let acc = acc.checked_mul(i64::from(radix))?;
+            if negative {
+                acc.checked_sub(digit)
+            } else {
+                acc.checked_add(digit)
+            }
+        })
+}
+
+/// Parse the sexagesimal integer `digits` (e.g.: `1:30`), ignoring `_`.
+fn parse_sexagesimal(digits: &str, negative: bool) -> Option<i64> {
+    digits.split(':').try_fold(0i64, |acc, digit| {
+        acc.checked_mul(60)?
+            .checked_add(accumulate_digits(digit, 10, negative)?)
+    })
+}
+
+/// Parse `value`, which must be a float in one of the schemas.
+#[allow(clippy::cast_precision_loss)]
+fn parse_float(value: &str) -> Option<f64> {
+    let negative = value.starts_with('-');
+    match strip_sign(value) {
+        ".inf" | ".Inf" | ".INF" if negative => return Some(f64::NEG_INFINITY),
+        ".inf" | ".Inf" | ".INF" => return Some(f64::INFINITY),
+        ".nan" | ".NaN" | ".NAN" => return Some(f64::NAN),
+        _ => {}
+    }
+    if let Some((int, frac)) = value.rsplit_once(':') {
+        let int = parse_sexagesimal(strip_sign(int), false)? as f64;
+        let abs = int * 60.0 + parse_decimal_float(frac)?;
+        return Some(if negative { -abs } else { abs });
+    }
+    parse_decimal_float(value)
+}
+
+/// Parse the decimal float `value`, ignoring `_`.
+fn parse_decimal_float(value: &str) -> Option<f64> {
+    if value.contains('_') {
+        value.replace('_', "").parse().ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+/// Split `s` after the characters it starts with that match `pred`.
+fn split_while(s: &str, pred: impl Fn(char) -> bool) -> (&str, &str) {
+    s.split_at(s.find(|c| !pred(c)).unwrap_or(s.len()))
+}
+
+/// Return whether `s` is not empty and only contains characters that match `pred`.
+fn is_digits(s: &str, pred: impl Fn(char) -> bool) -> bool {
+    !s.is_empty() && s.chars().all(pred)
+}
+
+/// Remove the sign `s` starts with, if any.
+fn strip_sign(s: &str) -> &str {
+    s.strip_prefix(['-', '+']).unwrap_or(s)
+}
+
+/// Return whether `s` is empty or an exponent (`e3`), whose sign is optional unless `signed`.
+fn is_exponent(s: &str, signed: bool) -> bool {
+    let Some(exponent) = s.strip_prefix(['e', 'E']) else {
+        return s.is_empty();
+    };
+    match exponent.strip_prefix(['-', '+']) {
+        Some(digits) => is_digits(digits, |c| c.is_ascii_digit()),
+        None => !signed && is_digits(exponent, |c| c.is_ascii_digit()),
+    }
+}
+
+/// Return whether `s` is an infinity or not-a-number, as the core and YAML 1.1 schemas write them.
+fn is_special_float(s: &str) -> bool {
+    matches!(strip_sign(s), ".inf" | ".Inf" | ".INF") || matches!(s, ".nan" | ".NaN" | ".NAN")
+}
+
+/// Return whether `s` is an integer in the core schema.
+fn is_core_int(s: &str) -> bool {
+    if let Some(octal) = s.strip_prefix("0o") {
+        is_digits(octal, |c| matches!(c, '0'..='7'))
+    } else if let Some(hex) = s.strip_prefix("0x") {
+        is_digits(hex, |c| c.is_ascii_hexdigit())
+    } else {
+        is_digits(strip_sign(s), |c| c.is_ascii_digit())
+    }
+}
+
+/// Return whether `s` is a float in the core schema.
+fn is_core_float(s: &str) -> bool {
+    if is_special_float(s) {
+        return true;
+    }
+    let (int, rest) = split_while(strip_sign(s), |c| c.is_ascii_digit());
+    let (frac, rest) = match rest.strip_prefix('.') {
+        Some(rest) => split_while(rest, |c| c.is_ascii_digit()),
+        None => ("", rest),
+    };
+    (!int.is_empty() || !frac.is_empty()) && is_exponent(rest, false)
+}
+
+/// Return whether `s` is an integer in the JSON schema.
+fn is_json_int(s: &str) -> bool {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    s == "0" || (!s.starts_with('0') && is_digits(s, |c| c.is_ascii_digit()))
+}
+
+/// Return whether `s` is a float in the JSON schema.
+fn is_json_float(s: &str) -> bool {
+    let (int, rest) = split_while(s.strip_prefix('-').unwrap_or(s), |c| c.is_ascii_digit());
+    let rest = rest
+        .strip_prefix('.')
+        .map_or(rest, |rest| split_while(rest, |c| c.is_ascii_digit()).1);
+    (int == "0" || (!int.is_empty() && !int.starts_with('0'))) && is_exponent(rest, false)
+}
+
+/// Return whether `s` is a non-empty sequence of sexagesimal digits, each preceded by a `:`
+/// (e.g.: `:25:30`).
+fn is_sexagesimal(s: &str) -> bool {
+    s.strip_prefix(':').is_some_and(|s| {
+        s.split(':').all(|digit| match digit.as_bytes() {
+            [units] => units.is_ascii_digit(),
+            [tens, units] => matches!(tens, b'0'..=b'5') && units.is_ascii_digit(),
+            _ => false,
+        })
+    })
+}
+
+/// Return whether `s` is an integer in YAML 1.1.
+fn is_yaml11_int(s: &str) -> bool {
+    let s = strip_sign(s);
+    if let Some(binary) = s.strip_prefix("0b") {
+        is_digits(binary, |c| matches!(c, '0' | '1' | '_'))
+    } else if let Some(hex) = s.strip_prefix("0x") {
+        is_digits(hex, |c| c.is_ascii_hexdigit() || c == '_')
+    } else if let Some(octal) = s.strip_prefix('0') {
+        octal.chars().all(|c| matches!(c, '0'..='7' | '_'))
+    } else {
+        let (int, rest) = split_while(s, |c| c.is_ascii_digit() || c == '_');
+        int.starts_with(|c: char| c.is_ascii_digit()) && (rest.is_empty() || is_sexagesimal(rest))
+    }
+}
+
+/// Return whether `s` is a float in YAML 1.1.
+fn is_yaml11_float(s: &str) -> bool {
+    if is_special_float(s) {
+        return true;
+    }
+    let is_digit = |c: char| c.is_ascii_digit() || c == '_';
+    let (int, rest) = split_while(strip_sign(s), is_digit);
+    if int.starts_with('_') {
+        return false;
+    }
+    if rest.starts_with(':') {
+        return rest.split_once('.').is_some_and(|(sexagesimal, frac)| {
+            !int.is_empty() && is_sexagesimal(sexagesimal) && frac.chars().all(is_digit)
+        });
+    }
+    let Some(rest) = rest.strip_prefix('.') else {
+        return false;
+    };
+    let (frac, rest) = split_while(rest, is_digit);
+    int.chars().chain(frac.chars()).any(|c| c.is_ascii_digit()) && is_exponent(rest, true)
+}
+
+/// Decode the base64 content of a `!!binary` scalar.
+///
+/// Whitespace (including line breaks) is ignored, so that the content may be folded over several
+/// lines of a block scalar. Errors are reported at `mark`, which should be the start of the
+/// scalar.
+///
+/// ```
+/// # use saphyr_parser::{decode_base64, Marker};
+/// let mark = Marker::new(0, 1, 0);
+/// assert_eq!(decode_base64("aGVs\nbG8=\n", mark).unwrap(), b"hello");
+/// assert!(decode_base64("aGVsbG8", mark).is_err());
+/// ```
+///
+/// # Errors
+/// Returns `ScanError` if `value` contains a character outside of the base64 alphabet, if its
+/// padding is misplaced or if its length is not a multiple of 4.
+pub fn decode_base64(value: &str, mark: Marker) -> Result<Vec<u8>, ScanError> {
+    let mut bytes = Vec::with_capacity(value.len() / 4 * 3);
+    // The bits decoded but not yet pushed to `bytes`, and their number.
+    let mut bits = 0u32;
+    let mut nbits = 0;
+    let mut ndigits = 0usize;
+    let mut npadding = 0;
+    for c in value.chars() {
+        let digit = match c {
+            ' ' | '\t' | '\n' | '\r' => continue,
+            '=' => {
+                npadding += 1;
+                ndigits += 1;
+                continue;
+            }
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            _ => {
+                return Err(ScanError::new(
+                    mark,
+                    format!("invalid character `{c}' in base64 binary data"),
+                ))
+            }
+        };
+        if npadding > 0 {
+            return Err(ScanError::new_str(
+                mark,
+                "padding may only appear at the end of base64 binary data",
+            ));
+        }
+        ndigits += 1;
+        bits = (bits << 6) | digit;
+        nbits += 6;
+        if nbits >= 8 {
+            nbits -= 8;
+            #[allow(clippy::cast_possible_truncation)]
+            bytes.push((bits >> nbits) as u8);
+            bits &= (1 << nbits) - 1;
+        }
+    }
+    if ndigits % 4 != 0 || npadding > 2 {
+        return Err(ScanError::new_str(
+            mark,
+            "invalid length of base64 binary data",
+        ));
+    }
+    Ok(bytes)
+}
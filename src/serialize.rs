@@ -0,0 +1,41 @@
+//! [`Serialize`] implementations for locations and errors.
+//!
+//! The serialized shapes are part of the public API and are kept stable:
+//!   - [`Marker`]: `{ "index": usize, "line": usize, "col": usize }`, as returned by
+//!     [`Marker::index`], [`Marker::line`] and [`Marker::col`].
+//!   - [`Span`]: `{ "start": Marker, "end": Marker }`.
+//!   - [`ScanError`]: `{ "marker": Marker, "info": string }`, as returned by
+//!     [`ScanError::marker`] and [`ScanError::info`]. The underlying error, if any, is not
+//!     serialized.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::{Marker, ScanError, Span};
+
+impl Serialize for Marker {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Marker", 3)?;
+        state.serialize_field("index", &self.index())?;
+        state.serialize_field("line", &self.line())?;
+        state.serialize_field("col", &self.col())?;
+        state.end()
+    }
+}
+
+impl Serialize for Span {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Span", 2)?;
+        state.serialize_field("start", &self.start)?;
+        state.serialize_field("end", &self.end)?;
+        state.end()
+    }
+}
+
+impl Serialize for ScanError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ScanError", 2)?;
+        state.serialize_field("marker", self.marker())?;
+        state.serialize_field("info", self.info())?;
+        state.end()
+    }
+}
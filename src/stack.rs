@@ -0,0 +1,117 @@
+//! A stack storing its first elements inline.
+
+/// A stack storing its first `N` elements inline and the following ones on the heap.
+///
+/// The parser and the scanner keep stacks whose depth follows the nesting of the document. Most
+/// documents are shallow: storing the first levels inline avoids allocating for them at all.
+#[derive(Clone, Debug)]
+pub(crate) struct SmallStack<T: Copy, const N: usize> {
+    /// The first `N` elements of the stack. Only the first `inline_len` ones are `Some`.
+    inline: [Option<T>; N],
+    /// The number of elements in `inline`.
+    inline_len: usize,
+    /// The elements past the first `N`, if any.
+    ///
+    /// This only allocates once more than `N` elements are pushed.
+    spilled: Vec<T>,
+}
+
+impl<T: Copy, const N: usize> SmallStack<T, N> {
+    /// Create an empty stack. This does not allocate.
+    pub(crate) fn new() -> Self {
+        Self {
+            inline: [None; N],
+            inline_len: 0,
+            spilled: Vec::new(),
+        }
+    }
+
+    /// Push `value` on top of the stack.
+    pub(crate) fn push(&mut self, value: T) {
+        if self.inline_len < N {
+            self.inline[self.inline_len] = Some(value);
+            self.inline_len += 1;
+        } else {
+            self.spilled.push(value);
+        }
+    }
+
+    /// Remove the top of the stack and return it, if any.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        if let Some(value) = self.spilled.pop() {
+            return Some(value);
+        }
+        self.inline_len = self.inline_len.checked_sub(1)?;
+        self.inline[self.inline_len].take()
+    }
+
+    /// Return the top of the stack, if any.
+    pub(crate) fn last(&self) -> Option<&T> {
+        if let Some(value) = self.spilled.last() {
+            return Some(value);
+        }
+        self.inline[..self.inline_len].last()?.as_ref()
+    }
+
+    /// Return the top of the stack, if any.
+    pub(crate) fn last_mut(&mut self) -> Option<&mut T> {
+        if !self.spilled.is_empty() {
+            return self.spilled.last_mut();
+        }
+        self.inline[..self.inline_len].last_mut()?.as_mut()
+    }
+
+    /// Return whether the stack is empty.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.inline_len == 0
+    }
+
+    /// Iterate over the elements of the stack, from bottom to top.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.inline[..self.inline_len]
+            .iter()
+            .flatten()
+            .chain(&self.spilled)
+    }
+
+    /// Iterate mutably over the elements of the stack, from bottom to top.
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.inline[..self.inline_len]
+            .iter_mut()
+            .flatten()
+            .chain(&mut self.spilled)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SmallStack;
+
+    #[test]
+    fn test_small_stack() {
+        let mut stack = SmallStack::<usize, 2>::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.last(), None);
+        assert_eq!(stack.pop(), None);
+
+        // Spill past the inline capacity and come back.
+        for i in 0..5 {
+            stack.push(i);
+            assert_eq!(stack.last(), Some(&i));
+        }
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+        for value in stack.iter_mut() {
+            *value *= 10;
+        }
+        *stack.last_mut().unwrap() += 1;
+        assert_eq!(stack.pop(), Some(41));
+        assert_eq!(stack.pop(), Some(30));
+        assert_eq!(stack.pop(), Some(20));
+        *stack.last_mut().unwrap() += 1;
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), [0, 11]);
+        assert_eq!(stack.pop(), Some(11));
+        assert_eq!(stack.pop(), Some(0));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+    }
+}
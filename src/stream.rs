@@ -0,0 +1,208 @@
+//! An asynchronous [`Stream`] of events, behind the `async` feature.
+
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    pin::Pin,
+    rc::Rc,
+    str::Chars,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use crate::{parser::ParseResult, BufferedInput, Event, Marker, Parser};
+
+/// The maximum number of characters pulled from the source before the events are parsed.
+///
+/// This bounds how long [`EventStream::poll_next`] runs for when the source is always ready.
+const MAX_PULLED_CHARS: usize = 64 * 1024;
+
+/// The events parsed from an asynchronous source of characters.
+///
+/// This is meant for servers reading YAML from a socket. Characters are pulled from the source as
+/// they become available, and each event is yielded as soon as the characters received so far
+/// determine it. Once the source ends, the remaining events are yielded, up to
+/// [`Event::StreamEnd`] or an error. No event is yielded after an error.
+///
+/// The scanner is synchronous: when it needs characters that have yet to be received, parsing
+/// stops and resumes from the start of the current document once more characters are received.
+/// The characters of the documents already parsed are dropped. Each part of a document is thus
+/// scanned several times if the document arrives in several parts. This is cheap for documents of
+/// a few kilobytes, but large documents should be received in large parts.
+///
+/// ```
+/// # use saphyr_parser::{Event, EventStream};
+/// # use tokio_stream::StreamExt;
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut events = EventStream::new(tokio_stream::iter("a: b".chars()));
+/// let mut scalars = vec![];
+/// while let Some(event) = events.next().await {
+///     if let (Event::Scalar(value, ..), _) = event.unwrap() {
+///         scalars.push(value);
+///     }
+/// }
+/// assert_eq!(scalars, ["a", "b"]);
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct EventStream<S> {
+    /// The source of characters.
+    source: S,
+    /// The characters received so far, from the start of the current document.
+    text: String,
+    /// Where [`Self::text`] starts in the stream, if the documents before it were dropped.
+    resume: Option<Resume>,
+    /// Whether the source has ended.
+    source_done: bool,
+    /// The number of events parsed from [`Self::text`] that were already queued.
+    queued: usize,
+    /// The events determined by the characters received so far, yet to be yielded.
+    ready: VecDeque<ParseResult>,
+    /// Whether the last event was queued, after which the stream ends.
+    finished: bool,
+}
+
+/// Where a stream is resumed once the documents before it were parsed and dropped.
+#[derive(Clone, Copy, Debug)]
+struct Resume {
+    /// The position of the end of the last document dropped, at the start of a line.
+    start: Marker,
+    /// The ID of the next anchor after those of the documents dropped.
+    next_anchor_id: usize,
+}
+
+/// The characters received so far, recording whether the scanner needed more of them.
+struct Received<'a> {
+    /// The characters yet to be read by the scanner.
+    chars: Chars<'a>,
+    /// Whether the scanner tried to read past the characters received.
+    exhausted: Rc<Cell<bool>>,
+}
+
+impl Iterator for Received<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c.is_none() {
+            self.exhausted.set(true);
+        }
+        c
+    }
+}
+
+impl<S: Stream<Item = char> + Unpin> EventStream<S> {
+    /// Create a new [`EventStream`] parsing the characters yielded by `source`.
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            text: String::new(),
+            resume: None,
+            source_done: false,
+            queued: 0,
+            ready: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Pull the characters available from the source.
+    ///
+    /// Return whether any character was received or the source ended.
+    fn pull(&mut self, cx: &mut Context<'_>) -> bool {
+        let mut received = false;
+        for _ in 0..MAX_PULLED_CHARS {
+            match Pin::new(&mut self.source).poll_next(cx) {
+                Poll::Ready(Some(c)) => {
+                    self.text.push(c);
+                    received = true;
+                }
+                Poll::Ready(None) => {
+                    self.source_done = true;
+                    return true;
+                }
+                Poll::Pending => break,
+            }
+        }
+        received
+    }
+
+    /// Queue the events determined by the characters received so far which were not queued yet.
+    ///
+    /// Until the source ends, an event is determined if the scanner did not try to read past the
+    /// characters received before the parser returned it: more characters cannot change it. The
+    /// characters of the documents whose end is determined are then dropped.
+    fn queue_events(&mut self) {
+        let exhausted = Rc::new(Cell::new(false));
+        let received = Received {
+            chars: self.text.chars(),
+            exhausted: Rc::clone(&exhausted),
+        };
+        let mut parser = Parser::new(BufferedInput::new(received));
+        if let Some(resume) = self.resume {
+            parser.resume_after_document(resume.start, resume.next_anchor_id);
+        }
+        // The last document end determined, and the number of events up to it.
+        let mut document_end = None;
+        let mut n_events = 0;
+        while let Some(next) = parser.next() {
+            if !self.source_done && exhausted.get() {
+                break;
+            }
+            if self.resume.is_some() && matches!(next, Ok((Event::StreamStart, _))) {
+                continue;
+            }
+            n_events += 1;
+            // Documents end at the start of a line, unless the input is invalid.
+            if let Ok((Event::DocumentEnd, span)) = &next {
+                if span.start.col() == 0 {
+                    let resume = Resume {
+                        start: span.start,
+                        next_anchor_id: parser.next_anchor_id(),
+                    };
+                    document_end = Some((resume, n_events));
+                }
+            }
+            if n_events <= self.queued {
+                continue;
+            }
+            self.queued += 1;
+            let last = matches!(next, Err(_) | Ok((Event::StreamEnd, _)));
+            self.ready.push_back(next);
+            if last {
+                self.finished = true;
+                return;
+            }
+        }
+        if !self.source_done && exhausted.get() {
+            if let Some((resume, n_events)) = document_end {
+                let text_start = self.resume.map_or(0, |resume| resume.start.index());
+                self.text.drain(..resume.start.index() - text_start);
+                self.queued -= n_events;
+                self.resume = Some(resume);
+            }
+        } else {
+            self.finished = true;
+        }
+    }
+}
+
+impl<S: Stream<Item = char> + Unpin> Stream for EventStream<S> {
+    type Item = ParseResult;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(next) = this.ready.pop_front() {
+                return Poll::Ready(Some(next));
+            }
+            if this.finished {
+                return Poll::Ready(None);
+            }
+            if !this.pull(cx) {
+                return Poll::Pending;
+            }
+            this.queue_events();
+        }
+    }
+}
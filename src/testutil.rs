@@ -0,0 +1,219 @@
+//! Helpers to compare event streams in tests.
+//!
+//! Comparing large `Vec`s of events with `assert_eq!` prints both vectors in full on mismatch,
+//! which is hard to read. These helpers instead report the first event that differs, along with
+//! the events preceding it and their spans, or dump event streams in a textual notation suited
+//! to snapshot tests.
+
+use std::fmt::{self, Write};
+
+use crate::{Event, Span, TScalarStyle, Tag};
+
+/// The number of identical events shown before the first differing one.
+const CONTEXT_LEN: usize = 3;
+
+/// The first difference between two event streams, as returned by [`diff_events`].
+///
+/// Its [`Display`](fmt::Display) implementation shows the differing events and the events
+/// preceding them, with their spans.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventsDiff<'a> {
+    /// The index of the first differing event.
+    pub index: usize,
+    /// The events preceding the first differing one, identical in both streams.
+    pub context: &'a [(Event, Span)],
+    /// The expected event at [`Self::index`], or `None` if the expected stream is shorter.
+    pub expected: Option<&'a (Event, Span)>,
+    /// The actual event at [`Self::index`], or `None` if the actual stream is shorter.
+    pub actual: Option<&'a (Event, Span)>,
+}
+
+/// Return the first difference between two event streams, if any.
+///
+/// Events are compared along with their spans. A stream that is a prefix of the other differs at
+/// the index past its last event.
+///
+/// ```
+/// # use saphyr_parser::{testutil::diff_events, Event, Parser};
+/// let expected: Vec<_> = Parser::new_from_str("[a, b]").map(Result::unwrap).collect();
+/// let actual: Vec<_> = Parser::new_from_str("[a, c]").map(Result::unwrap).collect();
+/// let diff = diff_events(&expected, &actual).unwrap();
+/// assert_eq!(diff.index, 4);
+/// assert!(matches!(&diff.actual.unwrap().0, Event::Scalar(v, ..) if v == "c"));
+/// assert!(diff_events(&expected, &expected).is_none());
+/// ```
+#[must_use]
+pub fn diff_events<'a>(
+    expected: &'a [(Event, Span)],
+    actual: &'a [(Event, Span)],
+) -> Option<EventsDiff<'a>> {
+    let index = expected
+        .iter()
+        .zip(actual)
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+    if index == expected.len() && index == actual.len() {
+        return None;
+    }
+    Some(EventsDiff {
+        index,
+        context: &expected[index.saturating_sub(CONTEXT_LEN)..index],
+        expected: expected.get(index),
+        actual: actual.get(index),
+    })
+}
+
+/// Assert that two event streams are equal, including spans.
+///
+/// # Panics
+/// If the streams differ. The panic message shows the first differing event, as described in
+/// [`EventsDiff`].
+#[track_caller]
+pub fn assert_events_eq(expected: &[(Event, Span)], actual: &[(Event, Span)]) {
+    if let Some(diff) = diff_events(expected, actual) {
+        panic!("{diff}");
+    }
+}
+
+impl fmt::Display for EventsDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "event streams differ at index {}", self.index)?;
+        let first = self.index - self.context.len();
+        for (i, event) in self.context.iter().enumerate() {
+            write_event(f, "  ", first + i, Some(event))?;
+        }
+        write_event(f, "- ", self.index, self.expected)?;
+        write_event(f, "+ ", self.index, self.actual)
+    }
+}
+
+/// Write a line describing `event`, with its index in the stream and its span.
+fn write_event(
+    f: &mut fmt::Formatter<'_>,
+    prefix: &str,
+    index: usize,
+    event: Option<&(Event, Span)>,
+) -> fmt::Result {
+    match event {
+        Some((event, span)) => writeln!(
+            f,
+            "{prefix}{index:>4}: {event:?} at {}:{}..{}:{}",
+            span.start.line(),
+            span.start.col().saturating_add(1),
+            span.end.line(),
+            span.end.col().saturating_add(1),
+        ),
+        None => writeln!(f, "{prefix}{index:>4}: <end of stream>"),
+    }
+}
+
+/// Dump an event stream in a stable textual notation, one event per line.
+///
+/// This is meant for snapshot tests, whose expected output is easy to review in diffs. Each line
+/// holds the span of the event, as `line:column..line:column` (columns starting at 1), followed
+/// by the event in the notation of the [yaml-test-suite](https://github.com/yaml/yaml-test-suite):
+///   - `+STR` and `-STR` for the start and end of the stream.
+///   - `+DOC` (`+DOC ---` if explicit) and `-DOC` for the start and end of a document.
+///   - `+SEQ` and `-SEQ`, `+MAP` and `-MAP` for the start and end of collections.
+///   - `=VAL` for a scalar, followed by its style (`:` plain, `'` single-quoted, `"`
+///     double-quoted, `|` literal or `>` folded) and its value, with backslashes, line breaks, tabs
+///     and backspaces escaped.
+///   - `=ALI *id` for an alias.
+///   - `=IND` followed by the indicator for an [`Event::Indicator`].
+///
+/// Nodes with an anchor have its ID (e.g.: `&1`) and nodes with a tag have it between `<` and
+/// `>` after their start.
+///
+/// ```
+/// # use saphyr_parser::{testutil::dump_events, Parser};
+/// let events: Vec<_> = Parser::new_from_str("a: &x !t 'b'").map(Result::unwrap).collect();
+/// assert_eq!(
+///     dump_events(&events),
+///     "\
+/// 1:1..1:1 +STR
+/// 1:1..1:1 +DOC
+/// 1:1..1:1 +MAP
+/// 1:1..1:2 =VAL :a
+/// 1:10..1:13 =VAL &1 <!t> 'b
+/// 2:1..2:1 -MAP
+/// 2:1..2:1 -DOC
+/// 2:1..2:1 -STR
+/// "
+/// );
+/// ```
+#[must_use]
+pub fn dump_events(events: &[(Event, Span)]) -> String {
+    let mut dump = String::new();
+    for (event, span) in events {
+        let _ = write!(
+            dump,
+            "{}:{}..{}:{} ",
+            span.start.line(),
+            span.start.col().saturating_add(1),
+            span.end.line(),
+            span.end.col().saturating_add(1),
+        );
+        write_notation(&mut dump, event);
+        dump.push('\n');
+    }
+    dump
+}
+
+/// Write `event` to `dump` in the notation of the yaml-test-suite.
+fn write_notation(dump: &mut String, event: &Event) {
+    match event {
+        Event::Nothing => dump.push_str("=NOP"),
+        Event::StreamStart => dump.push_str("+STR"),
+        Event::StreamEnd => dump.push_str("-STR"),
+        Event::DocumentStart(true) => dump.push_str("+DOC ---"),
+        Event::DocumentStart(false) => dump.push_str("+DOC"),
+        Event::DocumentEnd => dump.push_str("-DOC"),
+        Event::SequenceStart(anchor, tag) => {
+            dump.push_str("+SEQ");
+            write_properties(dump, *anchor, tag.as_ref());
+        }
+        Event::SequenceEnd => dump.push_str("-SEQ"),
+        Event::MappingStart(anchor, tag) => {
+            dump.push_str("+MAP");
+            write_properties(dump, *anchor, tag.as_ref());
+        }
+        Event::MappingEnd => dump.push_str("-MAP"),
+        Event::Scalar(value, style, anchor, tag) => {
+            dump.push_str("=VAL");
+            write_properties(dump, *anchor, tag.as_ref());
+            dump.push_str(match style {
+                TScalarStyle::Plain => " :",
+                TScalarStyle::SingleQuoted => " '",
+                TScalarStyle::DoubleQuoted => " \"",
+                TScalarStyle::Literal => " |",
+                TScalarStyle::Folded => " >",
+            });
+            for c in value.chars() {
+                match c {
+                    '\\' => dump.push_str("\\\\"),
+                    '\n' => dump.push_str("\\n"),
+                    '\r' => dump.push_str("\\r"),
+                    '\t' => dump.push_str("\\t"),
+                    '\x08' => dump.push_str("\\b"),
+                    c => dump.push(c),
+                }
+            }
+        }
+        Event::Alias(anchor) => {
+            let _ = write!(dump, "=ALI *{anchor}");
+        }
+        Event::Indicator(indicator) => {
+            let _ = write!(dump, "=IND {indicator}");
+        }
+    }
+}
+
+/// Write the anchor ID and tag of a node, if any, to `dump`.
+fn write_properties(dump: &mut String, anchor: usize, tag: Option<&Tag>) {
+    if anchor > 0 {
+        let _ = write!(dump, " &{anchor}");
+    }
+    if let Some(tag) = tag {
+        let _ = write!(dump, " <{}{}>", tag.handle, tag.suffix);
+    }
+}
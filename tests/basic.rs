@@ -1,7 +1,18 @@
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::float_cmp)]
 
-use saphyr_parser::{Event, Parser, ScanError, TScalarStyle};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use saphyr_parser::{
+    count_documents, line_start, parse_partial, scan_indentation_issues, validate, BufferedInput,
+    CommentedEvents, Conflict, DocumentEndCause, DocumentStream, Event, EventReceiver,
+    FallibleInput, FoldMode, Marker, OneDocument, Parser, ResolvedScalar, RootKind, ScalarType,
+    ScalarValue, ScanError, SchemaVersion, Span, StrInput, TScalarStyle, Tag, TeeInput,
+    UnsupportedAction,
+};
 
 /// Run the parser through the string.
 ///
@@ -145,6 +156,228 @@ a scalar
     );
 }
 
+#[test]
+fn test_multi_doc_isolation() {
+    let scalar = |v: &str| Event::Scalar(v.to_string(), TScalarStyle::Plain, 0, None);
+
+    // A deeply indented block document does not leak its indentation into a flow-only document,
+    // whether it ends with `...`, `---` or with content on the `---` line.
+    let block = "a:\n  b:\n    c:\n      - d\n      - e:\n          f\n";
+    for separator in ["...\n---\n", "---\n", "--- "] {
+        let events = run_parser(&format!("{block}{separator}{{g: [h, i], j: k}}\n")).unwrap();
+        let second = events
+            .iter()
+            .rposition(|ev| matches!(ev, Event::DocumentStart(_)))
+            .unwrap();
+        assert_eq!(
+            events[second..],
+            [
+                Event::DocumentStart(true),
+                Event::MappingStart(0, None),
+                scalar("g"),
+                Event::SequenceStart(0, None),
+                scalar("h"),
+                scalar("i"),
+                Event::SequenceEnd,
+                scalar("j"),
+                scalar("k"),
+                Event::MappingEnd,
+                Event::DocumentEnd,
+                Event::StreamEnd,
+            ],
+            "{separator:?}"
+        );
+    }
+
+    // Documents can alternate between block and flow styles.
+    let events = run_parser("- - - a\n--- [b]\n---\n{c: d}\n---\n? e\n: - f\n").unwrap();
+    assert_eq!(
+        events
+            .iter()
+            .filter(|ev| **ev == Event::DocumentEnd)
+            .count(),
+        4
+    );
+
+    // Anchors are scoped to their document, including when iterating over events.
+    assert_eq!(
+        run_parser("a: &x 1\n---\nb: *x\n").unwrap_err().info(),
+        "while parsing node, found unknown anchor"
+    );
+}
+
+#[test]
+fn test_trailing_content() {
+    // Content after the root node of a document points at the content.
+    for (input, index, line, col) in [
+        ("[1, 2] extra", 7, 1, 7),
+        ("[1, 2] extra\n", 7, 1, 7),
+        ("{a: b} c", 7, 1, 7),
+        ("[1] [2]", 4, 1, 4),
+        ("[1]\n[2]\n", 4, 2, 0),
+        ("\"a\"\n\"b\"\n", 4, 2, 0),
+        ("&x [1] *x", 7, 1, 7),
+        // Block content after a flow root node is trailing content as well.
+        ("[1,2]\n- a", 6, 2, 0),
+        ("[1,2]\n? a", 6, 2, 0),
+    ] {
+        let error = run_parser(input).unwrap_err();
+        assert_eq!(
+            error.info(),
+            "trailing content after the root node of a document is not allowed",
+            "{input:?}"
+        );
+        assert_eq!(
+            (
+                error.marker().index(),
+                error.marker().line(),
+                error.marker().col()
+            ),
+            (index, line, col),
+            "{input:?}"
+        );
+    }
+
+    // Comments and further documents are fine.
+    assert!(run_parser("[1, 2] # comment\n").is_ok());
+    assert!(run_parser("[1, 2]\n--- [3]\n...\n").is_ok());
+}
+
+#[test]
+fn test_parse_one_document() {
+    let scalar = |v: &str| Event::Scalar(v.to_string(), TScalarStyle::Plain, 0, None);
+    let one = |s: &str| Parser::new_from_str(s).parse_one_document().unwrap();
+
+    // The end marker includes `...` but not the `---` of the next document.
+    let input = "a: b\n...\n--- [c]\n# comment\n--- d\n";
+    let mut parser = Parser::new_from_str(input);
+    let mut ends = vec![];
+    let mut documents = vec![];
+    while let OneDocument::Document(events, end) = parser.parse_one_document().unwrap() {
+        ends.push(&input[end.index()..]);
+        documents.push(events);
+    }
+    assert_eq!(ends, ["\n--- [c]\n# comment\n--- d\n", "--- d\n", ""]);
+    assert_eq!(
+        documents[0],
+        [
+            Event::DocumentStart(false),
+            Event::MappingStart(0, None),
+            scalar("a"),
+            scalar("b"),
+            Event::MappingEnd,
+            Event::DocumentEnd,
+        ]
+    );
+    assert_eq!(
+        documents[2],
+        [Event::DocumentStart(true), scalar("d"), Event::DocumentEnd]
+    );
+    assert_eq!(
+        parser.parse_one_document().unwrap(),
+        OneDocument::EndOfStream
+    );
+
+    // Parsing can resume from the end marker with a new parser.
+    let mut rest = input;
+    let mut resumed = vec![];
+    while let OneDocument::Document(events, end) = one(rest) {
+        resumed.push(events);
+        rest = &rest[end.index()..];
+    }
+    assert_eq!(resumed, documents);
+
+    assert_eq!(one(""), OneDocument::EndOfStream);
+    assert_eq!(one("# comment\n...\n"), OneDocument::EndOfStream);
+
+    // Documents cut in the middle need more input.
+    for input in [
+        "a: [b, c",
+        "{a: b,\n",
+        "a: 'b",
+        "a: \"b\\",
+        "a: \"b\\u12",
+        "- !<tag:yaml.org",
+        "%YAML 1.2\n",
+        "--- &x\n- *x\n[",
+    ] {
+        assert_eq!(one(input), OneDocument::NeedsMoreInput, "{input:?}");
+    }
+
+    // Other errors are reported.
+    for input in ["[a, }", "[1, 2] x\n", "a: b: c\n", "'a' b"] {
+        assert!(
+            Parser::new_from_str(input).parse_one_document().is_err(),
+            "{input:?}"
+        );
+    }
+}
+
+#[test]
+fn test_block_scalar_folding() {
+    let scalars = |s: &str| -> Vec<String> {
+        run_parser(s)
+            .unwrap()
+            .into_iter()
+            .filter_map(|ev| match ev {
+                Event::Scalar(value, ..) => Some(value),
+                _ => None,
+            })
+            .collect()
+    };
+
+    for (input, expected) in [
+        // Lines are folded. Empty lines are kept as line breaks.
+        (">\n a\n b\n\n c\n", "a b\nc\n"),
+        (">\n a\n\n\n b\n", "a\n\nb\n"),
+        // Trailing spaces are content.
+        (">\n a \n b\n", "a  b\n"),
+        // Leading empty lines are kept.
+        (">\n\n\n a\n", "\n\na\n"),
+        (">\n  \n  \n  a\n", "\n\na\n"),
+        // More-indented lines are kept as-is, along with the line breaks around them.
+        (">\n a\n  b\n c\n", "a\n b\nc\n"),
+        (">\n a\n\n  b\n", "a\n\n b\n"),
+        (">\n a\n  b\n\n c\n", "a\n b\n\nc\n"),
+        (">\n a\n\n\n  b\n\n\n c\n", "a\n\n\n b\n\n\nc\n"),
+        (">\n a\n \tb\n c\n", "a\n\tb\nc\n"),
+        (">\n \ta\n b\n", "\ta\nb\n"),
+        (">2\n   a\n  b\n", " a\nb\n"),
+        (">\n a\n  \n b\n", "a\n \nb\n"),
+        (">\n a\n   \n b\n", "a\n  \nb\n"),
+        // Spec example 8.10.
+        (
+            ">\n\n folded\n line\n\n next\n line\n   * bullet\n\n   * list\n   * lines\n\n last\n line\n\n# Comment\n",
+            "\nfolded line\nnext line\n  * bullet\n\n  * list\n  * lines\n\nlast line\n",
+        ),
+        // Trailing empty lines are chomped.
+        (">\n a\n\n\n", "a\n"),
+        (">-\n a\n\n\n", "a"),
+        (">+\n a\n\n\n", "a\n\n\n"),
+        (">\n a\n b\n \n", "a b\n"),
+        // The last line may not end with a line break.
+        (">\n a", "a\n"),
+        (">-\n a", "a"),
+        (">\n a\n b\n ", "a b\n"),
+        ("|\n a\n ", "a\n"),
+        (">+\n a\n\n ", "a\n\n\n"),
+        // Without content, only kept empty lines remain.
+        (">\n", ""),
+        (">\n\n", ""),
+        (">-\n\n", ""),
+        (">+\n\n", "\n"),
+        ("- |\n", ""),
+    ] {
+        assert_eq!(scalars(input), [expected], "{input:?}");
+    }
+
+    // Spec example 8.6.
+    assert_eq!(
+        scalars("strip: >-\n\nclip: >\n\nkeep: |+\n\n"),
+        ["strip", "", "clip", "", "keep", "\n"]
+    );
+}
+
 #[test]
 fn test_github_27() {
     // https://github.com/chyh1990/yaml-rust/issues/27
@@ -201,6 +434,32 @@ foobar";
         ]
     );
 }
+
+#[test]
+fn test_non_specific_tag() {
+    let tag_of = |s: &str| match &run_parser(s).unwrap()[2] {
+        Event::Scalar(_, _, _, tag) => tag.clone(),
+        ev => panic!("expected a scalar, got {ev:?}"),
+    };
+
+    // A bare `!` is the non-specific tag.
+    let tag = tag_of("! foo").unwrap();
+    assert!(tag.is_non_specific());
+    assert_eq!(tag.handle, "");
+    assert_eq!(tag.suffix, "!");
+    // A `%TAG !` directive does not change it.
+    assert!(tag_of("%TAG ! tag:example.com,2000:\n--- ! foo")
+        .unwrap()
+        .is_non_specific());
+    // Nor is it confused with local or global tags.
+    assert!(!tag_of("!foo bar").unwrap().is_non_specific());
+    assert!(!tag_of("!!str foo").unwrap().is_non_specific());
+    // Untagged nodes have no tag at all.
+    assert_eq!(tag_of("foo"), None);
+    // The secondary handle alone is not a tag.
+    assert!(run_parser("!! foo").is_err());
+}
+
 #[test]
 fn test_large_block_scalar_indent() {
     // https://github.com/Ethiraric/yaml-rust2/issues/29
@@ -229,6 +488,25 @@ a: |-
     );
 }
 
+#[test]
+fn test_buffered_input_fallback_lookahead_count() {
+    let count = |input: &str| {
+        let mut parser = Parser::new(BufferedInput::new(input.chars()));
+        for event in &mut parser {
+            event.unwrap();
+        }
+        parser.input().fallback_lookahead_count()
+    };
+
+    // Nothing requires a large lookahead.
+    assert_eq!(count("a: b\nc:\n  - d\n  - |\n    e\n    f\n"), 0);
+    // The indentation fits in the buffer.
+    assert_eq!(count(&format!("a: |\n{0}b\n{0}c\n", " ".repeat(13))), 0);
+    // The indentation does not fit. The fallback is used for each line of the scalar.
+    assert_eq!(count(&format!("a: |\n{0}b\n{0}c\n", " ".repeat(14))), 2);
+    assert_eq!(count(&format!("a: |\n{0}b\n\n{0}c\n", " ".repeat(40))), 3);
+}
+
 #[test]
 fn test_bad_docstart() {
     run_parser("---This used to cause an infinite loop").unwrap();
@@ -327,3 +605,3285 @@ fn test_recursion_depth_check_arrays() {
     let s = "[".repeat(10_000) + &"]".repeat(10_000);
     assert!(run_parser(&s).is_err());
 }
+
+#[test]
+fn test_tabs_in_flow() {
+    // Tabs are valid separation between flow entries and around indicators.
+    let expected = [
+        Event::StreamStart,
+        Event::DocumentStart(false),
+        Event::SequenceStart(0, None),
+        Event::Scalar("1".to_string(), TScalarStyle::Plain, 0, None),
+        Event::Scalar("2".to_string(), TScalarStyle::Plain, 0, None),
+        Event::SequenceEnd,
+        Event::DocumentEnd,
+        Event::StreamEnd,
+    ];
+    assert_eq!(run_parser("[1,\t2]").unwrap(), expected);
+    assert_eq!(run_parser("[1\t,2]").unwrap(), expected);
+    assert_eq!(run_parser("[\t1,\t2\t]").unwrap(), expected);
+    assert_eq!(run_parser("[\n\t1,\n\t2\n]").unwrap(), expected);
+
+    let expected = [
+        Event::StreamStart,
+        Event::DocumentStart(false),
+        Event::MappingStart(0, None),
+        Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
+        Event::Scalar("b".to_string(), TScalarStyle::Plain, 0, None),
+        Event::MappingEnd,
+        Event::DocumentEnd,
+        Event::StreamEnd,
+    ];
+    assert_eq!(run_parser("{a:\tb}").unwrap(), expected);
+    assert_eq!(run_parser("{a\t:\tb}").unwrap(), expected);
+    assert_eq!(run_parser("{?\ta:\tb}").unwrap(), expected);
+}
+
+#[test]
+fn test_tabs_in_plain_scalars() {
+    // Tabs within a plain scalar are part of its content, leading and trailing ones are not.
+    assert_eq!(
+        run_parser("k:\ta\tb\t").unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            Event::MappingStart(0, None),
+            Event::Scalar("k".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("a\tb".to_string(), TScalarStyle::Plain, 0, None),
+            Event::MappingEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+    assert_eq!(
+        run_parser("[a\tb,\tc\t]").unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            Event::SequenceStart(0, None),
+            Event::Scalar("a\tb".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("c".to_string(), TScalarStyle::Plain, 0, None),
+            Event::SequenceEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+    // Block indicators followed by a tab can still introduce scalars.
+    assert!(run_parser("-\t-1").is_ok());
+    assert!(run_parser("k:\t-1").is_ok());
+    assert!(run_parser("?\tkey").is_ok());
+    assert!(run_parser("k:\t[1]").is_ok());
+    assert!(run_parser("k:\t|\n  text").is_ok());
+}
+
+#[test]
+fn test_tabs_in_indentation() {
+    // Tabs cannot be used as indentation.
+    assert!(run_parser("a:\n\tb: 1").is_err());
+    assert!(run_parser("a:\n  b: 1\n\tc: 2").is_err());
+    assert!(run_parser("- [\n\tfoo,\n foo\n ]").is_err());
+    // Nor can they separate a block indicator from a block collection on the same line.
+    assert!(run_parser("-\t- a").is_err());
+    assert!(run_parser("- \t- a").is_err());
+    assert!(run_parser("?\t- a").is_err());
+    assert!(run_parser("k:\t- a").is_err());
+    assert!(run_parser("-\tkey: value").is_err());
+    assert!(run_parser("?\tkey: value").is_err());
+    assert!(run_parser("? key\n:\tkey: value").is_err());
+    // Tab-only lines are fine.
+    assert!(run_parser("a: 1\n\t\nb: 2").is_ok());
+}
+
+#[test]
+fn test_base_indent() {
+    let run_parser_with_base_indent = |input: &str, indent: usize| {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_base_indent(indent);
+        parser
+            .map(|x| x.map(|(ev, _)| ev))
+            .collect::<Result<Vec<_>, ScanError>>()
+    };
+
+    let s = "a: 1\n    b:\n      - c\n    d: |\n      text\n";
+    assert!(run_parser(s).is_err());
+    assert_eq!(
+        run_parser_with_base_indent(s, 4).unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            Event::MappingStart(0, None),
+            Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("1".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("b".to_string(), TScalarStyle::Plain, 0, None),
+            Event::SequenceStart(0, None),
+            Event::Scalar("c".to_string(), TScalarStyle::Plain, 0, None),
+            Event::SequenceEnd,
+            Event::Scalar("d".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("text\n".to_string(), TScalarStyle::Literal, 0, None),
+            Event::MappingEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+
+    // Without a base indent, the second line is a continuation of the plain scalar.
+    let s = "- a\n    - b\n";
+    assert_eq!(
+        run_parser(s).unwrap()[3],
+        Event::Scalar("a - b".to_string(), TScalarStyle::Plain, 0, None)
+    );
+    assert_eq!(
+        run_parser_with_base_indent(s, 4).unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            Event::SequenceStart(0, None),
+            Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
+            Event::Scalar("b".to_string(), TScalarStyle::Plain, 0, None),
+            Event::SequenceEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+
+    // Lines dedented past the base indent are still misindented.
+    assert!(run_parser_with_base_indent("a: 1\nb: 2", 4).is_err());
+
+    // Only the columns of the first line are offset.
+    let mut parser = Parser::new_from_str("a: 1\n    b: 2\n    c: 3\n");
+    parser.set_base_indent(4);
+    let columns: Vec<_> = parser
+        .filter_map(|x| match x.unwrap() {
+            (Event::Scalar(..), span) => Some((span.start.line(), span.start.col())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(columns, [(1, 4), (1, 7), (2, 4), (2, 7), (3, 4), (3, 7)]);
+}
+
+#[test]
+fn test_empty_stream_and_documents() {
+    /// Collects events pushed by `Parser::load`.
+    struct EventSink(Vec<Event>);
+
+    impl EventReceiver for EventSink {
+        fn on_event(&mut self, ev: Event) {
+            self.0.push(ev);
+        }
+    }
+
+    /// Run the parser through `load`, which must agree with iterating over the parser.
+    fn run_parser_load(input: &str) -> Result<Vec<Event>, ScanError> {
+        let mut sink = EventSink(vec![]);
+        Parser::new_from_str(input).load(&mut sink, true)?;
+        Ok(sink.0)
+    }
+
+    let null = || Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None);
+
+    // No document at all.
+    for s in [
+        "",
+        "\n\n",
+        "  \n\t\n",
+        "# only a comment",
+        "# a\n\n# b\n",
+        "...",
+        "...\n...\n",
+    ] {
+        assert_eq!(
+            run_parser(s).unwrap(),
+            [Event::StreamStart, Event::StreamEnd]
+        );
+        assert_eq!(
+            run_parser_load(s).unwrap(),
+            [Event::StreamStart, Event::StreamEnd]
+        );
+    }
+
+    // A single empty explicit document.
+    for s in [
+        "---",
+        "---\n",
+        "--- # comment",
+        "---\n...",
+        "%YAML 1.2\n---",
+        "# c\n---\n# c\n",
+    ] {
+        let expected = [
+            Event::StreamStart,
+            Event::DocumentStart(true),
+            null(),
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ];
+        assert_eq!(run_parser(s).unwrap(), expected);
+        assert_eq!(run_parser_load(s).unwrap(), expected);
+    }
+
+    // A trailing `---` starts a new, empty document.
+    let expected = [
+        Event::StreamStart,
+        Event::DocumentStart(false),
+        Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
+        Event::DocumentEnd,
+        Event::DocumentStart(true),
+        null(),
+        Event::DocumentEnd,
+        Event::StreamEnd,
+    ];
+    assert_eq!(run_parser("a\n---").unwrap(), expected);
+    assert_eq!(run_parser_load("a\n---").unwrap(), expected);
+    // A trailing `...` does not.
+    assert_eq!(run_parser("a\n...\n").unwrap().len(), 5);
+
+    // Directives must be followed by a document.
+    assert!(run_parser("%YAML 1.2").is_err());
+    assert!(run_parser("%YAML 1.2\n...").is_err());
+    assert!(run_parser("%TAG ! tag:example.com,2000:\n").is_err());
+    assert!(run_parser_load("%YAML 1.2").is_err());
+}
+
+#[test]
+fn test_tee_input() {
+    let s = "%YAML 1.2
+%TAG !e! tag:example.com,2000:
+--- # comment
+key: &anchor value
+? complex key
+: !e!tag [a, 'b''c', \"d\\te\", {f: g}]
+seq:
+  - *anchor
+  - |+
+    literal
+  - >-
+    folded
+    text
+
+  - plain
+    multiline   # trailing comment
+...
+--- \"second\"
+# end
+";
+    let mut consumed = String::new();
+    let parser = Parser::new(TeeInput::new(StrInput::new(s), &mut consumed));
+    assert!(parser.collect::<Result<Vec<_>, _>>().is_ok());
+    assert_eq!(consumed, s);
+
+    let mut consumed = String::new();
+    let parser = Parser::new(TeeInput::new(BufferedInput::new(s.chars()), &mut consumed));
+    assert!(parser.collect::<Result<Vec<_>, _>>().is_ok());
+    assert_eq!(consumed, s);
+}
+
+#[test]
+fn test_large_indentation_and_nesting() {
+    let last_scalar = |events: &[Event]| {
+        events
+            .iter()
+            .rev()
+            .find_map(|ev| match ev {
+                Event::Scalar(s, ..) => Some(s.clone()),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    // Block scalars indented past 255 columns.
+    let s = format!("a: |\n{0}x\n{0} y\n", " ".repeat(300));
+    assert_eq!(last_scalar(&run_parser(&s).unwrap()), "x\n y\n");
+    let s = format!("{}a: |2\n{}x\n", " ".repeat(300), " ".repeat(302));
+    assert_eq!(last_scalar(&run_parser(&s).unwrap()), "x\n");
+
+    // Block mappings and sequences nested 300 levels deep.
+    let mut s = String::new();
+    for i in 0..300 {
+        s += &" ".repeat(i);
+        s += "k:\n";
+    }
+    s += &" ".repeat(300);
+    s += "v\n";
+    let events = run_parser(&s).unwrap();
+    assert_eq!(
+        events
+            .iter()
+            .filter(|ev| matches!(ev, Event::MappingStart(..)))
+            .count(),
+        300
+    );
+    assert_eq!(last_scalar(&events), "v");
+    let events = run_parser(&format!("{}v\n", "- ".repeat(300))).unwrap();
+    assert_eq!(
+        events
+            .iter()
+            .filter(|ev| matches!(ev, Event::SequenceStart(..)))
+            .count(),
+        300
+    );
+    assert_eq!(last_scalar(&events), "v");
+
+    // Flow collections are limited to 255 levels of nesting and error cleanly beyond that.
+    assert!(run_parser(&format!("{}{}", "[".repeat(255), "]".repeat(255))).is_ok());
+    let err = run_parser(&format!("{}{}", "[".repeat(256), "]".repeat(256))).unwrap_err();
+    assert_eq!(err.info(), "recursion limit exceeded");
+    assert_eq!(err.marker().index(), 255);
+    let err = run_parser(&format!("{}a{}", "{a: ".repeat(300), "}".repeat(300))).unwrap_err();
+    assert_eq!(err.info(), "recursion limit exceeded");
+}
+
+#[test]
+fn test_forbid_anchors() {
+    let run = |s: &str| {
+        let mut parser = Parser::new_from_str(s);
+        parser.set_forbid_anchors(true);
+        parser.collect::<Result<Vec<_>, _>>()
+    };
+
+    let err = run("&a x").unwrap_err();
+    assert_eq!(err.marker().index(), 0);
+    assert!(err.info().contains("anchor"));
+    let err = run("*a").unwrap_err();
+    assert_eq!(err.marker().index(), 0);
+    assert!(err.info().contains("alias"));
+
+    // Anchors on keys and collections, after tags, and aliases nested in flow collections.
+    assert_eq!(run("key: &a\n  - x").unwrap_err().marker().index(), 5);
+    assert_eq!(run("- !!str &a x").unwrap_err().marker().index(), 8);
+    assert_eq!(run("&a key: x").unwrap_err().marker().index(), 0);
+    assert_eq!(run("[a, {b: *c}]").unwrap_err().marker().index(), 8);
+
+    // Documents without anchors are unaffected.
+    assert!(run("a: [b, '&c', \"*d\", e&f]").is_ok());
+    // Anchors are allowed by default.
+    assert!(run_parser("a: &x 1\nb: *x").is_ok());
+}
+
+#[test]
+fn test_cr_line_endings() {
+    /// Run the parser with both inputs, returning events and the lines they start and end on.
+    fn run(input: &str) -> Vec<(Event, usize, usize)> {
+        let events: Vec<_> = Parser::new_from_str(input)
+            .map(|x| x.map(|(ev, span)| (ev, span.start.line(), span.end.line())))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let buffered: Vec<_> = Parser::new(BufferedInput::new(input.chars()))
+            .map(|x| x.map(|(ev, span)| (ev, span.start.line(), span.end.line())))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(events, buffered);
+        events
+    }
+    let scalar = |s: &str, style| Event::Scalar(s.to_string(), style, 0, None);
+
+    assert_eq!(
+        run("a:\r  b: c\r  d: e\rf: g\r"),
+        [
+            (Event::StreamStart, 1, 1),
+            (Event::DocumentStart(false), 1, 1),
+            (Event::MappingStart(0, None), 1, 1),
+            (scalar("a", TScalarStyle::Plain), 1, 1),
+            (Event::MappingStart(0, None), 2, 2),
+            (scalar("b", TScalarStyle::Plain), 2, 2),
+            (scalar("c", TScalarStyle::Plain), 2, 2),
+            (scalar("d", TScalarStyle::Plain), 3, 3),
+            (scalar("e", TScalarStyle::Plain), 3, 3),
+            (Event::MappingEnd, 4, 4),
+            (scalar("f", TScalarStyle::Plain), 4, 4),
+            (scalar("g", TScalarStyle::Plain), 4, 4),
+            (Event::MappingEnd, 5, 5),
+            (Event::DocumentEnd, 5, 5),
+            (Event::StreamEnd, 5, 5),
+        ]
+    );
+
+    assert_eq!(
+        run("# comment\r---\ra: |+\r  x\r   y\r\r# comment\rb: >\r  f\r  g\r\r  h\r...\r"),
+        [
+            (Event::StreamStart, 1, 1),
+            (Event::DocumentStart(true), 2, 2),
+            (Event::MappingStart(0, None), 3, 3),
+            (scalar("a", TScalarStyle::Plain), 3, 3),
+            (scalar("x\n y\n\n", TScalarStyle::Literal), 4, 7),
+            (scalar("b", TScalarStyle::Plain), 8, 8),
+            (scalar("f g\nh\n", TScalarStyle::Folded), 9, 13),
+            (Event::MappingEnd, 13, 13),
+            (Event::DocumentEnd, 13, 13),
+            (Event::StreamEnd, 14, 14),
+        ]
+    );
+
+    // `\r\n` is a single line break, `\n\r` are two.
+    assert_eq!(
+        run("a\r\n\rb\n\rc")[2],
+        (scalar("a\nb\nc", TScalarStyle::Plain), 1, 5)
+    );
+
+    // Line breaks in quoted scalars fold the same way regardless of their style.
+    for (input, value) in [
+        ("\"a\r\nb\"", "a b"),
+        ("\"a \r\n  b\"", "a b"),
+        ("\"a\t\r\nb\"", "a b"),
+        ("\"a\r\n\r\nb\"", "a\nb"),
+        ("\"a\\\r\n  b\"", "ab"),
+        ("'a\r\nb'", "a b"),
+        ("'a\r\n\r\nb'", "a\nb"),
+    ] {
+        let style = if input.starts_with('"') {
+            TScalarStyle::DoubleQuoted
+        } else {
+            TScalarStyle::SingleQuoted
+        };
+        let (event, start, _) = run(input).swap_remove(2);
+        assert_eq!((event, start), (scalar(value, style), 1), "{input:?}");
+    }
+    assert_eq!(
+        run("- \"a\r\n  b\"\r\n- c\r\n")[3..5],
+        [
+            (scalar("a b", TScalarStyle::DoubleQuoted), 1, 2),
+            (scalar("c", TScalarStyle::Plain), 3, 3),
+        ]
+    );
+}
+
+#[test]
+fn test_explicit_complex_keys() {
+    let plain = |s: &str| Event::Scalar(s.to_string(), TScalarStyle::Plain, 0, None);
+    let null = || plain("~");
+    let wrap = |events: Vec<Event>| {
+        let mut v = vec![Event::StreamStart, Event::DocumentStart(false)];
+        v.extend(events);
+        v.extend([Event::DocumentEnd, Event::StreamEnd]);
+        v
+    };
+
+    // A sequence as a key, with a sequence as a value.
+    assert_eq!(
+        run_parser("? - a\n  - b\n: - c\n  - d\n").unwrap(),
+        wrap(vec![
+            Event::MappingStart(0, None),
+            Event::SequenceStart(0, None),
+            plain("a"),
+            plain("b"),
+            Event::SequenceEnd,
+            Event::SequenceStart(0, None),
+            plain("c"),
+            plain("d"),
+            Event::SequenceEnd,
+            Event::MappingEnd,
+        ])
+    );
+
+    // A mapping as a key, with a mapping as a value, on the next lines.
+    assert_eq!(
+        run_parser("?\n  a: 1\n  b: 2\n:\n  c: 3\n").unwrap(),
+        wrap(vec![
+            Event::MappingStart(0, None),
+            Event::MappingStart(0, None),
+            plain("a"),
+            plain("1"),
+            plain("b"),
+            plain("2"),
+            Event::MappingEnd,
+            Event::MappingStart(0, None),
+            plain("c"),
+            plain("3"),
+            Event::MappingEnd,
+            Event::MappingEnd,
+        ])
+    );
+
+    // Multi-line flow collections as keys and values.
+    assert_eq!(
+        run_parser("? [a,\n   b]\n: {c: d,\n   e: f}\n").unwrap(),
+        wrap(vec![
+            Event::MappingStart(0, None),
+            Event::SequenceStart(0, None),
+            plain("a"),
+            plain("b"),
+            Event::SequenceEnd,
+            Event::MappingStart(0, None),
+            plain("c"),
+            plain("d"),
+            plain("e"),
+            plain("f"),
+            Event::MappingEnd,
+            Event::MappingEnd,
+        ])
+    );
+
+    // Explicit keys with empty values, either omitted or with an empty `:`.
+    let expected = wrap(vec![
+        Event::MappingStart(0, None),
+        Event::SequenceStart(0, None),
+        plain("a"),
+        Event::SequenceEnd,
+        null(),
+        plain("b"),
+        null(),
+        plain("c"),
+        plain("d"),
+        Event::MappingEnd,
+    ]);
+    assert_eq!(run_parser("? - a\n? b\n? c\n: d\n").unwrap(), expected);
+    assert_eq!(
+        run_parser("? - a\n:\n? b\n:\n? c\n: d\n").unwrap(),
+        expected
+    );
+
+    // Explicit keys in a sequence, mixed with implicit keys.
+    assert_eq!(
+        run_parser("- ? - a\n  : b\n  c: d\n- ? e\n").unwrap(),
+        wrap(vec![
+            Event::SequenceStart(0, None),
+            Event::MappingStart(0, None),
+            Event::SequenceStart(0, None),
+            plain("a"),
+            Event::SequenceEnd,
+            plain("b"),
+            plain("c"),
+            plain("d"),
+            Event::MappingEnd,
+            Event::MappingStart(0, None),
+            plain("e"),
+            null(),
+            Event::MappingEnd,
+            Event::SequenceEnd,
+        ])
+    );
+
+    // Empty values have an empty span where the value would be.
+    let spans: Vec<_> = Parser::new_from_str("? a\n? b\n:\n? c")
+        .filter_map(|x| match x.unwrap() {
+            (Event::Scalar(s, ..), span) if s == "~" => {
+                Some((span.start.index(), span.end.index()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(spans, [(4, 4), (10, 10), (13, 13)]);
+}
+
+#[test]
+fn test_json_mode() {
+    /// Run the parser with the given JSON mode, returning only the scalar values.
+    fn run(input: &str, json_mode: bool) -> Result<Vec<String>, ScanError> {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_json_mode(json_mode);
+        let mut scalars = vec![];
+        for x in parser {
+            if let Event::Scalar(value, ..) = x?.0 {
+                scalars.push(value);
+            }
+        }
+        Ok(scalars)
+    }
+
+    // Quoted keys allow an adjacent value regardless of the mode.
+    for json_mode in [false, true] {
+        assert_eq!(run(r#"{"a":1}"#, json_mode).unwrap(), ["a", "1"]);
+        assert_eq!(
+            run(r#"{"a":1,"b":[2,3],"c":{"d":4}}"#, json_mode).unwrap(),
+            ["a", "1", "b", "2", "3", "c", "d", "4"]
+        );
+    }
+
+    // Plain keys require a space after `:` in YAML.
+    assert_eq!(run("{1:2}", false).unwrap(), ["1:2", "~"]);
+    assert_eq!(run("{1:2}", true).unwrap(), ["1", "2"]);
+    assert_eq!(run("[a:b, c:d]", false).unwrap(), ["a:b", "c:d"]);
+    assert_eq!(run("[a:b, c:d]", true).unwrap(), ["a", "b", "c", "d"]);
+    assert!(run("{a:[1]}", false).is_err());
+    assert_eq!(
+        run("{a:[1],b:{c:2}}", true).unwrap(),
+        ["a", "1", "b", "c", "2"]
+    );
+
+    // Block contexts are unaffected.
+    assert_eq!(run("a:b: c", true).unwrap(), ["a:b", "c"]);
+    assert_eq!(
+        run("- http://example.com", true).unwrap(),
+        ["http://example.com"]
+    );
+}
+
+#[test]
+fn test_max_line_length() {
+    let run = |s: &str, max: usize| {
+        let mut parser = Parser::new_from_str(s);
+        parser.set_max_line_length(max);
+        parser.collect::<Result<Vec<_>, _>>()
+    };
+    let run_buffered = |s: &str, max: usize| {
+        let mut parser = Parser::new(BufferedInput::new(s.chars()));
+        parser.set_max_line_length(max);
+        parser.collect::<Result<Vec<_>, _>>()
+    };
+
+    // Lines of exactly `max` characters are accepted, line breaks excluded.
+    assert!(run("abc: de\nf: g\n", 7).is_ok());
+    assert!(run("abc: de\r\nf: g\r\n", 7).is_ok());
+    assert!(run("abc: def\nf: g\n", 7).is_err());
+
+    for run in [run, run_buffered] {
+        let long = "x".repeat(100);
+        // In the middle of a plain scalar.
+        let err = run(&format!("a: {long}\n"), 50).unwrap_err();
+        assert_eq!(err.info(), "line exceeds the maximum line length");
+        assert_eq!(err.marker().line(), 1);
+        // In quoted scalars.
+        assert!(run(&format!("a: 'b {long}'\n"), 50).is_err());
+        assert!(run(&format!("a: \"{long}\"\n"), 50).is_err());
+        // In a block scalar.
+        assert!(run(&format!("a: |\n  {long}\n"), 50).is_err());
+        // Scalars are reported where they cross the limit, not at the end of the line.
+        let huge = "x".repeat(1_000_000);
+        for input in [
+            format!("{huge}\n"),
+            format!("'{huge}'\n"),
+            format!("\"{huge}\"\n"),
+            format!("|\n{huge}\n"),
+            format!("|\n x\n {huge}\n"),
+            format!("'a {huge}'\n"),
+        ] {
+            let err = run(&input, 100).unwrap_err();
+            assert_eq!(err.info(), "line exceeds the maximum line length");
+            assert_eq!(err.marker().col(), 101);
+        }
+        // In a comment, or on the last line without a line break.
+        assert!(run(&format!("a: b # {long}\nc: d\n"), 50).is_err());
+        assert!(run(&format!("a: [b, c]\n[{long}]"), 50).is_err());
+        // Within a flow collection spread across many tokens.
+        let flow = format!("[{}]", vec!["1"; 40].join(", "));
+        let err = run(&flow, 50).unwrap_err();
+        assert_eq!(err.marker().line(), 1);
+        assert!(err.marker().col() > 50);
+        // Long documents with short lines are fine.
+        assert!(run(&"- a\n".repeat(1000), 3).is_ok());
+    }
+
+    // There is no limit by default.
+    assert!(run_parser(&"x".repeat(100_000)).is_ok());
+}
+
+#[test]
+fn test_control_chars() {
+    let run = |s: &str, allow: bool| {
+        let mut parser = Parser::new_from_str(s);
+        parser.set_allow_control_chars(allow);
+        parser.collect::<Result<Vec<_>, _>>()
+    };
+    let run_buffered = |s: &str, allow: bool| {
+        let mut parser = Parser::new(BufferedInput::new(s.chars()));
+        parser.set_allow_control_chars(allow);
+        parser.collect::<Result<Vec<_>, _>>()
+    };
+
+    for run in [run, run_buffered] {
+        // The error points at the control character, in every scalar style.
+        for (input, index) in [
+            ("a: b\x07c\n", 4),
+            ("a: bé\x07\n", 6),
+            ("a: \x07\n", 3),
+            ("a: 'b\x07'\n", 5),
+            ("a: \"b\x07\"\n", 5),
+            ("- [b, c\x07]\n", 7),
+            ("a: |\n  b\n  c\x07\n", 12),
+            ("a: >\n  \x7f\n", 7),
+        ] {
+            let err = run(input, false).unwrap_err();
+            assert_eq!(
+                err.info(),
+                format!(
+                    "found control character U+{:04X}, which is not allowed in YAML",
+                    input[index..].chars().next().unwrap() as u32
+                ),
+                "{input:?}"
+            );
+            assert_eq!(err.marker().index(), index, "{input:?}");
+            // They are accepted when allowed.
+            assert!(run(input, true).is_ok(), "{input:?}");
+        }
+
+        // They are rejected outside of scalars too.
+        for (input, index) in [
+            ("a: b # c\x07\n", 8),
+            ("a: b # é\x07\n", 9),
+            ("# \x07\na: b\n", 2),
+            ("&a\x07b c\n", 2),
+            ("- *a\x07\n", 4),
+            ("!a\x07 b\n", 2),
+            ("!<a\x07> b\n", 3),
+            ("[!a\x07]\n", 3),
+            ("%TAG !a\x07! tag:a\n--- b\n", 7),
+            ("%TAG ! tag:a\x07\n--- b\n", 12),
+            ("%YAML 1\x07.2\n--- b\n", 7),
+            ("%FOO\x07\n--- b\n", 4),
+            ("%FOO bar\x07\n--- b\n", 8),
+        ] {
+            let err = run(input, false).unwrap_err();
+            assert_eq!(
+                err.info(),
+                "found control character U+0007, which is not allowed in YAML",
+                "{input:?}"
+            );
+            assert_eq!(err.marker().index(), index, "{input:?}");
+        }
+        assert!(run("a: b # c\x07\n", true).is_ok());
+        assert!(run("&a\x07b c\n", true).is_ok());
+
+        // Tabs, escape sequences and non-ASCII characters are fine.
+        assert!(run("a: \"\\a\tb\"\nc: |\n  \td\né: \u{85}\u{a0}\n", false).is_ok());
+        assert!(run("a: b # c\té \u{85}\n", false).is_ok());
+        // C1 control characters other than `U+0085` are rejected.
+        assert!(run("a: \u{9b}\n", false).is_err());
+    }
+
+    // The value is kept as-is when allowed.
+    let events = run("a: b\x07c\n", true).unwrap();
+    assert!(events
+        .iter()
+        .any(|(ev, _)| matches!(ev, Event::Scalar(v, ..) if v == "b\x07c")));
+}
+
+#[test]
+fn test_extra_whitespace() {
+    /// Return the events of `input`, treating non-breaking spaces as whitespace if `lenient`.
+    fn events(input: &str, lenient: bool) -> Result<Vec<Event>, ScanError> {
+        let mut parser = Parser::new_from_str(input);
+        let mut buffered = Parser::new(BufferedInput::new(input.chars()));
+        if lenient {
+            parser.set_extra_whitespace(&['\u{a0}']);
+            buffered.set_extra_whitespace(&['\u{a0}']);
+        }
+        let events: Vec<_> = parser
+            .map(|ev| ev.map(|(ev, _)| ev))
+            .collect::<Result<_, _>>()?;
+        let buffered: Vec<_> = buffered
+            .map(|ev| ev.map(|(ev, _)| ev))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(events, buffered, "{input:?}");
+        Ok(events)
+    }
+
+    // Non-breaking spaces separate tokens as spaces do.
+    for input in [
+        "[a, b]",
+        "[a , b ]",
+        "{a: 1, b: 2}",
+        "a: b\nc:  d\n",
+        "- a\n- b \n",
+        "a: [b, c] # comment\n",
+        "? a\n: b\n",
+        "a: 'b'  \n",
+    ] {
+        let nbsp = input.replace(' ', "\u{a0}");
+        assert_eq!(
+            events(&nbsp, true).unwrap(),
+            events(input, false).unwrap(),
+            "{nbsp:?}"
+        );
+    }
+    /// Return the values of the scalars of `input`.
+    fn scalars(input: &str, lenient: bool) -> Vec<String> {
+        events(input, lenient)
+            .unwrap()
+            .into_iter()
+            .filter_map(|ev| match ev {
+                Event::Scalar(value, ..) => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // By default, they are content.
+    assert_eq!(scalars("[a,\u{a0}b]", false), ["a", "\u{a0}b"]);
+    assert_eq!(scalars("a:\u{a0}b", false), ["a:\u{a0}b"]);
+
+    // They are kept within scalars, but not at their end.
+    assert_eq!(
+        scalars("[a\u{a0}b\u{a0}, 'c\u{a0}']", true),
+        ["a\u{a0}b", "c\u{a0}"]
+    );
+    // They are never indentation.
+    assert_eq!(scalars("[a,\n\u{a0}b]", true), ["a", "\u{a0}b"]);
+    assert!(events("a:\n\u{a0}b\n", true).is_err());
+
+    // Spans account for their length in bytes.
+    let mut parser = Parser::new_from_str("[a,\u{a0}b]");
+    parser.set_extra_whitespace(&['\u{a0}']);
+    let spans: Vec<_> = parser
+        .map(Result::unwrap)
+        .filter(|(ev, _)| ev.is_scalar())
+        .map(|(_, span)| (span.start.index(), span.end.index()))
+        .collect();
+    assert_eq!(spans, [(1, 2), (5, 6)]);
+}
+
+#[test]
+fn test_expect_root() {
+    let run = |s: &str, kind: RootKind| {
+        let mut parser = Parser::new_from_str(s);
+        parser.expect_root(kind);
+        parser.collect::<Result<Vec<_>, _>>()
+    };
+
+    for (input, kind, found, index) in [
+        ("a scalar", RootKind::Mapping, "a scalar", 0),
+        ("--- 'a'\n", RootKind::Mapping, "a scalar", 4),
+        ("# comment\n- a\n", RootKind::Mapping, "a sequence", 10),
+        ("a: b\n", RootKind::Sequence, "a mapping", 0),
+        ("--- !t {a: b}\n", RootKind::Scalar, "a mapping", 7),
+        // An empty document holds an empty scalar.
+        ("---\n...\n", RootKind::Mapping, "a scalar", 4),
+        // Every document is checked.
+        ("a: b\n--- [c]\n", RootKind::Mapping, "a sequence", 9),
+    ] {
+        let err = run(input, kind).unwrap_err();
+        let expected = match kind {
+            RootKind::Mapping => "a mapping",
+            RootKind::Sequence => "a sequence",
+            _ => "a scalar",
+        };
+        assert_eq!(
+            err.info(),
+            format!("expected {expected} at the root of the document, found {found}"),
+            "{input:?}"
+        );
+        assert_eq!(err.marker().index(), index, "{input:?}");
+    }
+
+    // Only the root node is checked.
+    assert!(run("a: [b, {c: d}]\n--- {e: f}\n", RootKind::Mapping).is_ok());
+    assert!(run("- a\n- b: c\n", RootKind::Sequence).is_ok());
+    assert!(run("--- &x !t a\n--- |\n b\n", RootKind::Scalar).is_ok());
+    // There is no expectation by default.
+    assert!(run_parser("a scalar").is_ok());
+}
+
+#[test]
+fn test_max_flow_items() {
+    let run = |s: &str, max: usize| {
+        let mut parser = Parser::new_from_str(s);
+        parser.set_max_flow_items(max);
+        parser.collect::<Result<Vec<_>, _>>()
+    };
+
+    assert!(run("[1, 2, 3]", 3).is_ok());
+    assert!(run("[1, 2, 3,]", 3).is_ok());
+    assert!(run("{a: 1, b: 2, c: 3}", 3).is_ok());
+    assert!(run("{a: 1, b: 2, c: 3, }", 3).is_ok());
+
+    let err = run("[1, 2, 3, 4]", 3).unwrap_err();
+    assert_eq!(
+        err.info(),
+        "flow collection exceeds the maximum number of items"
+    );
+    assert_eq!(err.marker().index(), 10);
+    let err = run("{a: 1, b: 2, c: 3, d: 4}", 3).unwrap_err();
+    assert_eq!(
+        err.info(),
+        "flow collection exceeds the maximum number of items"
+    );
+    assert_eq!(err.marker().index(), 19);
+    // Implicit keys, empty keys and single-pair mappings in sequences are items as well.
+    assert!(run("{a, ? b, : c, d}", 3).is_err());
+    assert!(run("[a: 1, b: 2, c: 3, d: 4]", 3).is_err());
+
+    // Nested collections have their own count, and count as one item in their parent.
+    assert!(run("[[1, 2, 3], {a: 1, b: 2, c: 3}, [1, 2, 3]]", 3).is_ok());
+    assert!(run("[[1, 2, 3], [1, 2, 3, 4]]", 3).is_err());
+    assert!(run("[1, [2], 3, 4]", 3).is_err());
+
+    // Block collections are not affected.
+    assert!(run("- 1\n- 2\n- 3\n- 4\n", 3).is_ok());
+    assert!(run("a: 1\nb: 2\nc: 3\nd: 4\n", 3).is_ok());
+}
+
+#[test]
+fn test_is_finished() {
+    let mut parser = Parser::new_from_str("- a\n- b\n");
+    assert!(!parser.is_finished());
+    let mut events = vec![];
+    while !parser.is_finished() {
+        events.push(parser.next_event().unwrap().unwrap().0);
+    }
+    assert_eq!(events.last(), Some(&Event::StreamEnd));
+    assert!(parser.next_event().is_none());
+    assert!(parser.is_finished());
+
+    // A peeked `StreamEnd` has not been returned yet.
+    let mut parser = Parser::new_from_str("");
+    parser.next_event().unwrap().unwrap();
+    assert_eq!(parser.peek().unwrap().unwrap().0, Event::StreamEnd);
+    assert!(!parser.is_finished());
+    assert_eq!(parser.next_event().unwrap().unwrap().0, Event::StreamEnd);
+    assert!(parser.is_finished());
+
+    // Parsing is complete after an error.
+    let mut parser = Parser::new_from_str("[a, b");
+    while parser.next_event().unwrap().is_ok() {
+        assert!(!parser.is_finished());
+    }
+    assert!(parser.is_finished());
+}
+
+#[test]
+fn test_next_event_into() {
+    /// Collect events from [`Parser::next_event_into`], putting scalar values back in the events.
+    fn run(
+        mut next_event_into: impl FnMut(&mut String) -> Option<Result<(Event, Span), ScanError>>,
+    ) -> Vec<Event> {
+        let mut buf = String::new();
+        let mut events = vec![];
+        while let Some(ev) = next_event_into(&mut buf) {
+            let mut ev = ev.unwrap().0;
+            if let Event::Scalar(value, ..) = &mut ev {
+                assert!(value.is_empty());
+                value.clone_from(&buf);
+            }
+            events.push(ev);
+        }
+        events
+    }
+
+    let yaml =
+        "a: |\n  literal\n  text\nb: [c, 'd e', \"f\\tg\"]\n? - h\n: >\n  folded\n---\nplain\n";
+    let expected = run_parser(yaml).unwrap();
+    let mut parser = Parser::new_from_str(yaml);
+    assert_eq!(run(|buf| parser.next_event_into(buf)), expected);
+    let mut parser = Parser::new(BufferedInput::new(yaml.chars()));
+    assert_eq!(run(|buf| parser.next_event_into(buf)), expected);
+
+    // The allocation of the buffer is reused for the following scalars.
+    let long = "x".repeat(1000);
+    let yaml = format!("- {long}\n- y\n- 'z'\n");
+    let mut parser = Parser::new_from_str(&yaml);
+    let mut buf = String::new();
+    let mut scalars = vec![];
+    while let Some(ev) = parser.next_event_into(&mut buf) {
+        if let Event::Scalar(..) = ev.unwrap().0 {
+            scalars.push((buf.clone(), buf.capacity()));
+        }
+    }
+    assert_eq!(scalars[0].0, long);
+    assert_eq!(scalars[1].0, "y");
+    assert!(scalars[1].1 >= 1000);
+    assert_eq!(scalars[2].0, "z");
+    assert!(scalars[2].1 >= 1000);
+}
+
+#[test]
+fn test_multiline_plain_scalars() {
+    let plain = |s: &str| Event::Scalar(s.to_string(), TScalarStyle::Plain, 0, None);
+    let mapping = |events: Vec<Event>| {
+        let mut v = vec![
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            Event::MappingStart(0, None),
+        ];
+        v.extend(events);
+        v.extend([Event::MappingEnd, Event::DocumentEnd, Event::StreamEnd]);
+        v
+    };
+
+    // A more indented line continues the scalar, a line break is folded into a space.
+    assert_eq!(
+        run_parser("a: b\n  c").unwrap(),
+        mapping(vec![plain("a"), plain("b c")])
+    );
+    // A line that is not more indented than the mapping starts a new key.
+    assert_eq!(
+        run_parser("a: b\nc:").unwrap(),
+        mapping(vec![plain("a"), plain("b"), plain("c"), plain("~")])
+    );
+    // Empty lines are kept as line breaks, surrounding whitespace is stripped.
+    assert_eq!(
+        run_parser("a: b  \n\n    c\n\n\n  d \n").unwrap(),
+        mapping(vec![plain("a"), plain("b\nc\n\nd")])
+    );
+    // `#` and `:` not preceded, resp. followed, by whitespace are part of the scalar.
+    assert_eq!(
+        run_parser("a: b\n  c#d\n  e:f\n").unwrap(),
+        mapping(vec![plain("a"), plain("b c#d e:f")])
+    );
+    // A comment ends the scalar, whether it is on the scalar's line or on the next one.
+    assert_eq!(
+        run_parser("a: b\n  c #d\ne: f\n").unwrap(),
+        mapping(vec![plain("a"), plain("b c"), plain("e"), plain("f")])
+    );
+    assert_eq!(
+        run_parser("a: b\n# c\ne: f\n").unwrap(),
+        mapping(vec![plain("a"), plain("b"), plain("e"), plain("f")])
+    );
+    assert!(run_parser("a: b #c\n  d").is_err());
+    assert!(run_parser("a: b\n  #c\n  d").is_err());
+    // Implicit keys cannot span multiple lines.
+    assert!(run_parser("a: b\n  c: d").is_err());
+    assert!(run_parser("a\n b: c").is_err());
+    // A document marker ends the scalar.
+    assert_eq!(
+        run_parser("a\n---\nb").unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            plain("a"),
+            Event::DocumentEnd,
+            Event::DocumentStart(true),
+            plain("b"),
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+    // In flow collections, continuation lines need not be indented more than the collection.
+    assert_eq!(
+        run_parser("- [a\n  b, c\n\n   d]").unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            Event::SequenceStart(0, None),
+            Event::SequenceStart(0, None),
+            plain("a b"),
+            plain("c\nd"),
+            Event::SequenceEnd,
+            Event::SequenceEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+}
+
+#[test]
+fn test_state_name() {
+    let mut parser = Parser::new_from_str("a: [b]\n");
+    let mut states = vec![parser.state_name()];
+    while let Some(ev) = parser.next_event() {
+        ev.unwrap();
+        states.push(parser.state_name());
+    }
+    assert_eq!(
+        states,
+        [
+            "StreamStart",
+            "ImplicitDocumentStart",
+            "BlockNode",
+            "BlockMappingFirstKey",
+            "BlockMappingValue",
+            "FlowSequenceFirstEntry",
+            "FlowSequenceEntry",
+            "BlockMappingKey",
+            "DocumentEnd",
+            "DocumentStart",
+            "End",
+        ]
+    );
+}
+
+#[test]
+fn test_truncated_escape_sequences() {
+    let first_error = |s: &str| {
+        let err = Parser::new_from_str(s).find_map(Result::err).unwrap();
+        let buffered = Parser::new(BufferedInput::new(s.chars()))
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(err.marker(), buffered.marker());
+        assert_eq!(err.info(), buffered.info());
+        err
+    };
+
+    // Escape sequences cut by the end of input, at various offsets so that they straddle the
+    // boundary of the buffer of `BufferedInput`.
+    for escape in [
+        "\\",
+        "\\x",
+        "\\x4",
+        "\\u",
+        "\\u1",
+        "\\u12",
+        "\\u123",
+        "\\U",
+        "\\U0001F60",
+    ] {
+        for padding in 0..20 {
+            let yaml = format!("- {}\"{escape}", " ".repeat(padding));
+            let err = first_error(&yaml);
+            assert_eq!(
+                err.info(),
+                "while parsing a quoted scalar, found unexpected end of stream in escape sequence",
+                "{yaml:?}"
+            );
+            assert_eq!(err.marker().index(), yaml.len() - escape.len(), "{yaml:?}");
+        }
+    }
+
+    // Other invalid escape sequences point at the escape as well.
+    let err = first_error("a: \"b\\qc\"");
+    assert_eq!(
+        err.info(),
+        "while parsing a quoted scalar, found unknown escape character"
+    );
+    assert_eq!(err.marker().index(), 5);
+    let err = first_error("a: \"b\\x4gc\"");
+    assert_eq!(
+        err.info(),
+        "while parsing a quoted scalar, did not find expected hexadecimal number"
+    );
+    assert_eq!(err.marker().index(), 5);
+    let err = first_error("a: \"b\\UFFFFFFFF\"");
+    assert_eq!(
+        err.info(),
+        "while parsing a quoted scalar, found invalid Unicode character escape code"
+    );
+    assert_eq!(err.marker().index(), 5);
+}
+
+#[test]
+fn test_schema_bool_and_null() {
+    /// Resolve the scalars of `input` with the given schema.
+    fn run(input: &str, schema: SchemaVersion) -> Vec<Option<ResolvedScalar>> {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_schema(schema);
+        assert_eq!(parser.schema(), schema);
+        let mut resolved = vec![];
+        while let Some(ev) = parser.next_event() {
+            let (event, _) = ev.unwrap();
+            if event.is_scalar() {
+                resolved.push(parser.resolve_bool_or_null(&event));
+            }
+        }
+        resolved
+    }
+    use ResolvedScalar::{Bool, Null};
+
+    let yaml = "[on, off, yes, no, Y, n, ON, Off]";
+    assert_eq!(run(yaml, SchemaVersion::Core1_2), [None; 8]);
+    assert_eq!(run(yaml, SchemaVersion::Json), [None; 8]);
+    assert_eq!(
+        run(yaml, SchemaVersion::Yaml1_1),
+        [
+            Some(Bool(true)),
+            Some(Bool(false)),
+            Some(Bool(true)),
+            Some(Bool(false)),
+            Some(Bool(true)),
+            Some(Bool(false)),
+            Some(Bool(true)),
+            Some(Bool(false)),
+        ]
+    );
+
+    let yaml = "[true, false, True, FALSE, null, Null, NULL, ~, ]";
+    let core = [
+        Some(Bool(true)),
+        Some(Bool(false)),
+        Some(Bool(true)),
+        Some(Bool(false)),
+        Some(Null),
+        Some(Null),
+        Some(Null),
+        Some(Null),
+    ];
+    assert_eq!(run(yaml, SchemaVersion::Core1_2), core);
+    assert_eq!(run(yaml, SchemaVersion::Yaml1_1), core);
+    assert_eq!(
+        run(yaml, SchemaVersion::Json),
+        [
+            Some(Bool(true)),
+            Some(Bool(false)),
+            None,
+            None,
+            Some(Null),
+            None,
+            None,
+            Some(Null),
+        ]
+    );
+
+    // Empty nodes are null in all schemas.
+    for schema in [
+        SchemaVersion::Core1_2,
+        SchemaVersion::Json,
+        SchemaVersion::Yaml1_1,
+    ] {
+        assert_eq!(run("a:", schema), [None, Some(Null)]);
+    }
+
+    // Only untagged plain scalars are resolved.
+    assert_eq!(
+        run(
+            "['yes', \"on\", !!bool yes, ! on, yes]",
+            SchemaVersion::Yaml1_1
+        ),
+        [None, None, None, None, Some(Bool(true))]
+    );
+    // The default schema is the core schema.
+    assert_eq!(Parser::new_from_str("").schema(), SchemaVersion::Core1_2);
+}
+
+#[test]
+fn test_schema_scalar_type() {
+    /// Resolve the types of the scalars of `input` with the given schema.
+    fn run(input: &str, schema: SchemaVersion) -> Vec<Option<ScalarType>> {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_schema(schema);
+        let mut types = vec![];
+        while let Some(ev) = parser.next_event() {
+            let (event, _) = ev.unwrap();
+            if event.is_scalar() {
+                types.push(parser.resolve_type(&event));
+            }
+        }
+        types
+    }
+    use ScalarType::{Bool, Float, Int, Null, Str};
+
+    // The "Norway problem": `NO` is a boolean in YAML 1.1 only.
+    let yaml = "[NO, yes, 1.0, 0x1e3, ~]";
+    assert_eq!(
+        run(yaml, SchemaVersion::Core1_2),
+        [Some(Str), Some(Str), Some(Float), Some(Int), Some(Null)]
+    );
+    assert_eq!(
+        run(yaml, SchemaVersion::Json),
+        [Some(Str), Some(Str), Some(Float), Some(Str), Some(Null)]
+    );
+    assert_eq!(
+        run(yaml, SchemaVersion::Yaml1_1),
+        [Some(Bool), Some(Bool), Some(Float), Some(Int), Some(Null)]
+    );
+
+    // Only untagged plain scalars are resolved.
+    assert_eq!(
+        run("['1', \"2\", !!str 3, 4]", SchemaVersion::Core1_2),
+        [None, None, None, Some(Int)]
+    );
+
+    for (value, core, json, yaml11) in [
+        ("0", Int, Int, Int),
+        ("-12", Int, Int, Int),
+        ("+12", Int, Str, Int),
+        ("012", Int, Str, Int),
+        ("0o14", Int, Str, Str),
+        ("0b1100", Str, Str, Int),
+        ("1_000", Str, Str, Int),
+        ("190:20:30", Str, Str, Int),
+        ("1.", Float, Float, Float),
+        (".5", Float, Str, Float),
+        ("1e3", Float, Float, Str),
+        ("1.5e+3", Float, Float, Float),
+        ("1_000.5", Str, Str, Float),
+        ("190:20:30.15", Str, Str, Float),
+        ("-.inf", Float, Str, Float),
+        (".NaN", Float, Str, Float),
+        (".", Str, Str, Str),
+        ("1.2.3", Str, Str, Str),
+        ("0x", Str, Str, Str),
+        ("e3", Str, Str, Str),
+    ] {
+        for (schema, expected) in [
+            (SchemaVersion::Core1_2, core),
+            (SchemaVersion::Json, json),
+            (SchemaVersion::Yaml1_1, yaml11),
+        ] {
+            assert_eq!(
+                schema.resolve_type(value),
+                expected,
+                "{value} in {schema:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_parse_numbers() {
+    use ScalarValue::{Float, Int};
+    use SchemaVersion::{Core1_2, Json, Yaml1_1};
+
+    for (schema, value, expected) in [
+        (Core1_2, "12", Some(Int(12))),
+        (Core1_2, "+12", Some(Int(12))),
+        (Core1_2, "012", Some(Int(12))),
+        (Core1_2, "0o14", Some(Int(12))),
+        (Core1_2, "0xC", Some(Int(12))),
+        (Core1_2, "-9223372036854775808", Some(Int(i64::MIN))),
+        (Core1_2, "9223372036854775808", None),
+        (Core1_2, "1.5", Some(Float(1.5))),
+        (Core1_2, "-.5e1", Some(Float(-5.0))),
+        (Core1_2, "-.inf", Some(Float(f64::NEG_INFINITY))),
+        (Core1_2, "NO", None),
+        (Json, "-12", Some(Int(-12))),
+        (Json, "1e3", Some(Float(1000.0))),
+        (Json, "0x1e3", None),
+        (Yaml1_1, "014", Some(Int(12))),
+        (Yaml1_1, "-0b1100", Some(Int(-12))),
+        (Yaml1_1, "0x_C", Some(Int(12))),
+        (Yaml1_1, "1_000", Some(Int(1000))),
+        (Yaml1_1, "-190:20:30", Some(Int(-685_230))),
+        (Yaml1_1, "1_000.5", Some(Float(1000.5))),
+        (Yaml1_1, "190:20:30.15", Some(Float(685_230.15))),
+        (Yaml1_1, "1e3", None),
+    ] {
+        assert_eq!(
+            schema.parse_number(value),
+            expected,
+            "{value} in {schema:?}"
+        );
+    }
+    assert!(matches!(Core1_2.parse_number(".NaN"), Some(Float(f)) if f.is_nan()));
+
+    // Only untagged plain scalars are parsed, when enabled.
+    let yaml = "[1, '2', !!int 3, 4.5, a]";
+    let mut parser = Parser::new_from_str(yaml);
+    let mut numbers = vec![];
+    while let Some(ev) = parser.next_event() {
+        ev.unwrap();
+        numbers.push(parser.number());
+    }
+    assert!(numbers.iter().all(Option::is_none));
+
+    let mut parser = Parser::new_from_str(yaml);
+    parser.set_parse_numbers(true);
+    let mut buf = String::new();
+    let mut numbers = vec![];
+    while let Some(ev) = parser.next_event_into(&mut buf) {
+        if ev.unwrap().0.is_scalar() {
+            numbers.push(parser.number());
+        }
+    }
+    assert_eq!(numbers, [Some(Int(1)), None, None, Some(Float(4.5)), None]);
+}
+
+#[test]
+fn test_parse_partial() {
+    // Events before an error are those of any valid input starting with the same content.
+    for (invalid, valid, error_line) in [
+        ("a: 1\nb: c: d\ne: f\n", "a: 1\nb: c\n", 2),
+        ("- [a, b\n", "- [a, b]\n", 2),
+        ("a: 1\nb: 'unterminated\n", "a: 1\nb: 'x'\n", 2),
+        ("[a, *x, b]", "[a, b]", 1),
+        ("a:\n\tb: c\n", "a:\n  b: c\n", 2),
+        ("--- a\n--- [b", "--- a\n--- [b]", 3),
+        ("]", "a", 1),
+    ] {
+        let (events, error) = parse_partial(invalid);
+        let expected = run_parser(valid).unwrap();
+        assert!(expected.starts_with(&events), "{invalid:?}: {events:?}");
+        assert_eq!(
+            error.map(|e| e.marker().line()),
+            Some(error_line),
+            "{invalid:?}"
+        );
+        // The iterator yields the same events before the error.
+        let iterated: Vec<_> = Parser::new_from_str(invalid)
+            .map_while(Result::ok)
+            .map(|(ev, _)| ev)
+            .collect();
+        assert_eq!(iterated, events, "{invalid:?}");
+    }
+
+    // Valid inputs are fully parsed.
+    let yaml = "a: [b, {c: d}]\n--- e\n";
+    assert_eq!(parse_partial(yaml), (run_parser(yaml).unwrap(), None));
+}
+
+#[test]
+fn test_validate() {
+    for valid in [
+        "",
+        "# only a comment\n",
+        "a: 1\nb: [c, {d: e}]\n",
+        "--- &x |\n  text\n--- [&y a, *y]\n...\n",
+        "%YAML 1.2\n--- \"quoted\\tescape\"\n",
+    ] {
+        assert_eq!(validate(valid), Ok(()), "{valid:?}");
+        // `validate` agrees with collecting events.
+        assert!(run_parser(valid).is_ok());
+    }
+
+    for (invalid, info) in [
+        (
+            "key: [1, 2]]\n",
+            "while parsing a block mapping, did not find expected key",
+        ),
+        (
+            "a: b: c\n",
+            "mapping values are not allowed in this context",
+        ),
+        (
+            "[a, b\n",
+            "while parsing a flow sequence, expected ',' or ']'",
+        ),
+        (
+            "\"\\q\"",
+            "while parsing a quoted scalar, found unknown escape character",
+        ),
+        ("*unknown\n", "while parsing node, found unknown anchor"),
+        (
+            "a:\n\t- b\n",
+            "tabs disallowed within this context (block indentation)",
+        ),
+    ] {
+        let error = validate(invalid).unwrap_err();
+        assert_eq!(error.info(), info, "{invalid:?}");
+        // The error is the first one the parser would return.
+        assert_eq!(run_parser(invalid).unwrap_err(), error, "{invalid:?}");
+    }
+}
+
+/// Parse `input` in front matter mode and return the events and the rest of the input.
+fn run_frontmatter_parser(input: &str) -> Result<(Vec<Event>, &str), ScanError> {
+    let mut parser = Parser::new_from_str(input);
+    parser.set_frontmatter_mode(true);
+    let mut events = vec![];
+    let mut rest = input;
+    for x in parser {
+        let (event, span) = x?;
+        if event == Event::StreamEnd {
+            rest = &input[span.start.index()..];
+        }
+        events.push(event);
+    }
+    Ok((events, rest))
+}
+
+#[test]
+fn test_frontmatter_mode() {
+    let scalar = |value: &str| Event::Scalar(value.to_owned(), TScalarStyle::Plain, 0, None);
+    let front_matter = [
+        Event::StreamStart,
+        Event::DocumentStart(true),
+        Event::MappingStart(0, None),
+        scalar("title"),
+        scalar("Hello"),
+        scalar("tags"),
+        Event::SequenceStart(0, None),
+        scalar("a"),
+        scalar("b"),
+        Event::SequenceEnd,
+        Event::MappingEnd,
+        Event::DocumentEnd,
+        Event::StreamEnd,
+    ];
+
+    // The Markdown body is not valid YAML and is never scanned.
+    let body = "\n# Title: with [colon\n\n`code`\n---\n@not: yaml\n";
+    for (input, rest) in [
+        (format!("---\ntitle: Hello\ntags: [a, b]\n---{body}"), body),
+        (
+            format!("---\ntitle: Hello\ntags:\n- a\n- b\n...{body}"),
+            body,
+        ),
+        (
+            format!("#!/usr/bin/env tool\n---\ntitle: Hello\ntags: [a, b]\n---{body}"),
+            body,
+        ),
+    ] {
+        let (events, actual_rest) = run_frontmatter_parser(&input).unwrap();
+        assert_eq!(events, front_matter, "{input:?}");
+        assert_eq!(actual_rest, rest, "{input:?}");
+    }
+
+    // Markers are relative to the whole input.
+    let mut parser = Parser::new_from_str("#!/usr/bin/env tool\n---\na: b\n---\nbody\n");
+    parser.set_frontmatter_mode(true);
+    let spans: Vec<_> = parser
+        .filter_map(|x| match x.unwrap() {
+            (Event::Scalar(..), span) => Some((span.start.line(), span.start.index())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(spans, [(3, 24), (3, 27)]);
+
+    // An empty front matter.
+    let (events, rest) = run_frontmatter_parser("---\n---\nbody").unwrap();
+    assert_eq!(
+        events,
+        [
+            Event::StreamStart,
+            Event::DocumentStart(true),
+            scalar("~"),
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+    assert_eq!(rest, "\nbody");
+
+    // Inputs without front matter have no document and are not scanned.
+    for input in ["# Title: [\nbody\n", "title: Hello\n---\n", "", "  ---\n"] {
+        let (events, rest) = run_frontmatter_parser(input).unwrap();
+        assert_eq!(events, [Event::StreamStart, Event::StreamEnd], "{input:?}");
+        assert_eq!(rest, input, "{input:?}");
+    }
+
+    // The closing fence is required.
+    let error = run_frontmatter_parser("---\ntitle: Hello\n").unwrap_err();
+    assert_eq!(
+        error.info(),
+        "while parsing a front matter, did not find the closing '---' or '...'"
+    );
+
+    // Without front matter mode, the body is parsed.
+    assert!(run_parser(&format!("---\na: b\n---{body}")).is_err());
+}
+
+#[test]
+fn test_conflicting_indicators() {
+    let conflict_of = |input: &str| {
+        let error = run_parser(input).unwrap_err();
+        (
+            error.marker().index(),
+            error
+                .conflict()
+                .map(|c| (c.found, c.other, c.other_mark.index())),
+        )
+    };
+
+    // A second `:` where a value was expected.
+    assert_eq!(conflict_of("a: b: c"), (4, Some((':', ':', 1))));
+    assert_eq!(conflict_of("x:\n  a: b: c"), (9, Some((':', ':', 6))));
+    let error = run_parser("- a: b: c").unwrap_err();
+    assert_eq!(
+        error.info(),
+        "mapping values are not allowed in this context"
+    );
+    assert_eq!(
+        error.conflict(),
+        Some(&Conflict {
+            found: ':',
+            other: ':',
+            other_mark: Marker::new(3, 1, 3),
+        })
+    );
+
+    // Block indicators after a mapping value on the same line.
+    assert_eq!(conflict_of("a: - b"), (3, Some(('-', ':', 1))));
+    assert_eq!(conflict_of("a: ? b"), (3, Some(('?', ':', 1))));
+
+    // Block sequence entries in flow collections.
+    assert_eq!(conflict_of("[a, - b]"), (4, Some(('-', '[', 0))));
+    assert_eq!(
+        conflict_of("{a: [b], c: {d: - e}}"),
+        (16, Some(('-', '{', 12)))
+    );
+
+    // No indicator precedes the misplaced one on the same line.
+    assert_eq!(conflict_of("a:\n  b\n  c: d"), (10, None));
+}
+
+#[test]
+fn test_max_anchor_bytes() {
+    let run = |input: &str, max: usize| {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_max_anchor_bytes(max);
+        parser.try_for_each(|x| x.map(|_| ()))
+    };
+
+    // Many long, distinct anchor names.
+    let mut yaml = String::new();
+    for i in 0..1000 {
+        yaml += &format!("- &{}{i} {i}\n", "a".repeat(100));
+    }
+    assert_eq!(run(&yaml, 200_000), Ok(()));
+    let error = run(&yaml, 10_000).unwrap_err();
+    assert_eq!(
+        error.info(),
+        "while parsing anchor, anchor names exceed the maximum total size"
+    );
+    // The 99th anchor (101 bytes for the first 10, 102 after) exceeds the limit.
+    assert_eq!(error.marker().line(), 99);
+    assert_eq!(error.marker().col(), 2);
+
+    // Reusing a name does not count twice.
+    let yaml = "- &anchor 1\n- &anchor 2\n- *anchor\n".repeat(100);
+    assert_eq!(run(&yaml, 6), Ok(()));
+    assert!(run(&yaml, 5).is_err());
+}
+
+#[test]
+fn test_directives() {
+    let scalar = |v: &str| Event::Scalar(v.to_owned(), TScalarStyle::Plain, 0, None);
+
+    // A directive followed by an empty document.
+    assert_eq!(
+        run_parser("%YAML 1.2\n---").unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(true),
+            scalar("~"),
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ]
+    );
+
+    // Tag directives apply to the document that follows them, all of them.
+    let events = run_parser("%TAG ! !local-\n%TAG !e! tag:e,2000:\n--- [!a 1, !e!b 2]").unwrap();
+    let tags: Vec<_> = events
+        .iter()
+        .filter_map(|ev| ev.as_scalar().and_then(|(_, _, _, tag)| tag))
+        .map(|tag| format!("{}{}", tag.handle, tag.suffix))
+        .collect();
+    assert_eq!(tags, ["!local-a", "tag:e,2000:b"]);
+
+    // Unknown directives are ignored.
+    assert_eq!(
+        run_parser("%FOO bar baz\n--- a").unwrap(),
+        run_parser("--- a").unwrap()
+    );
+    assert_eq!(
+        run_parser("%FOO\n%YAML 1.2\n--- a").unwrap(),
+        run_parser("--- a").unwrap()
+    );
+
+    const MALFORMED_HANDLE: &str =
+        "while parsing a tag directive, found malformed tag handle (expected `!', `!!' or `!name!')";
+    for (input, info, index, line, col) in [
+        // Duplicate directives in the same document.
+        (
+            "%YAML 1.2\n%YAML 1.2\n---",
+            "duplicate version directive",
+            10,
+            2,
+            0,
+        ),
+        (
+            "%YAML 1.2\n%TAG ! !x\n%YAML 1.1\n---",
+            "duplicate version directive",
+            20,
+            3,
+            0,
+        ),
+        (
+            "%TAG ! !a\n%TAG ! !b\n--- x",
+            "the TAG directive must only be given at most once per handle in the same document",
+            10,
+            2,
+            0,
+        ),
+        // Directives must be followed by `---`.
+        (
+            "%YAML 1.2\na",
+            "did not find expected <document start>",
+            10,
+            2,
+            0,
+        ),
+        (
+            "%YAML 1.2\n",
+            "did not find expected <document start>",
+            10,
+            2,
+            0,
+        ),
+        (
+            "%FOO\n...\n",
+            "did not find expected <document start>",
+            5,
+            2,
+            0,
+        ),
+        // `%TAG` directives must have a well-formed handle and a prefix.
+        ("%TAG x tag:a\n--- b", MALFORMED_HANDLE, 0, 1, 0),
+        ("%TAG !a tag:a\n--- b", MALFORMED_HANDLE, 0, 1, 0),
+        ("%TAG !a!b tag:a\n--- b", MALFORMED_HANDLE, 0, 1, 0),
+        ("%TAG !a%! tag:a\n--- b", MALFORMED_HANDLE, 0, 1, 0),
+        (
+            "%TAG !a!\n--- b",
+            "while parsing a tag directive, did not find expected tag prefix",
+            0,
+            1,
+            0,
+        ),
+        (
+            "%TAG !a!  # c\n--- b",
+            "while parsing a tag directive, did not find expected tag prefix",
+            0,
+            1,
+            0,
+        ),
+        // Directives after a document must follow an explicit `...`.
+        (
+            "a: b\n%YAML 1.2\n--- c",
+            "missing explicit document end marker before directive",
+            5,
+            2,
+            0,
+        ),
+    ] {
+        let error = run_parser(input).unwrap_err();
+        assert_eq!(error.info(), info, "{input:?}");
+        assert_eq!(
+            (
+                error.marker().index(),
+                error.marker().line(),
+                error.marker().col()
+            ),
+            (index, line, col),
+            "{input:?}"
+        );
+    }
+
+    // In a plain scalar, a `%` line is content rather than a directive.
+    assert_eq!(
+        run_parser("a\n%YAML 1.2\n--- b").unwrap()[2],
+        scalar("a %YAML 1.2")
+    );
+
+    // Directives of different documents do not conflict.
+    assert!(run_parser("%YAML 1.2\n--- a\n...\n%YAML 1.2\n--- b").is_ok());
+    assert!(run_parser("%TAG ! !a\n--- x\n...\n%TAG ! !b\n--- y").is_ok());
+}
+
+#[test]
+fn test_input_read_error() {
+    use std::io;
+
+    /// Parse `input` followed by a read error, returning the events and the error.
+    fn run(input: &str) -> (Vec<Event>, ScanError) {
+        let error = io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        );
+        let chars = input.chars().map(Ok).chain([Err(error)]);
+        let mut parser = Parser::new(FallibleInput::new(chars));
+        let mut events = vec![];
+        for x in &mut parser {
+            match x {
+                Ok((ev, _)) => events.push(ev),
+                Err(e) => {
+                    // The error is not forgotten once reported.
+                    assert_eq!(parser.next_event().unwrap().unwrap_err(), e);
+                    return (events, e);
+                }
+            }
+        }
+        panic!("the read error of {input:?} was not reported");
+    }
+
+    let (events, err) = run("a: 1\nb: [x, y");
+    assert_eq!(
+        err.info(),
+        "error reading input: stream did not contain valid UTF-8"
+    );
+    assert_eq!(err.marker().index(), 13);
+    let source = std::error::Error::source(&err).unwrap();
+    assert_eq!(
+        source.downcast_ref::<io::Error>().unwrap().kind(),
+        io::ErrorKind::InvalidData
+    );
+    // The truncated sequence is not reported as the end of the stream.
+    assert!(!events.contains(&Event::StreamEnd));
+    assert!(!events.contains(&Event::SequenceEnd));
+
+    // The error is reported even where the input could have ended.
+    let (events, err) = run("a: 1\n");
+    assert!(err.info().starts_with("error reading input"));
+    assert!(!events.contains(&Event::StreamEnd));
+    let (events, _) = run("");
+    assert_eq!(events, []);
+
+    // Inputs without errors end normally.
+    let chars = "a: 1\n".chars().map(Ok::<_, io::Error>);
+    let events: Vec<_> = Parser::new(FallibleInput::new(chars))
+        .map(|x| x.unwrap().0)
+        .collect();
+    assert_eq!(events.last(), Some(&Event::StreamEnd));
+}
+
+#[test]
+fn test_reserved_indicators() {
+    // `@` and `` ` `` cannot start a plain scalar, in block and flow contexts alike.
+    for (input, index) in [
+        ("@foo", 0),
+        ("`foo", 0),
+        ("a: @b", 3),
+        ("- `b", 2),
+        ("[a, @b]", 4),
+        ("{a: `b}", 4),
+        ("? @a\n: b", 2),
+    ] {
+        let err = run_parser(input).unwrap_err();
+        assert_eq!(err.marker().index(), index, "{input:?}");
+        assert!(err.info().contains("reserved indicator"), "{input:?}");
+    }
+
+    // They are fine inside quoted scalars and after the first character of a plain scalar.
+    for (input, value) in [
+        ("'@foo'", "@foo"),
+        ("\"`foo\"", "`foo"),
+        ("a@b", "a@b"),
+        ("a`b", "a`b"),
+    ] {
+        let events = run_parser(input).unwrap();
+        assert!(events[2].scalar_value_eq(value), "{input:?}");
+    }
+}
+
+#[test]
+fn test_anchored_empty_nodes() {
+    let plain = |s: &str, anchor| Event::Scalar(s.to_string(), TScalarStyle::Plain, anchor, None);
+
+    // Anchored empty nodes are empty scalars keeping their anchor, which aliases refer to.
+    for (input, start, key) in [
+        ("a: &x\nb: *x", 1, "b"),
+        ("{a: &x , b: *x}", 1, "b"),
+        ("- &x\n- *x", 0, ""),
+        ("[&x , *x]", 0, ""),
+        ("&x : v\n*x : w", 0, ""),
+    ] {
+        let events = run_parser(input).unwrap();
+        let events = &events[3..events.len() - 3];
+        let anchored = events.iter().position(|ev| ev == &plain("", 1));
+        let alias = events.iter().position(|ev| ev == &Event::Alias(1));
+        assert_eq!(anchored, Some(start), "{input:?}");
+        assert!(alias > anchored, "{input:?}");
+        if !key.is_empty() {
+            assert_eq!(events[alias.unwrap() - 1], plain(key, 0), "{input:?}");
+        }
+    }
+
+    // So are anchored and tagged empty nodes.
+    let events = run_parser("a: !!str &x\nb: *x").unwrap();
+    assert!(matches!(&events[4], Event::Scalar(v, _, 1, Some(_)) if v.is_empty()));
+}
+
+#[test]
+fn test_anchored_and_tagged_collections() {
+    let tag = |suffix: &str| {
+        Some(Tag {
+            handle: "tag:yaml.org,2002:".to_owned(),
+            suffix: suffix.to_owned(),
+        })
+    };
+
+    // Properties before a collection, in either order, belong to its start event. Its children
+    // have none.
+    for (input, start) in [
+        ("&a !!map {x: 1}", Event::MappingStart(1, tag("map"))),
+        ("!!map &a {x: 1}", Event::MappingStart(1, tag("map"))),
+        ("&a !!seq [1]", Event::SequenceStart(1, tag("seq"))),
+        ("!!seq &a [1]", Event::SequenceStart(1, tag("seq"))),
+        ("&a !!map\nx: 1\n", Event::MappingStart(1, tag("map"))),
+        ("!!map &a\n? x\n: 1\n", Event::MappingStart(1, tag("map"))),
+        ("!!seq &a\n- 1\n", Event::SequenceStart(1, tag("seq"))),
+        ("--- &a !!seq\n- 1\n", Event::SequenceStart(1, tag("seq"))),
+        ("k: &a !!map\n  x: 1\n", Event::MappingStart(1, tag("map"))),
+        ("k: !!seq &a\n- 1\n", Event::SequenceStart(1, tag("seq"))),
+        ("- &a !!map\n  x: 1\n", Event::MappingStart(1, tag("map"))),
+        ("[&a !!map {x: 1}]", Event::MappingStart(1, tag("map"))),
+        ("{k: !!seq &a [1]}", Event::SequenceStart(1, tag("seq"))),
+    ] {
+        let events = run_parser(input).unwrap();
+        let with_properties: Vec<_> = events
+            .iter()
+            .filter(|ev| match ev {
+                Event::Scalar(_, _, anchor, tag)
+                | Event::SequenceStart(anchor, tag)
+                | Event::MappingStart(anchor, tag) => *anchor != 0 || tag.is_some(),
+                _ => false,
+            })
+            .collect();
+        assert_eq!(with_properties, [&start], "{input:?}");
+    }
+
+    // Properties on the same line as the first key of a block mapping belong to that key.
+    let events = run_parser("&a !!str x: 1").unwrap();
+    assert_eq!(events[2], Event::MappingStart(0, None));
+    assert_eq!(
+        events[3],
+        Event::Scalar("x".to_owned(), TScalarStyle::Plain, 1, tag("str"))
+    );
+}
+
+#[test]
+fn test_scan_indentation_issues() {
+    let yaml = "\
+server:
+\thost: localhost
+  port: 80
+  \tuser: admin
+\t
+\t# a comment
+list:
+  - a
+ \t - b
+\t\t- c\r\n\tend: é
+";
+    let issues: Vec<_> = scan_indentation_issues(yaml)
+        .iter()
+        .map(|mark| (mark.index(), mark.line(), mark.col()))
+        .collect();
+    assert_eq!(
+        issues,
+        [(8, 2, 0), (38, 4, 2), (79, 9, 1), (85, 10, 0), (92, 11, 0)]
+    );
+    for (index, ..) in issues {
+        assert_eq!(yaml.as_bytes()[index], b'\t');
+    }
+
+    // The parser rejects the first of them.
+    assert_eq!(run_parser(yaml).unwrap_err().marker().line(), 2);
+    let fixed = yaml.replace("\n\t", "\n ").replace(" \t", "  ");
+    assert!(scan_indentation_issues(&fixed).is_empty());
+
+    // Tabs after the indentation or on empty lines are fine.
+    assert!(scan_indentation_issues("a:\tb\n- \tc\n\t\n\t\r\n").is_empty());
+    assert!(scan_indentation_issues("").is_empty());
+}
+
+#[test]
+fn test_document_events() {
+    /// Collect the events of each document, stopping each after `limit` events.
+    fn run(input: &str, limit: usize) -> Vec<Vec<Result<Event, ScanError>>> {
+        let mut documents: DocumentStream<_> = Parser::new_from_str(input).into_document_events();
+        let mut all = vec![];
+        while let Some(document) = documents.next_document() {
+            all.push(
+                document
+                    .take(limit)
+                    .map(|ev| ev.map(|(ev, _)| ev))
+                    .collect(),
+            );
+        }
+        all
+    }
+    let plain = |s: &str| Ok(Event::Scalar(s.to_string(), TScalarStyle::Plain, 0, None));
+
+    let docs = run("a\n---\nb\n...\n--- [c]\n", usize::MAX);
+    assert_eq!(
+        docs,
+        [
+            vec![
+                Ok(Event::DocumentStart(false)),
+                plain("a"),
+                Ok(Event::DocumentEnd)
+            ],
+            vec![
+                Ok(Event::DocumentStart(true)),
+                plain("b"),
+                Ok(Event::DocumentEnd)
+            ],
+            vec![
+                Ok(Event::DocumentStart(true)),
+                Ok(Event::SequenceStart(0, None)),
+                plain("c"),
+                Ok(Event::SequenceEnd),
+                Ok(Event::DocumentEnd),
+            ],
+        ]
+    );
+
+    // The events of a document that were not pulled are skipped.
+    let docs = run("[a, b]\n--- c\n--- {d: e}\n", 1);
+    assert_eq!(
+        docs,
+        [
+            [Ok(Event::DocumentStart(false))],
+            [Ok(Event::DocumentStart(true))],
+            [Ok(Event::DocumentStart(true))],
+        ]
+    );
+
+    // An error ends the current document and the stream.
+    let docs = run("a\n--- [b, c\n--- d\n", usize::MAX);
+    assert_eq!(docs.len(), 2);
+    assert_eq!(
+        docs[1][..3],
+        [
+            Ok(Event::DocumentStart(true)),
+            Ok(Event::SequenceStart(0, None)),
+            plain("b")
+        ]
+    );
+    assert!(docs[1].last().unwrap().is_err());
+
+    // An error before a document starts is its only event.
+    let docs = run("a\n...\n%FOO\n", usize::MAX);
+    assert_eq!(docs.len(), 2);
+    assert!(matches!(&docs[1][..], [Err(_)]));
+
+    assert!(run("", usize::MAX).is_empty());
+    assert!(run("# only a comment\n", usize::MAX).is_empty());
+
+    // The parser can be retrieved, e.g. to read the rest of the stream as a flat list of events.
+    let mut documents = Parser::new_from_str("a\n--- b\n").into_document_events();
+    assert!(documents.next_document().is_some());
+    let rest: Vec<_> = documents.into_parser().map(|ev| ev.unwrap().0).collect();
+    assert_eq!(rest[0], Event::DocumentStart(true));
+}
+
+#[test]
+fn test_document_end_cause() {
+    /// Return the cause of each document end of `input`.
+    fn causes(input: &str) -> Vec<DocumentEndCause> {
+        let mut parser = Parser::new_from_str(input);
+        assert_eq!(parser.document_end_cause(), None);
+        let mut causes = vec![];
+        while let Some(event) = parser.next_event() {
+            if event.unwrap().0 == Event::DocumentEnd {
+                causes.push(parser.document_end_cause().unwrap());
+            }
+        }
+        causes
+    }
+    use DocumentEndCause::{Eof, ExplicitDots, NextDocumentMarker};
+
+    assert_eq!(causes("a"), [Eof]);
+    assert_eq!(causes("a\n...\n"), [ExplicitDots]);
+    assert_eq!(causes("--- a\n--- b\n"), [NextDocumentMarker, Eof]);
+    assert_eq!(causes("a\n...\n--- b\n...\n"), [ExplicitDots, ExplicitDots]);
+    assert_eq!(
+        causes("[a]\n---\n{b: c}\n...\n# end\n"),
+        [NextDocumentMarker, ExplicitDots]
+    );
+    assert_eq!(
+        causes("%YAML 1.2\n---\na\n...\n%YAML 1.2\n---\nb"),
+        [ExplicitDots, Eof]
+    );
+    assert!(causes("").is_empty());
+}
+
+#[test]
+fn test_block_scalar_sink() {
+    /// Return the values of the scalars of `input`, with the content of block scalars streamed,
+    /// and the number of chunks streamed.
+    fn streamed(input: &str) -> (Vec<String>, usize) {
+        let chunks = Arc::new(Mutex::new(vec![]));
+        let mut parser = Parser::new_from_str(input);
+        let sink = Arc::clone(&chunks);
+        parser.set_block_scalar_sink(move |chunk| sink.lock().unwrap().push(chunk.to_owned()));
+        let mut values = vec![];
+        let mut n_chunks = 0;
+        while let Some(event) = parser.next() {
+            if let (Event::Scalar(value, style, ..), _) = event.unwrap() {
+                let chunks = std::mem::take(&mut *chunks.lock().unwrap());
+                assert_eq!(parser.last_scalar_streamed(), !chunks.is_empty());
+                if style.is_block() {
+                    assert!(value.is_empty());
+                    n_chunks += chunks.len();
+                    values.push(chunks.concat());
+                } else {
+                    assert!(chunks.is_empty());
+                    values.push(value);
+                }
+            }
+        }
+        (values, n_chunks)
+    }
+    /// Return the values of the scalars of `input`.
+    fn values(input: &str) -> Vec<String> {
+        run_parser(input)
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Scalar(value, ..) => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    let line = format!("  {}\n", "x".repeat(99));
+    let big = format!("log: |\n{}", line.repeat(2000));
+    let (streamed_values, n_chunks) = streamed(&big);
+    assert_eq!(streamed_values, values(&big));
+    assert_eq!(streamed_values[1].len(), 200_000);
+    assert!(n_chunks > 1);
+
+    for input in [
+        "a: |\n  x\n  y\nb: >+\n  folded\n  line\n\n\nc: plain\n",
+        "- |-\n  x\n\n- >\n  y\n  \n  z\n- |\n",
+        "- |+\n\n",
+    ] {
+        assert_eq!(streamed(input).0, values(input), "{input:?}");
+    }
+
+    // Empty block scalars are not streamed and are told apart from the streamed ones.
+    let mut parser = Parser::new_from_str("- |\n  x\n- |\n- >-\n\n- y\n");
+    parser.set_block_scalar_sink(|_| {});
+    let mut flags = vec![];
+    while let Some(event) = parser.next() {
+        if let (Event::Scalar(..), _) = event.unwrap() {
+            flags.push(parser.last_scalar_streamed());
+        }
+    }
+    assert_eq!(flags, [true, false, false, false]);
+}
+
+#[test]
+fn test_bom() {
+    /// Return the plain scalars of `input`.
+    fn scalars(input: &str) -> Vec<String> {
+        run_parser(input)
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Scalar(value, ..) => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Return the line and column of the error when parsing `input`.
+    fn error(input: &str) -> (usize, usize) {
+        let err = run_parser(input).unwrap_err();
+        assert_eq!(
+            err.info(),
+            "a byte order mark may only appear at the start of the stream or of a document"
+        );
+        (err.marker().line(), err.marker().col())
+    }
+
+    // At the start of the stream, the BOM does not count towards indentation.
+    assert_eq!(scalars("\u{FEFF}a: 1\nb: 2\n"), ["a", "1", "b", "2"]);
+    assert_eq!(scalars("\u{FEFF}%YAML 1.2\n---\na\n"), ["a"]);
+    assert_eq!(scalars("\u{FEFF}# comment\n--- a\n"), ["a"]);
+    assert!(scalars("\u{FEFF}").is_empty());
+
+    // After a document end marker or before a document start marker.
+    assert_eq!(scalars("a\n...\n\u{FEFF}%YAML 1.2\n---\nb\n"), ["a", "b"]);
+    assert_eq!(scalars("a\n...\n\u{FEFF}b\n"), ["a", "b"]);
+    assert_eq!(scalars("--- a\n\u{FEFF}--- b\n"), ["a", "b"]);
+
+    // Quoted scalars may contain a BOM.
+    assert_eq!(scalars("\"a\u{FEFF}b\""), ["a\u{FEFF}b"]);
+
+    // Anywhere else, it is an error.
+    assert_eq!(error("a: \u{FEFF}b\n"), (1, 3));
+    assert_eq!(error("a\u{FEFF}b\n"), (1, 1));
+    assert_eq!(error("- a\n\u{FEFF}- b\n"), (2, 0));
+    assert_eq!(error("[\u{FEFF}a]\n"), (1, 1));
+    assert_eq!(error("--- a\n\u{FEFF}%YAML 1.2\n--- b\n"), (2, 0));
+}
+
+#[test]
+fn test_max_flow_depth() {
+    /// Return the error of parsing `input` with flow collections nested at most `max` levels.
+    fn error(input: &str, max: usize) -> Option<ScanError> {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_max_flow_depth(max);
+        parser.find_map(Result::err)
+    }
+
+    let nested = format!("{}{}", "[".repeat(50_000), "]".repeat(50_000));
+    let err = error(&nested, 64).unwrap();
+    assert_eq!(
+        err.info(),
+        "flow collections exceed the maximum nesting depth"
+    );
+    assert_eq!(err.marker().index(), 64);
+
+    assert!(error("[[a], {b: [c]}]", 3).is_none());
+    assert_eq!(error("[[a], {b: [c]}]", 2).unwrap().marker().index(), 10);
+    assert_eq!(error("{a: b}", 0).unwrap().marker().index(), 0);
+
+    // Block collections are not affected.
+    assert!(error("- - - a\n  - b: [c]\n", 1).is_none());
+}
+
+#[test]
+fn test_max_simple_keys() {
+    /// Return the error of parsing `input` with at most `max` possible simple keys pending.
+    fn error(input: &str, max: usize) -> Option<ScanError> {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_max_simple_keys(max);
+        parser.find_map(Result::err)
+    }
+
+    // Every `[` may start a key of its parent collection.
+    let nested = format!("{}{}", "[".repeat(50_000), "]".repeat(50_000));
+    let err = error(&nested, 64).unwrap();
+    assert_eq!(err.info(), "possible simple keys exceed the maximum");
+    assert_eq!(err.marker().index(), 64);
+
+    assert!(error("[[[a]: b]: c]", 4).is_none());
+    assert_eq!(error("[[[a]: b]: c]", 3).unwrap().marker().index(), 3);
+    assert_eq!(error("[a, b, c]", 1).unwrap().marker().index(), 1);
+    assert_eq!(error("a: b", 0).unwrap().marker().index(), 0);
+
+    // Possible keys of the same collection replace each other.
+    assert!(error("[a, b, c]", 2).is_none());
+    assert!(error("a: b\nc: d\ne:\n  f: g\n", 1).is_none());
+}
+
+#[test]
+fn test_commented_events() {
+    /// Return the events of `input` that have comments, with the text of the comments and whether
+    /// they are trailing.
+    fn comments(input: &str) -> Vec<(Event, Vec<(&str, bool)>)> {
+        CommentedEvents::new(Parser::new_from_str(input))
+            .map(Result::unwrap)
+            .filter(|(_, _, comments)| !comments.is_empty())
+            .map(|(event, _, comments)| {
+                let comments = comments.iter().map(|c| (c.text, c.trailing)).collect();
+                (event, comments)
+            })
+            .collect()
+    }
+    let scalar = |value: &str| Event::Scalar(value.to_owned(), TScalarStyle::Plain, 0, None);
+
+    let yaml = "# head\nx:\n  # above a\n  a: 1 # one\n\n  # above y\ny: [b, c] # flow\n# tail\n";
+    assert_eq!(
+        comments(yaml),
+        [
+            (scalar("x"), vec![(" head", false)]),
+            (scalar("a"), vec![(" above a", false)]),
+            (scalar("1"), vec![(" one", true)]),
+            (scalar("y"), vec![(" above y", false)]),
+            (Event::SequenceEnd, vec![(" flow", true)]),
+            (Event::StreamEnd, vec![(" tail", false)]),
+        ]
+    );
+
+    // Several comments for the same event, around documents.
+    assert_eq!(
+        comments("--- # doc\n# one\n# two\n- v # v\n...\n"),
+        [
+            (Event::DocumentStart(true), vec![(" doc", true)]),
+            (
+                scalar("v"),
+                vec![(" one", false), (" two", false), (" v", true)]
+            ),
+        ]
+    );
+
+    // Spans of comments.
+    let yaml = "é: b # ü\n";
+    let (_, _, comments) = CommentedEvents::new(Parser::new_from_str(yaml))
+        .map(Result::unwrap)
+        .find(|(_, _, comments)| !comments.is_empty())
+        .unwrap();
+    let span = comments[0].span;
+    assert_eq!(&yaml[span.start.index()..span.end.index()], "# ü");
+    assert_eq!((span.start.col(), span.end.col()), (5, 8));
+
+    // Events before an error are returned with their comments, then the error.
+    let mut events = CommentedEvents::new(Parser::new_from_str("[a # c\n"));
+    let last = events.by_ref().map_while(Result::ok).last().unwrap();
+    assert_eq!(last.0, scalar("a"));
+    assert_eq!(last.2[0].text, " c");
+}
+
+#[test]
+fn test_misindented_keys() {
+    /// Return the message of the error of `input`, and the line and column it points at.
+    fn error(input: &str) -> (String, usize, usize) {
+        let err = run_parser(input).unwrap_err();
+        (
+            err.info().to_owned(),
+            err.marker().line(),
+            err.marker().col(),
+        )
+    }
+    let more = |expected: usize, found: usize| {
+        format!(
+            "mapping key is more indented than its siblings (expected {expected} spaces, found \
+             {found})"
+        )
+    };
+    let less = |expected: usize, found: usize| {
+        format!(
+            "mapping key is less indented than its siblings (expected {expected} spaces, found \
+             {found})"
+        )
+    };
+
+    // Keys indented more than their siblings, read as a continuation of the previous value.
+    assert_eq!(error("a: 1\n b: 2\n"), (more(0, 1), 2, 1));
+    assert_eq!(error("a:\n  b: 1\n    c: 2\n"), (more(2, 4), 3, 4));
+    assert_eq!(error("- a: 1\n   b: 2\n"), (more(2, 3), 2, 3));
+
+    // Keys indented less than their siblings, but more than the enclosing collection.
+    assert_eq!(error("a:\n  b: 1\n c: 2\n"), (less(2, 1), 3, 1));
+    assert_eq!(error("a:\n  b:\n    c: 1\n   d: 2\n"), (less(4, 3), 4, 3));
+    assert_eq!(error("- a: 1\n b: 2\n"), (less(2, 1), 2, 1));
+
+    // Keys at the indentation of an enclosing mapping are its keys.
+    assert!(run_parser("a:\n  b: 1\nc: 2\n").is_ok());
+    assert!(run_parser("a:\n  b: 1\n  c:\n    d: 2\n").is_ok());
+
+    // A value on its own line is not the value of a key on the previous one.
+    assert_eq!(
+        error("a:\n  b\n  c: d\n").0,
+        "mapping values are not allowed in this context"
+    );
+    assert_eq!(
+        error("- a\n  b: 1\n").0,
+        "mapping values are not allowed in this context"
+    );
+}
+
+#[test]
+fn test_metrics() {
+    let yaml = "\
+a: &x 1
+b:
+  - [c, {d: \"e\\tf\"}]
+  - *x
+---
+g
+";
+    let mut parser = Parser::new_from_str(yaml);
+    assert_eq!(parser.metrics(), None);
+    parser.set_collect_metrics(true);
+    for event in &mut parser {
+        event.unwrap();
+    }
+    let metrics = parser.metrics().unwrap();
+    assert_eq!(metrics.events(), 22);
+    assert_eq!(metrics.max_depth(), 4);
+    assert_eq!(metrics.documents(), 2);
+    assert_eq!(metrics.aliases(), 1);
+    // `a`, `1`, `b`, `c`, `d`, `e\tf` and `g`.
+    assert_eq!(metrics.scalar_bytes(), 9);
+
+    // Metrics are collected from when they are enabled, and peeked events count once.
+    let mut parser = Parser::new_from_str("[a, b]");
+    parser.next_event().unwrap().unwrap();
+    parser.set_collect_metrics(true);
+    parser.peek().unwrap().unwrap();
+    assert_eq!(parser.metrics().unwrap().events(), 1);
+    for event in &mut parser {
+        event.unwrap();
+    }
+    let metrics = parser.metrics().unwrap();
+    assert_eq!((metrics.events(), metrics.documents()), (7, 1));
+    assert_eq!(metrics.max_depth(), 1);
+
+    parser.set_collect_metrics(false);
+    assert_eq!(parser.metrics(), None);
+}
+
+#[test]
+fn test_decode_binary() {
+    let yaml = "a: !!binary aGVsbG8=\nb: !!binary |\n  aGVs\n  bG8gd29y\n  bGQ=\nc: aGVsbG8=\nd: !<tag:yaml.org,2002:binary> AAH/\n";
+    let decoded: Vec<_> = Parser::new_from_str(yaml)
+        .filter_map(|ev| {
+            let (event, span) = ev.unwrap();
+            event.decode_binary(span)
+        })
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        decoded,
+        [b"hello".to_vec(), b"hello world".to_vec(), vec![0, 1, 255]]
+    );
+
+    for (value, info) in [
+        ("aGVsbG8", "invalid length of base64 binary data"),
+        ("aGV*bG8=", "invalid character `*' in base64 binary data"),
+        (
+            "aG=sbG8=",
+            "padding may only appear at the end of base64 binary data",
+        ),
+        ("a===", "invalid length of base64 binary data"),
+    ] {
+        let yaml = format!("key: !!binary {value}");
+        let (event, span) = Parser::new_from_str(&yaml)
+            .map(Result::unwrap)
+            .find(|(ev, _)| ev.scalar_value_eq(value))
+            .unwrap();
+        let error = event.decode_binary(span).unwrap().unwrap_err();
+        assert_eq!(error.info(), info);
+        assert_eq!(error.marker().col(), 14);
+    }
+}
+
+#[test]
+fn test_indentation() {
+    let yaml = "\
+a:
+  b: &x c
+  d:
+  - e
+  - - f
+    - g: [h, i]
+      j: *x
+k: !t
+    l
+---
+m
+";
+    let mut parser = Parser::new_from_str(yaml);
+    parser.set_record_indentation(true);
+    let mut indents = vec![];
+    while let Some(event) = parser.next_event() {
+        let (event, _) = event.unwrap();
+        if !matches!(
+            event,
+            Event::StreamStart | Event::StreamEnd | Event::DocumentStart(_) | Event::DocumentEnd
+        ) {
+            indents.push((event, parser.indentation()));
+        }
+    }
+    let scalar = |v: &str| Event::Scalar(v.to_owned(), TScalarStyle::Plain, 0, None);
+    let mut expected = vec![
+        (Event::MappingStart(0, None), Some(0)),
+        (scalar("a"), Some(0)),
+        (Event::MappingStart(0, None), Some(2)),
+        (scalar("b"), Some(2)),
+        (
+            Event::Scalar("c".to_owned(), TScalarStyle::Plain, 1, None),
+            Some(2),
+        ),
+        (scalar("d"), Some(2)),
+        (Event::SequenceStart(0, None), Some(2)),
+        (scalar("e"), Some(2)),
+        (Event::SequenceStart(0, None), Some(4)),
+        (scalar("f"), Some(4)),
+        (Event::MappingStart(0, None), Some(6)),
+        (scalar("g"), Some(6)),
+        (Event::SequenceStart(0, None), None),
+        (scalar("h"), None),
+        (scalar("i"), None),
+        (Event::SequenceEnd, None),
+        (scalar("j"), Some(6)),
+        (Event::Alias(1), Some(6)),
+        (Event::MappingEnd, None),
+        (Event::SequenceEnd, None),
+        (Event::SequenceEnd, None),
+        (Event::MappingEnd, None),
+        (scalar("k"), Some(0)),
+    ];
+    let tagged = indents.remove(expected.len());
+    assert!(matches!(tagged, (Event::Scalar(ref v, ..), Some(0)) if v == "l"));
+    expected.extend([(Event::MappingEnd, None), (scalar("m"), None)]);
+    assert_eq!(indents, expected);
+
+    // Peeking does not change the indentation of the last event returned.
+    let mut parser = Parser::new_from_str("- a\n- b\n");
+    parser.set_record_indentation(true);
+    for _ in 0..2 {
+        parser.next_event().unwrap().unwrap();
+    }
+    parser.peek().unwrap().unwrap();
+    assert_eq!(parser.indentation(), None);
+    parser.next_event().unwrap().unwrap();
+    assert_eq!(parser.indentation(), Some(0));
+}
+
+#[test]
+fn test_alias_chain_not_expanded() {
+    // Each anchored node refers twice to the previous one: expanding aliases would take
+    // 2^20000 nodes. Parsing on a small stack shows that aliases are not expanded recursively.
+    let mut yaml = "- &a0 [x]\n".to_owned();
+    for i in 1..20_000 {
+        yaml.push_str(&format!("- &a{i} [*a{}, *a{}]\n", i - 1, i - 1));
+    }
+    let aliases = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || {
+            Parser::new_from_str(&yaml)
+                .map(Result::unwrap)
+                .filter(|(ev, _)| matches!(ev, Event::Alias(_)))
+                .count()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(aliases, 2 * 19_999);
+}
+
+#[test]
+fn test_mapping_keys() {
+    let yaml = "\
+name: app
+server:
+  host: localhost
+  ports: [80, 443]
+  tls: {cert: a.pem, key: b.pem}
+routes:
+  - path: /
+    handler: index
+  - - nested: true
+? [complex, key]
+: value
+? {inner: key}
+: &x value
+alias: *x
+---
+- not a key
+";
+    let keys: Vec<_> = Parser::new_from_str(yaml)
+        .into_mapping_keys()
+        .map(|key| {
+            let key = key.unwrap();
+            assert_eq!(
+                &yaml[key.span.start.index()..key.span.end.index()],
+                key.value
+            );
+            (key.value, key.depth)
+        })
+        .collect();
+    let expected = [
+        ("name", 0),
+        ("server", 0),
+        ("host", 1),
+        ("ports", 1),
+        ("tls", 1),
+        ("cert", 2),
+        ("key", 2),
+        ("routes", 0),
+        ("path", 1),
+        ("handler", 1),
+        ("nested", 1),
+        ("inner", 1),
+        ("alias", 0),
+    ];
+    assert_eq!(keys, expected.map(|(key, depth)| (key.to_owned(), depth)));
+
+    let mut keys = Parser::new_from_str("a: 1\nb: [c, d").into_mapping_keys();
+    assert_eq!(keys.next().unwrap().unwrap().value, "a");
+    assert_eq!(keys.next().unwrap().unwrap().value, "b");
+    assert!(keys.next().unwrap().is_err());
+    assert!(keys.next().is_none());
+}
+
+#[test]
+fn test_on_unsupported() {
+    let yaml = "\
+%YAML 1.2
+%YAML 1.2
+%TAG !e! tag:e,2000:
+%TAG !e! tag:other:
+---
+- !e!a x
+- !u!b y
+- *nope
+- z
+";
+    let skipped = Arc::new(Mutex::new(vec![]));
+    let mut parser = Parser::new_from_str(yaml);
+    let recorded = Arc::clone(&skipped);
+    parser.set_on_unsupported(move |err| {
+        recorded.lock().unwrap().push(err.marker().line());
+        UnsupportedAction::Skip
+    });
+    let scalars: Vec<_> = parser
+        .filter_map(|ev| match ev.unwrap().0 {
+            Event::Scalar(value, _, _, tag) => Some((
+                value,
+                tag.map(|tag| format!("{}{}", tag.handle, tag.suffix)),
+            )),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        scalars,
+        [
+            ("x".to_owned(), Some("tag:e,2000:a".to_owned())),
+            ("y".to_owned(), Some("!u!b".to_owned())),
+            ("~".to_owned(), None),
+            ("z".to_owned(), None),
+        ]
+    );
+    assert_eq!(*skipped.lock().unwrap(), [2, 4, 7, 8]);
+
+    // The callback may choose which errors to skip.
+    let mut parser = Parser::new_from_str("[*a, !u!b c]");
+    parser.set_on_unsupported(|err| {
+        if err.info().contains("anchor") {
+            UnsupportedAction::Skip
+        } else {
+            UnsupportedAction::Fail
+        }
+    });
+    let err = parser.find_map(Result::err).unwrap();
+    assert_eq!(err.info(), "the handle wasn't declared");
+
+    // Other errors are not handed to the callback.
+    let mut parser = Parser::new_from_str("[a");
+    parser.set_on_unsupported(|_| panic!("the callback must not be called"));
+    assert!(parser.find_map(Result::err).is_some());
+}
+
+#[test]
+fn test_parser_is_send_and_sync() {
+    // Callbacks set on the parser must not keep it from being sent to or shared with another
+    // thread.
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Parser<StrInput>>();
+    assert_send_sync::<Parser<BufferedInput<std::str::Chars>>>();
+}
+
+#[test]
+fn test_plain_scalar_fold() {
+    /// Return the scalars of `input` parsed with `fold`.
+    fn scalars(input: &str, fold: FoldMode) -> Vec<String> {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_plain_scalar_fold(fold);
+        parser
+            .filter_map(|ev| match ev.unwrap().0 {
+                Event::Scalar(value, ..) => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    let yaml = "text: first line\n  second line\n  third line\nnext: value\n";
+    assert_eq!(
+        scalars(yaml, FoldMode::SpecFold),
+        ["text", "first line second line third line", "next", "value"]
+    );
+    assert_eq!(
+        scalars(yaml, FoldMode::PreserveNewlines),
+        [
+            "text",
+            "first line\nsecond line\nthird line",
+            "next",
+            "value"
+        ]
+    );
+    assert_eq!(
+        scalars(yaml, FoldMode::default()),
+        scalars(yaml, FoldMode::SpecFold)
+    );
+
+    // Empty lines are kept in both modes. Plain scalars in flow contexts are affected too.
+    let yaml = "[a\n b\n\n c, d]";
+    assert_eq!(scalars(yaml, FoldMode::SpecFold), ["a b\nc", "d"]);
+    assert_eq!(
+        scalars(yaml, FoldMode::PreserveNewlines),
+        ["a\nb\n\nc", "d"]
+    );
+
+    // Other styles are not affected.
+    let yaml = "- 'a\n  b'\n- >\n  c\n  d\n";
+    assert_eq!(
+        scalars(yaml, FoldMode::PreserveNewlines),
+        scalars(yaml, FoldMode::SpecFold)
+    );
+}
+
+#[test]
+fn test_line_start() {
+    let yaml = "a: 1\r\nb:\r  - 2\n\n  - 3\r\n\r\nc: |\r\n  4\n";
+    let starts: Vec<_> = (0..=10).map(|line| line_start(yaml, line)).collect();
+    assert_eq!(
+        starts,
+        [
+            None,
+            Some(0),
+            Some(6),
+            Some(9),
+            Some(15),
+            Some(16),
+            Some(23),
+            Some(25),
+            Some(31),
+            Some(35),
+            None
+        ]
+    );
+    assert_eq!(line_start("", 1), Some(0));
+    assert_eq!(line_start("", 2), None);
+
+    // Lines match those of the markers the parser reports.
+    for (event, span) in Parser::new_from_str(yaml).map(Result::unwrap) {
+        if event.is_scalar() {
+            let start = line_start(yaml, span.start.line()).unwrap();
+            assert_eq!(start + span.start.col(), span.start.index(), "{event:?}");
+        }
+    }
+}
+
+#[test]
+fn test_event_accessors() {
+    let events = run_parser("&s !!seq [&a !t x, *a, {k: v}]").unwrap();
+
+    let starts: Vec<_> = events
+        .iter()
+        .filter(|ev| ev.is_collection_start())
+        .collect();
+    assert_eq!(starts.len(), 2);
+    let ends: Vec<_> = events.iter().filter(|ev| ev.is_collection_end()).collect();
+    assert_eq!(ends, [&Event::MappingEnd, &Event::SequenceEnd]);
+
+    // Anchor IDs are reported for anchored nodes and aliases only.
+    let ids: Vec<_> = events.iter().map(Event::anchor_id).collect();
+    assert_eq!(
+        ids,
+        [
+            None,
+            None,
+            Some(1),
+            Some(2),
+            Some(2),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        ]
+    );
+
+    // Tags are reported for tagged nodes only.
+    let suffixes: Vec<_> = events
+        .iter()
+        .map(|ev| ev.tag().map(|tag| tag.suffix.as_str()))
+        .collect();
+    assert_eq!(
+        suffixes,
+        [
+            None,
+            None,
+            Some("seq"),
+            Some("t"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        ]
+    );
+
+    assert_eq!(
+        events[3].as_scalar(),
+        Some(("x", TScalarStyle::Plain, 2, events[3].tag()))
+    );
+    for ev in &events {
+        assert!(!(ev.is_collection_start() && ev.is_collection_end()));
+        assert_eq!(ev.as_scalar().is_some(), ev.is_scalar());
+    }
+}
+
+#[test]
+fn test_iterator_is_fused_after_error() {
+    for input in ["a: b: c", "[a, b", "- a\nb: c", "key: \"unterminated"] {
+        let mut parser = Parser::new_from_str(input);
+        let error = parser.by_ref().find_map(Result::err);
+        assert!(error.is_some(), "{input:?}");
+        for _ in 0..3 {
+            assert!(parser.next().is_none(), "{input:?}");
+        }
+        assert!(parser.is_finished());
+    }
+
+    // Errors returned by `peek` fuse the iterator too.
+    let mut parser = Parser::new_from_str("[a, b");
+    while !matches!(parser.peek(), Some(Err(_))) {
+        parser.next().unwrap().unwrap();
+    }
+    assert!(parser.next().is_none());
+
+    // The iterator also stops after the end of the stream.
+    let mut parser = Parser::new_from_str("a");
+    assert_eq!(parser.by_ref().count(), 5);
+    assert!(parser.next().is_none());
+}
+
+#[test]
+fn test_anchors_across_fragments() {
+    let mut first = Parser::new_from_str("a: &x 1\n---\nb: &y [2]\n").with_anchors(HashMap::new());
+    assert!(first.by_ref().all(|ev| ev.is_ok()));
+    let anchors = first.take_anchors();
+    assert_eq!(anchors.len(), 2);
+    assert_eq!(anchors["x"], 1);
+    assert_eq!(anchors["y"], 2);
+
+    // Aliases of the second fragment resolve to anchors of the first, and new anchors do not
+    // reuse their IDs.
+    let events: Vec<_> = Parser::new_from_str("c: *x\nd: *y\ne: &x 3\nf: *x\n")
+        .with_anchors(anchors)
+        .map(|ev| ev.unwrap().0)
+        .filter_map(|ev| ev.anchor_id())
+        .collect();
+    assert_eq!(events, [1, 2, 3, 3]);
+
+    // Without a table, anchors are scoped to their document.
+    let mut parser = Parser::new_from_str("a: &x 1\n");
+    assert!(parser.by_ref().all(|ev| ev.is_ok()));
+    assert!(parser.take_anchors().is_empty());
+    assert!(run_parser("b: *x\n").is_err());
+}
+
+#[test]
+fn test_unclosed_flow_before_block_content() {
+    for (input, found, other, other_mark) in [
+        ("- [1, 2\n- 3", '-', '[', Marker::new(2, 1, 2)),
+        ("- [1,\n- 2", '-', '[', Marker::new(2, 1, 2)),
+        ("k: [a\n- b", '-', '[', Marker::new(3, 1, 3)),
+        ("k: {a: b\n? c", '?', '{', Marker::new(3, 1, 3)),
+        ("k: [a\n: b", ':', '[', Marker::new(3, 1, 3)),
+        ("a:\n  b: [1,\n  - 2\n", '-', '[', Marker::new(8, 2, 5)),
+        // Closed flow collections of previous documents are not reported.
+        ("[{a}]\n---\n- [1,\n- 2", '-', '[', Marker::new(12, 3, 2)),
+    ] {
+        let error = run_parser(input).unwrap_err();
+        let kind = if other == '[' { "sequence" } else { "mapping" };
+        assert_eq!(
+            error.info(),
+            format!(
+                "flow {kind} opened at line {} is not closed before block content",
+                other_mark.line()
+            ),
+            "{input:?}"
+        );
+        // The error points at the block indicator, at the start of its line.
+        assert_eq!(
+            error.marker().col(),
+            input.lines().last().unwrap().find(found).unwrap()
+        );
+        assert_eq!(error.marker().line(), input.lines().count());
+        assert_eq!(
+            error.conflict(),
+            Some(&Conflict {
+                found,
+                other,
+                other_mark,
+            }),
+            "{input:?}"
+        );
+    }
+
+    // Flow content indented more than the enclosing block, or at the root, is not block content.
+    assert!(run_parser("k: [a\n  - b]").is_ok());
+    assert!(run_parser("[1, 2\n- 3]").is_ok());
+    assert_eq!(
+        run_parser("[1,\n- 2]").unwrap_err().info(),
+        r#""-" is only valid inside a block"#
+    );
+}
+
+#[test]
+fn test_count_documents() {
+    for (input, count) in [
+        ("", 0),
+        ("# comment\n", 0),
+        ("\n\n", 0),
+        ("---", 1),
+        ("a: 1", 1),
+        ("--- a\n...\n", 1),
+        ("%YAML 1.2\n---\n", 1),
+        ("a\n---\nb\n---\nc\n", 3),
+        ("---\n---\n---\n", 3),
+        ("--- [a,\n b]\n...\n--- &x b\n--- !t\n", 3),
+    ] {
+        assert_eq!(count_documents(input).unwrap(), count, "{input:?}");
+    }
+
+    // Errors are reported even after complete documents.
+    let error = count_documents("a\n---\nb: c: d\n").unwrap_err();
+    assert_eq!(error.marker().line(), 3);
+    assert!(count_documents("a\n--- [b\n").is_err());
+}
+
+#[test]
+fn test_trailing_document_separators() {
+    use DocumentEndCause::{Eof, ExplicitDots, NextDocumentMarker};
+
+    /// Parse `input`, returning the value of the root scalar of each document and the cause of
+    /// the end of each document.
+    fn documents(input: &str) -> (Vec<String>, Vec<DocumentEndCause>) {
+        let mut parser = Parser::new_from_str(input);
+        let (mut roots, mut causes) = (vec![], vec![]);
+        let mut at_root = false;
+        while let Some(event) = parser.next_event() {
+            match event.unwrap().0 {
+                Event::DocumentStart(_) => at_root = true,
+                Event::Scalar(value, ..) if at_root => {
+                    roots.push(value);
+                    at_root = false;
+                }
+                Event::DocumentEnd => causes.push(parser.document_end_cause().unwrap()),
+                _ => {}
+            }
+        }
+        assert_eq!(count_documents(input).unwrap(), roots.len(), "{input:?}");
+        (roots, causes)
+    }
+
+    // A trailing `---` starts a final, empty document, whose root is null.
+    for input in ["a\n---", "a\n---\n", "a\n--- \n", "a\n---\n# c\n"] {
+        assert_eq!(
+            documents(input),
+            (vec!["a".into(), "~".into()], vec![NextDocumentMarker, Eof]),
+            "{input:?}"
+        );
+    }
+    assert_eq!(
+        documents("a\n---\n..."),
+        (
+            vec!["a".into(), "~".into()],
+            vec![NextDocumentMarker, ExplicitDots]
+        )
+    );
+
+    // A trailing `...` ends the last document explicitly and does not start another one, however
+    // many there are.
+    for input in ["a\n...", "a\n...\n", "a\n...\n...\n", "a\n...\n# c\n"] {
+        assert_eq!(
+            documents(input),
+            (vec!["a".into()], vec![ExplicitDots]),
+            "{input:?}"
+        );
+    }
+    assert_eq!(
+        documents("a\n...\n---"),
+        (vec!["a".into(), "~".into()], vec![ExplicitDots, Eof])
+    );
+
+    // Separators alone.
+    assert_eq!(documents("---"), (vec!["~".into()], vec![Eof]));
+    assert_eq!(
+        documents("---\n..."),
+        (vec!["~".into()], vec![ExplicitDots])
+    );
+    assert_eq!(documents("..."), (vec![], vec![]));
+    assert_eq!(documents("...\n..."), (vec![], vec![]));
+}
+
+#[test]
+fn test_intern_scalars() {
+    let input = "- {name: a, type: t}\n- {name: b, type: t}\n- name: 'a'\n  type: !x t\n";
+    let mut parser = Parser::new_from_str(input);
+    parser.set_intern_scalars(true);
+    let mut buf = String::new();
+    let mut values: Vec<Arc<str>> = vec![];
+    while let Some(ev) = parser.next_event_into(&mut buf) {
+        let is_scalar = ev.unwrap().0.is_scalar();
+        // Only scalars have an interned value, equal to the value of the scalar.
+        assert_eq!(parser.interned_scalar().is_some(), is_scalar);
+        if let Some(value) = parser.interned_scalar() {
+            assert_eq!(*value, *buf);
+            values.push(value);
+        }
+    }
+    let names: Vec<_> = values.iter().map(|v| &**v).collect();
+    assert_eq!(
+        names,
+        ["name", "a", "type", "t", "name", "b", "type", "t", "name", "a", "type", "t"]
+    );
+    // Equal values share their storage, whatever their style and tag.
+    for (i, j) in [(0, 4), (0, 8), (1, 9), (3, 7), (3, 11)] {
+        assert!(Arc::ptr_eq(&values[i], &values[j]), "{i} {j}");
+    }
+    assert!(!Arc::ptr_eq(&values[1], &values[5]));
+
+    // Peeking does not change the interned value of the last event returned.
+    let mut parser = Parser::new_from_str("[a, b]");
+    parser.set_intern_scalars(true);
+    for _ in 0..4 {
+        parser.next_event().unwrap().unwrap();
+    }
+    parser.peek().unwrap().unwrap();
+    assert_eq!(parser.interned_scalar().as_deref(), Some("a"));
+    parser.next_event().unwrap().unwrap();
+    assert_eq!(parser.interned_scalar().as_deref(), Some("b"));
+
+    // Scalars are not interned by default, or once interning is disabled.
+    let mut parser = Parser::new_from_str("a");
+    while let Some(ev) = parser.next_event() {
+        ev.unwrap();
+        assert!(parser.interned_scalar().is_none());
+    }
+    let mut parser = Parser::new_from_str("[a, b]");
+    parser.set_intern_scalars(true);
+    for _ in 0..4 {
+        parser.next_event().unwrap().unwrap();
+    }
+    parser.set_intern_scalars(false);
+    assert!(parser.interned_scalar().is_none());
+
+    // Past the limit, new values are no longer interned while earlier ones still are.
+    let mut parser = Parser::new_from_str("[a, bc, a, bc, d, a]");
+    parser.set_intern_scalars(true);
+    parser.set_max_interned_bytes(2);
+    let mut values: Vec<Arc<str>> = vec![];
+    while let Some(ev) = parser.next_event() {
+        ev.unwrap();
+        values.extend(parser.interned_scalar());
+    }
+    assert!(Arc::ptr_eq(&values[0], &values[2]));
+    assert!(Arc::ptr_eq(&values[0], &values[5]));
+    assert!(!Arc::ptr_eq(&values[1], &values[3]));
+    assert_eq!(*values[4], *"d");
+}
+
+#[test]
+fn test_document_version() {
+    /// Parse `input`, returning the version of each document when it starts and ends.
+    fn versions(input: &str) -> Vec<Option<(u32, u32)>> {
+        let mut parser = Parser::new_from_str(input);
+        let mut versions = vec![];
+        while let Some(event) = parser.next_event() {
+            if let Event::DocumentStart(_) | Event::DocumentEnd = event.unwrap().0 {
+                versions.push(parser.document_version());
+            }
+        }
+        versions
+    }
+
+    assert_eq!(versions("a: b\n"), [None, None]);
+    assert_eq!(versions("--- a\n"), [None, None]);
+    assert_eq!(versions("%YAML 1.2\n--- a\n"), [Some((1, 2)); 2]);
+    assert_eq!(
+        versions("%YAML 1.1\n%TAG !e! tag:e,2000:\n--- !e!a b\n"),
+        [Some((1, 1)); 2]
+    );
+    // The version only applies to the document following the directive.
+    assert_eq!(
+        versions("%YAML 1.1\n--- a\n...\n--- b\n...\n%YAML 1.2\n--- c\n"),
+        [
+            Some((1, 1)),
+            Some((1, 1)),
+            None,
+            None,
+            Some((1, 2)),
+            Some((1, 2))
+        ]
+    );
+
+    // No version is known before the first document.
+    let mut parser = Parser::new_from_str("%YAML 1.1\n---\n");
+    assert_eq!(parser.next_event().unwrap().unwrap().0, Event::StreamStart);
+    assert_eq!(parser.document_version(), None);
+}
+
+#[test]
+fn test_empty_flow_entries() {
+    /// Parse `input`, accepting empty flow sequence entries.
+    fn run_lenient(input: &str) -> Result<Vec<Event>, ScanError> {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_allow_empty_flow_entries(true);
+        parser.map(|ev| ev.map(|(ev, _)| ev)).collect()
+    }
+
+    // A single trailing comma is valid YAML.
+    for (trailing, expected) in [
+        ("[1, 2,]", "[1, 2]"),
+        ("{a: 1,}", "{a: 1}"),
+        ("[1, 2 , ]", "[1, 2]"),
+        ("[a: 1,]", "[a: 1]"),
+        ("{a: [1,],}", "{a: [1]}"),
+    ] {
+        let expected = run_parser(expected).unwrap();
+        assert_eq!(run_parser(trailing).unwrap(), expected, "{trailing:?}");
+        assert_eq!(run_lenient(trailing).unwrap(), expected, "{trailing:?}");
+    }
+
+    // Empty entries are rejected by default, at the comma following them.
+    for (input, info, index) in [
+        (
+            "[1, , 3]",
+            "while parsing a flow sequence, found an empty entry",
+            4,
+        ),
+        (
+            "[,]",
+            "while parsing a flow sequence, found an empty entry",
+            1,
+        ),
+        (
+            "[1,,]",
+            "while parsing a flow sequence, found an empty entry",
+            3,
+        ),
+        (
+            "{a: 1, , b: 2}",
+            "while parsing a flow mapping, found an empty entry",
+            7,
+        ),
+        (
+            "{,}",
+            "while parsing a flow mapping, found an empty entry",
+            1,
+        ),
+    ] {
+        let error = run_parser(input).unwrap_err();
+        assert_eq!(error.info(), info, "{input:?}");
+        assert_eq!(error.marker().index(), index, "{input:?}");
+    }
+
+    // They are null scalars in flow sequences when allowed.
+    for (input, expected) in [
+        ("[1, , 3]", "[1, ~, 3]"),
+        ("[,]", "[~]"),
+        ("[1,,]", "[1, ~]"),
+        ("[, , a]", "[~, ~, a]"),
+        ("[{a: 1}, , [b]]", "[{a: 1}, ~, [b]]"),
+    ] {
+        assert_eq!(
+            run_lenient(input).unwrap(),
+            run_parser(expected).unwrap(),
+            "{input:?}"
+        );
+    }
+    let error = run_lenient("{a: 1, , b: 2}").unwrap_err();
+    assert_eq!(
+        error.info(),
+        "while parsing a flow mapping, found an empty entry"
+    );
+}
+
+#[test]
+fn test_max_total_bytes() {
+    let input: String = (0..100)
+        .map(|i| format!("key{i}: [{i}, '{i}']\n"))
+        .collect();
+
+    // The limit trips mid-stream, after the events of the content before it.
+    let mut parser = Parser::new(BufferedInput::new(input.chars()));
+    parser.set_max_total_bytes(1000);
+    let mut keys = 0;
+    let error = loop {
+        match parser.next_event().unwrap() {
+            Ok((Event::MappingEnd | Event::StreamEnd, _)) => panic!("the limit was not reached"),
+            Ok((ev, span)) => {
+                assert!(span.end.index() <= 1000);
+                if matches!(&ev, Event::Scalar(value, ..) if value.starts_with("key")) {
+                    keys += 1;
+                }
+            }
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(error.info(), "input exceeds the maximum total size");
+    assert!(error.marker().index() > 1000);
+    assert!(error.marker().index() < 1100);
+    assert!((40..=60).contains(&keys), "{keys}");
+
+    // Inputs within the limit are parsed in full.
+    let mut parser = Parser::new(BufferedInput::new(input.chars()));
+    parser.set_max_total_bytes(input.len());
+    assert!(parser.all(|ev| ev.is_ok()));
+
+    // Comments and quoted scalars count towards the limit.
+    for input in [
+        "# a long comment\na",
+        "'a long quoted scalar'",
+        "a: |\n  a long block scalar\n",
+    ] {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_max_total_bytes(8);
+        let error = parser.find_map(Result::err).unwrap();
+        assert_eq!(
+            error.info(),
+            "input exceeds the maximum total size",
+            "{input:?}"
+        );
+    }
+
+    // Scalars are reported where they cross the limit, not once they have been read whole.
+    let huge = "x".repeat(1_000_000);
+    let breaks = "\n".repeat(1_000_000);
+    for input in [
+        format!("{huge}\n"),
+        format!("'{huge}'\n"),
+        format!("\"{huge}\"\n"),
+        format!("|\n{huge}\n"),
+        format!("'a{breaks}b'\n"),
+        format!("a{breaks}b\n"),
+        format!("|\n a{breaks} b\n"),
+        format!("|{breaks} b\n"),
+    ] {
+        let run_str = || {
+            let mut parser = Parser::new_from_str(&input);
+            parser.set_max_total_bytes(100);
+            parser.find_map(Result::err).unwrap()
+        };
+        let run_buffered = || {
+            let mut parser = Parser::new(BufferedInput::new(input.chars()));
+            parser.set_max_total_bytes(100);
+            parser.find_map(Result::err).unwrap()
+        };
+        for error in [run_str(), run_buffered()] {
+            assert_eq!(error.info(), "input exceeds the maximum total size");
+            assert_eq!(error.marker().index(), 101);
+        }
+    }
+}
+
+#[test]
+fn test_error_on_tab_anywhere() {
+    // Tabs valid in YAML are accepted by default.
+    let inputs = [
+        "key:\tvalue",
+        "- a\t# comment",
+        "# a\tcomment\na",
+        "%YAML\t1.2\n---\na",
+        "'single\tquoted'",
+        "\"double\tquoted\"",
+        "\"escaped\\\ttab\"",
+        "plain\tscalar",
+        "[a,\tb]",
+        "|\n  literal\ttext\n",
+    ];
+    for input in inputs {
+        assert!(
+            Parser::new_from_str(input).all(|ev| ev.is_ok()),
+            "{input:?}"
+        );
+    }
+
+    // Each of them is reported when tabs are forbidden.
+    for input in inputs {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_error_on_tab_anywhere(true);
+        let error = parser.find_map(Result::err).unwrap();
+        assert_eq!(
+            error.info(),
+            "found a tab, but tabs are forbidden",
+            "{input:?}"
+        );
+        assert_eq!(
+            error.marker().index(),
+            input.find('\t').unwrap(),
+            "{input:?}"
+        );
+    }
+
+    // The `\t` escape sequence is not a tab in the input.
+    let mut parser = Parser::new_from_str("\"escaped\\ttab\"");
+    parser.set_error_on_tab_anywhere(true);
+    assert!(parser.all(|ev| ev.is_ok()));
+}
+
+#[test]
+fn test_tag_base() {
+    fn tags(input: &str, base: Option<&str>) -> Vec<(String, String)> {
+        let mut parser = Parser::new_from_str(input);
+        if let Some(base) = base {
+            parser.set_tag_base(base);
+        }
+        parser
+            .filter_map(|ev| ev.unwrap().0.tag().cloned())
+            .map(|tag| (tag.handle, tag.suffix))
+            .collect()
+    }
+    let pair = |handle: &str, suffix: &str| (handle.to_owned(), suffix.to_owned());
+    let base = Some("tag:example.com,2000:");
+
+    // Primary handle.
+    assert_eq!(tags("!foo a", None), [pair("!", "foo")]);
+    assert_eq!(tags("!foo a", base), [pair("tag:example.com,2000:", "foo")]);
+    assert_eq!(
+        tags("%TAG ! tag:other.org,2000:\n--- !foo a", base),
+        [pair("tag:other.org,2000:", "foo")]
+    );
+
+    // Secondary handle.
+    assert_eq!(tags("!!str a", base), [pair("tag:yaml.org,2002:", "str")]);
+    assert_eq!(
+        tags("%TAG !! tag:other.org,2000:\n--- !!str a", base),
+        [pair("tag:other.org,2000:", "str")]
+    );
+
+    // Named handle.
+    assert_eq!(
+        tags("%TAG !e! tag:other.org,2000:\n--- !e!foo a", None),
+        [pair("tag:other.org,2000:", "foo")]
+    );
+    assert_eq!(
+        tags("%TAG !e! tag:other.org,2000:\n--- !e!foo a", base),
+        [pair("tag:other.org,2000:", "foo")]
+    );
+    assert!(Parser::new_from_str("!e!foo a")
+        .any(|ev| ev.is_err_and(|err| err.info() == "the handle wasn't declared")));
+
+    // Verbatim and non-specific tags.
+    assert_eq!(tags("!<!foo> a", base), [pair("", "!foo")]);
+    assert_eq!(tags("! a", base), [pair("", "!")]);
+}
@@ -0,0 +1,169 @@
+#![cfg(feature = "emitter")]
+#![allow(dead_code)]
+#![allow(non_upper_case_globals)]
+
+use saphyr_parser::{events_to_yaml, Event, Parser, ScanError, TScalarStyle, Tag};
+
+include!("specexamples.rs.inc");
+
+/// Run the parser through the string, returning all the events.
+fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
+    Parser::new_from_str(input)
+        .map(|x| x.map(|(ev, _)| ev))
+        .collect()
+}
+
+/// Normalize the style of scalars which are not preserved by `events_to_yaml`.
+///
+/// These are non-plain scalars, and plain scalars which can only appear in block context.
+fn normalize_styles(events: Vec<Event>) -> Vec<Event> {
+    events
+        .into_iter()
+        .map(|ev| match ev {
+            Event::Scalar(v, style, anchor, tag)
+                if style != TScalarStyle::Plain || v.contains([',', '[', ']', '{', '}', '\n']) =>
+            {
+                Event::Scalar(v, TScalarStyle::DoubleQuoted, anchor, tag)
+            }
+            ev => ev,
+        })
+        .collect()
+}
+
+/// Check that the events of `input` survive a round-trip through `events_to_yaml`.
+fn assert_round_trips(input: &str) {
+    let events = run_parser(input).unwrap();
+    let yaml = events_to_yaml(&events);
+    let reparsed = run_parser(&yaml)
+        .unwrap_or_else(|e| panic!("failed to parse {yaml:?} (from {input:?}): {e}"));
+    assert_eq!(
+        normalize_styles(reparsed),
+        normalize_styles(events),
+        "{input:?} was emitted as {yaml:?}"
+    );
+}
+
+#[test]
+fn test_round_trip_spec_examples() {
+    let examples = [
+        EX2_1, EX2_2, EX2_3, EX2_4, EX2_5, EX2_6, EX2_7, EX2_8, EX2_9, EX2_10, EX2_11, EX2_12,
+        EX2_13, EX2_14, EX2_15, EX2_16, EX2_17, EX2_18, EX2_23, EX2_24, EX2_25, EX2_26, EX2_27,
+        EX2_28, EX5_3, EX5_4, EX5_5, EX5_6, EX5_7, EX5_8, EX5_11, EX5_12, EX5_13, EX5_14, EX6_1,
+        EX6_2, EX6_3, EX6_4, EX6_5, EX6_6, EX6_7, EX6_8, EX6_9, EX6_10, EX6_11, EX6_12, EX6_13,
+        EX6_14, EX6_15, EX6_16, EX6_17, EX6_18, EX6_19, EX6_20, EX6_21, EX6_22, EX6_23, EX6_24,
+        EX6_25, EX6_26, EX6_27a, EX6_27b, EX6_28, EX6_29, EX7_1, EX7_2, EX7_3, EX7_4, EX7_5, EX7_6,
+        EX7_7, EX7_8, EX7_9, EX7_10, EX7_11, EX7_12, EX7_13, EX7_14, EX7_15, EX7_16, EX7_17,
+        EX7_18, EX7_19, EX7_20, EX7_21, EX7_22, EX7_23, EX7_24, EX8_1, EX8_2, EX8_3a, EX8_3b,
+        EX8_3c, EX8_4, EX8_5, EX8_6, EX8_7, EX8_8, EX8_9, EX8_10, EX8_11, EX8_12, EX8_13, EX8_14,
+        EX8_15, EX8_16, EX8_17, EX8_18, EX8_19, EX8_20, EX8_21, EX8_22,
+    ];
+    let mut n_parsed = 0;
+    for example in examples {
+        // Some examples are invalid YAML.
+        if run_parser(example).is_ok() {
+            assert_round_trips(example);
+            n_parsed += 1;
+        }
+    }
+    assert!(n_parsed > 100);
+}
+
+#[test]
+fn test_emit_scalars() {
+    let scalar = |v: &str, style| Event::Scalar(v.to_string(), style, 0, None);
+    let emit = |ev: Event| events_to_yaml(&[Event::DocumentStart(false), ev, Event::DocumentEnd]);
+
+    assert_eq!(emit(scalar("foo bar", TScalarStyle::Plain)), "foo bar\n");
+    assert_eq!(emit(scalar("-1", TScalarStyle::Plain)), "-1\n");
+    assert_eq!(emit(scalar("a: b", TScalarStyle::Plain)), "\"a: b\"\n");
+    assert_eq!(emit(scalar("- a", TScalarStyle::Plain)), "\"- a\"\n");
+    assert_eq!(emit(scalar("--- a", TScalarStyle::Plain)), "\"--- a\"\n");
+    assert_eq!(emit(scalar("a #b", TScalarStyle::Plain)), "\"a #b\"\n");
+    assert_eq!(emit(scalar("[a]", TScalarStyle::Plain)), "\"[a]\"\n");
+    assert_eq!(emit(scalar("", TScalarStyle::Plain)), "\"\"\n");
+    assert_eq!(
+        emit(scalar("it's", TScalarStyle::SingleQuoted)),
+        "'it''s'\n"
+    );
+    assert_eq!(
+        emit(scalar("a\n\"b\"\t\\\u{7}", TScalarStyle::Literal)),
+        "\"a\\n\\\"b\\\"\\t\\\\\\x07\"\n"
+    );
+
+    for s in [
+        "a\n",
+        "'multi\n\n  line'",
+        r#""\0\a\b\t\n\v\f\r\e\N\L\P\x85\uFEFF ""#,
+        "|\n  literal\n   text\n\n",
+        ">-\n  folded\n  text\n",
+        "- \"a: b\"\n- 'c, d'\n- e#f\n- :g\n- http://h",
+    ] {
+        assert_round_trips(s);
+    }
+}
+
+#[test]
+fn test_emit_collections() {
+    assert_eq!(
+        events_to_yaml(&run_parser("a:\n  - b\n  - {c: d}\n[e]: f\n").unwrap()),
+        "{a: [b, {c: d}], ? [e] : f}\n"
+    );
+    assert_eq!(events_to_yaml(&run_parser("[]").unwrap()), "[]\n");
+    assert_eq!(events_to_yaml(&run_parser("{}").unwrap()), "{}\n");
+    for s in [
+        "? a\n? b\n: c",
+        "? - a\n  - b\n: - c\n",
+        "&a a: &b b\n*a : *b\n*b : [*a]",
+        "- &a\n- !!str\n- &b !!str\n- a: &c\n  &d : e",
+        "[a: b, c: d]",
+        "{a: [b, c], [d]: {e: f}}",
+    ] {
+        assert_round_trips(s);
+    }
+}
+
+#[test]
+fn test_emit_tags_and_documents() {
+    let events = run_parser("%TAG !e! tag:example.com,2000:app/\n--- !e!foo%21 a").unwrap();
+    let yaml = events_to_yaml(&events);
+    assert_eq!(
+        yaml,
+        "%TAG !t0! tag:example.com,2000:app/\n--- !t0!foo%21 a\n"
+    );
+    assert_eq!(
+        run_parser(&yaml).unwrap()[2],
+        Event::Scalar(
+            "a".to_string(),
+            TScalarStyle::Plain,
+            0,
+            Some(Tag {
+                handle: "tag:example.com,2000:app/".to_string(),
+                suffix: "foo!".to_string()
+            })
+        )
+    );
+
+    assert_eq!(
+        events_to_yaml(&run_parser("a\n...\nb\n---\nc\n--- d\n").unwrap()),
+        "a\n...\nb\n--- c\n--- d\n"
+    );
+    for s in [
+        "",
+        "---",
+        "--- ! a\n--- !local b\n--- !<tag:yaml.org,2002:str> c\n--- !!int 1",
+        "%TAG ! tag:example.com,2000:\n--- !a b\n...\n%TAG !e! tag:example.com,2000:\n--- !e!c d",
+        "a\n...\n%TAG !e! tag:example.com,2000:\n--- !e!b c",
+    ] {
+        assert_round_trips(s);
+    }
+}
+
+#[test]
+fn test_emit_ignores_indicators() {
+    let input = "a:\n- b\n? c\n: {d: e}\n";
+    let mut parser = Parser::new_from_str(input);
+    parser.set_indicator_events(true);
+    let events: Vec<_> = parser.map(|x| x.unwrap().0).collect();
+    assert!(events.iter().any(|ev| matches!(ev, Event::Indicator(_))));
+    assert_eq!(events_to_yaml(&events), "{a: [b], c: {d: e}}\n");
+}
@@ -194,3 +194,200 @@ fn test_pr12() {
         ]
     );
 }
+
+#[test]
+fn test_quoted_keys_adjacent_to_flow_indicators() {
+    // JSON-like (quoted) keys may be followed by their value without a space. Each input must
+    // parse as if a space followed the `:`.
+    for (compact, spaced) in [
+        (r#"{"a":[1],"b":{}}"#, r#"{"a": [1], "b": {}}"#),
+        (r#"{"a":{"b":1}}"#, r#"{"a": {"b": 1}}"#),
+        (r#"{"a":,"b":1}"#, r#"{"a": , "b": 1}"#),
+        (r#"{"a":}"#, r#"{"a": }"#),
+        (r#"{"a":"b","c":[]}"#, r#"{"a": "b", "c": []}"#),
+        (r#"["k":v]"#, r#"["k": v]"#),
+        (r#"["k":[v]]"#, r#"["k": [v]]"#),
+        (r#"["k":{a: b}]"#, r#"["k": {a: b}]"#),
+        (r#"["k":,v]"#, r#"["k": , v]"#),
+        (r#"["k":]"#, r#"["k": ]"#),
+        ("{'a':[1],'b':{}}", "{'a': [1], 'b': {}}"),
+        ("['k':[v],'l':]", "['k': [v], 'l': ]"),
+        (r#"key: {"a":[1]}"#, r#"key: {"a": [1]}"#),
+    ] {
+        let expected = run_parser(spaced).unwrap();
+        assert_eq!(run_parser(compact).unwrap(), expected, "{compact}");
+        assert_eq!(run_parser_buffered(compact).unwrap(), expected, "{compact}");
+    }
+}
+
+#[test]
+fn test_multiline_flow_collections() {
+    // Flow collections may span several lines, with comments and line breaks between any of
+    // their tokens, at the root of a document or nested in a block collection. Each input must
+    // parse as its single-line equivalent.
+    for (multiline, single_line) in [
+        ("[\n 1,\n 2,\n]", "[1, 2]"),
+        ("[1, # c\n 2]", "[1, 2]"),
+        ("[1, 2,]", "[1, 2]"),
+        ("{a: 1, b: 2,}", "{a: 1, b: 2}"),
+        ("[a\n,\nb]", "[a, b]"),
+        ("[\n[\n1\n]\n,\n{\na\n:\nb\n}\n,\n]", "[[1], {a: b}]"),
+        ("[ # c\n1 # d\n, # e\n2 # f\n] # g\n", "[1, 2]"),
+        ("{ # c\n a: 1, # d\n b: 2 # e\n}\n", "{a: 1, b: 2}"),
+        (
+            "{\n a: [\n  1, # c\n  2,\n ], # d\n b: {\n  c: 3,\n },\n}",
+            "{a: [1, 2], b: {c: 3}}",
+        ),
+        ("[\n? a\n: b\n]", "[a: b]"),
+        ("- [\n  1, # c\n  2,\n  ]\n", "- [1, 2]"),
+        ("a: {\n  b: 1, # c\n  c: 2,\n  }\n", "a: {b: 1, c: 2}"),
+        ("--- [\n1,\n2\n]\n...\n", "--- [1, 2]\n...\n"),
+    ] {
+        let expected = run_parser(single_line).unwrap();
+        assert_eq!(run_parser(multiline).unwrap(), expected, "{multiline:?}");
+        assert_eq!(
+            run_parser_buffered(multiline).unwrap(),
+            expected,
+            "{multiline:?}"
+        );
+    }
+}
+
+#[test]
+fn test_block_sequence_at_mapping_indentation() {
+    // The entries of a block sequence that is the value of a mapping may be at the indentation of
+    // its key. Each input must parse as its indented and flow equivalents.
+    for (same_indent, indented, flow) in [
+        ("key:\n- a\n", "key:\n  - a\n", "{key: [a]}"),
+        ("key:\n- a\n- b\n", "key:\n  - a\n  - b\n", "{key: [a, b]}"),
+        ("k:\n- a\nl: b\n", "k:\n  - a\nl: b\n", "{k: [a], l: b}"),
+        (
+            "a:\n  b:\n  - c\n  d: e\n",
+            "a:\n  b:\n    - c\n  d: e\n",
+            "{a: {b: [c], d: e}}",
+        ),
+        (
+            "- k:\n  - a\n  l: m\n",
+            "- k:\n    - a\n  l: m\n",
+            "[{k: [a], l: m}]",
+        ),
+        (
+            "k:\n- - a\n  - b\n- c: d\n",
+            "k:\n  - - a\n    - b\n  - c: d\n",
+            "{k: [[a, b], {c: d}]}",
+        ),
+        (
+            "k: &x !!seq\n- a\n",
+            "k: &x !!seq\n  - a\n",
+            "{k: &x !!seq [a]}",
+        ),
+        ("? k\n:\n- a\n", "? k\n:\n  - a\n", "{k: [a]}"),
+    ] {
+        let expected = run_parser(flow).unwrap();
+        // Only the style of the collections differs, which events do not record.
+        assert_eq!(
+            run_parser(same_indent).unwrap(),
+            expected,
+            "{same_indent:?}"
+        );
+        assert_eq!(run_parser(indented).unwrap(), expected, "{indented:?}");
+        assert_eq!(
+            run_parser_buffered(same_indent).unwrap(),
+            expected,
+            "{same_indent:?}"
+        );
+    }
+
+    // Entries less indented than their key are not part of the mapping.
+    assert!(run_parser("a:\n  b:\n- c\n").is_err());
+}
+
+#[test]
+fn test_mapping_value_after_blank_lines_and_comments() {
+    // Blank lines and comments, at any indentation, may separate a key from its value on a
+    // following line. Each input must parse as its flow equivalent.
+    for (input, flow) in [
+        ("key:\n\n  nested: v\n", "{key: {nested: v}}"),
+        ("key:\n  # c\n  nested: v\n", "{key: {nested: v}}"),
+        ("key:\n\n  # c\n  nested: v\n", "{key: {nested: v}}"),
+        (
+            "key:\n  # c\n\n  nested: v\nother: x\n",
+            "{key: {nested: v}, other: x}",
+        ),
+        ("key:\n# c\n  nested: v\n", "{key: {nested: v}}"),
+        ("key:\n    # c\n  nested: v\n", "{key: {nested: v}}"),
+        ("key:   # c\n\n\n  nested: v\n", "{key: {nested: v}}"),
+        ("key:\n  \n\t\n  # c\n  nested: v\n", "{key: {nested: v}}"),
+        ("key:\r\n\r\n  # c\r\n  nested: v\r\n", "{key: {nested: v}}"),
+        (
+            "a:\n  key:\n\n    # c\n    n: v\n  o: p\n",
+            "{a: {key: {n: v}, o: p}}",
+        ),
+        ("key:\n\n  # c\n  - a\n  - b\n", "{key: [a, b]}"),
+        ("key:\n\n# c\n  - a\n\n  # d\n  - b\n", "{key: [a, b]}"),
+        ("key:\n  \n  # c\n\n- a\n", "{key: [a]}"),
+        ("key: # c\n  # d\n  - a\nother: x\n", "{key: [a], other: x}"),
+    ] {
+        let expected = run_parser(flow).unwrap();
+        assert_eq!(run_parser(input).unwrap(), expected, "{input:?}");
+        assert_eq!(run_parser_buffered(input).unwrap(), expected, "{input:?}");
+    }
+
+    // A key followed only by blank lines and comments has a null value.
+    assert_eq!(
+        run_parser("key:\n\n  # c\nother: x\n").unwrap(),
+        run_parser("{key: ~, other: x}").unwrap()
+    );
+}
+
+#[test]
+fn test_single_quoted_doubled_quotes_at_buffer_edges() {
+    // `BufferedInput` reads 16 characters at a time. A doubled quote must be unescaped wherever
+    // it falls relative to the buffer, including straddling two reads.
+    for before in 0..40 {
+        for (after, quotes) in [(0, 1), (1, 1), (17, 1), (0, 2), (5, 3)] {
+            let value = format!(
+                "{}{}{}",
+                "a".repeat(before),
+                "'".repeat(quotes),
+                "b".repeat(after)
+            );
+            let quoted = format!("'{}'", value.replace('\'', "''"));
+            for input in [
+                quoted.clone(),
+                format!("k: {quoted}\n"),
+                format!("[{quoted}, c]"),
+            ] {
+                let expected = Event::Scalar(value.clone(), TScalarStyle::SingleQuoted, 0, None);
+                let events = run_parser(&input).unwrap();
+                assert!(events.contains(&expected), "{input:?}");
+                assert_eq!(run_parser_buffered(&input).unwrap(), events, "{input:?}");
+            }
+        }
+    }
+
+    // Doubled quotes next to a line fold.
+    let input = format!("'{}''\n  ''{}'", "a".repeat(15), "b".repeat(15));
+    let expected = format!("{}' '{}", "a".repeat(15), "b".repeat(15));
+    for events in [run_parser(&input), run_parser_buffered(&input)] {
+        assert_eq!(
+            events.unwrap()[2],
+            Event::Scalar(expected.clone(), TScalarStyle::SingleQuoted, 0, None)
+        );
+    }
+
+    // A doubled quote at the end of the input does not close the scalar.
+    for input in ["'abc''", "'''", "'a''\n", "k: 'abc''"] {
+        let error = run_parser(input).unwrap_err();
+        assert_eq!(
+            error.info(),
+            "while scanning a quoted scalar, found unexpected end of stream",
+            "{input:?}"
+        );
+        assert_eq!(run_parser_buffered(input).unwrap_err(), error, "{input:?}");
+    }
+    assert_eq!(
+        run_parser("''''").unwrap()[2],
+        Event::Scalar("'".to_owned(), TScalarStyle::SingleQuoted, 0, None)
+    );
+}
@@ -0,0 +1,60 @@
+use std::{fs::File, io::Write, path::PathBuf};
+
+use memmap2::Mmap;
+use saphyr_parser::{Event, Parser, StrInput};
+
+/// A file in the temporary directory, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: &[u8]) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("saphyr-parser-{}-{name}", std::process::id()));
+        File::create(&path).unwrap().write_all(contents).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn test_parse_mmap() {
+    let yaml = "a: [b, \"é\"]\nc: |\n  multi\n  line\n";
+    let file = TempFile::new("valid.yaml", yaml.as_bytes());
+    let mmap = unsafe { Mmap::map(&File::open(&file.0).unwrap()).unwrap() };
+
+    let expected: Vec<_> = Parser::new_from_str(yaml).map(Result::unwrap).collect();
+    let actual: Vec<_> = Parser::new(StrInput::from_utf8(&mmap).unwrap())
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(actual, expected);
+
+    // Spans can be used to slice the mapped bytes.
+    let scalars: Vec<_> = actual
+        .iter()
+        .filter(|(ev, _)| matches!(ev, Event::Scalar(..)))
+        .map(|(_, span)| &mmap[span.start.index()..span.end.index()])
+        .collect();
+    assert_eq!(
+        scalars,
+        [
+            &b"a"[..],
+            b"b",
+            "\"é\"".as_bytes(),
+            b"c",
+            b"multi\n  line\n"
+        ]
+    );
+}
+
+#[test]
+fn test_parse_mmap_invalid_utf8() {
+    let file = TempFile::new("invalid.yaml", b"a: b\nc: \xff\n");
+    let mmap = unsafe { Mmap::map(&File::open(&file.0).unwrap()).unwrap() };
+    let error = StrInput::from_utf8(&mmap).err().unwrap();
+    assert_eq!(error.valid_up_to(), 8);
+}
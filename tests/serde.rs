@@ -0,0 +1,40 @@
+#![cfg(feature = "serde")]
+
+use saphyr_parser::{Marker, Parser, ScanError, Span};
+use serde_json::json;
+
+#[test]
+fn test_serialize_marker_and_span() {
+    let marker = Marker::new(12, 3, 4);
+    assert_eq!(
+        serde_json::to_value(marker).unwrap(),
+        json!({ "index": 12, "line": 3, "col": 4 })
+    );
+
+    let span = Span::new(Marker::new(0, 1, 0), Marker::new(5, 2, 1));
+    assert_eq!(
+        serde_json::to_value(span).unwrap(),
+        json!({
+            "start": { "index": 0, "line": 1, "col": 0 },
+            "end": { "index": 5, "line": 2, "col": 1 },
+        })
+    );
+}
+
+#[test]
+fn test_serialize_scan_error() {
+    let err = ScanError::new_str(Marker::new(7, 2, 3), "some error");
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({
+            "marker": { "index": 7, "line": 2, "col": 3 },
+            "info": "some error",
+        })
+    );
+
+    // Errors from the parser serialize the same way.
+    let err = Parser::new_from_str("a: [b").find_map(Result::err).unwrap();
+    let value = serde_json::to_value(&err).unwrap();
+    assert_eq!(value["info"], err.info());
+    assert_eq!(value["marker"]["index"], err.marker().index());
+}
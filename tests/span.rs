@@ -1,7 +1,9 @@
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::float_cmp)]
 
-use saphyr_parser::{Event, Parser, ScanError};
+use saphyr_parser::{
+    BufferedInput, Chomping, Event, MappingPairs, Parser, ScanError, Span, SpannedEventReceiver,
+};
 
 /// Run the parser through the string, returning all the scalars, and collecting their spans to strings.
 fn run_parser_and_deref_scalar_spans(input: &str) -> Result<Vec<(String, String)>, ScanError> {
@@ -11,8 +13,7 @@ fn run_parser_and_deref_scalar_spans(input: &str) -> Result<Vec<(String, String)
         if let Event::Scalar(s, ..) = x.0 {
             let start = x.1.start.index();
             let end = x.1.end.index();
-            let input_s = input.chars().skip(start).take(end - start).collect();
-            events.push((s, input_s));
+            events.push((s, input[start..end].to_string()));
         }
     }
     Ok(events)
@@ -29,8 +30,7 @@ fn run_parser_and_deref_seq_spans(input: &str) -> Result<Vec<String>, ScanError>
             Event::SequenceEnd => {
                 let start = start_stack.pop().unwrap();
                 let end = x.1.end.index();
-                let input_s = input.chars().skip(start).take(end - start).collect();
-                events.push(input_s);
+                events.push(input[start..end].to_string());
             }
             _ => {}
         }
@@ -134,3 +134,462 @@ fn test_seq() {
         ["b\n    - c", "- a\n  - bar:\n    - b\n    - c"]
     );
 }
+
+/// Run [`MappingPairs`] through the string, collecting the key and value spans to strings.
+fn run_parser_and_deref_pair_spans(input: &str) -> Result<Vec<(String, String)>, ScanError> {
+    let deref = |span: Span| input[span.start.index()..span.end.index()].to_owned();
+    let mut pairs = vec![];
+    for x in MappingPairs::new(Parser::new_from_str(input)) {
+        let (key, value) = x?;
+        pairs.push((deref(key), deref(value)));
+    }
+    Ok(pairs)
+}
+
+#[test]
+fn test_mapping_pairs() {
+    assert_eq!(
+        deref_pairs(&run_parser_and_deref_pair_spans("foo: bar\nbaz: 'qux'").unwrap()),
+        [("foo", "bar"), ("baz", "'qux'")]
+    );
+    // Nested values. Pairs are yielded once their value is complete.
+    assert_eq!(
+        deref_pairs(
+            &run_parser_and_deref_pair_spans("a:\n  b: c\n  d:\n    - e\n    - f\n# comment\ng: h")
+                .unwrap()
+        ),
+        [
+            ("b", "c"),
+            ("d", "- e\n    - f"),
+            ("a", "b: c\n  d:\n    - e\n    - f"),
+            ("g", "h")
+        ]
+    );
+    // Flow collections.
+    assert_eq!(
+        deref_pairs(&run_parser_and_deref_pair_spans("{a: [b, c], d: {e: f}}").unwrap()),
+        [("a", "[b, c]"), ("e", "f"), ("d", "{e: f}")]
+    );
+    // Complex keys.
+    assert_eq!(
+        deref_pairs(
+            &run_parser_and_deref_pair_spans("? [x, y]\n: z\n? - q\n: {r: s}\n? a\n").unwrap()
+        ),
+        [("[x, y]", "z"), ("r", "s"), ("- q", "{r: s}"), ("a", "")]
+    );
+    // Mappings in sequences, empty values and aliases.
+    assert_eq!(
+        deref_pairs(&run_parser_and_deref_pair_spans("- a: &x b\n  c:\n- d: *x").unwrap()),
+        [("a", "b"), ("c", ""), ("d", "*x")]
+    );
+    // Errors are forwarded.
+    assert!(run_parser_and_deref_pair_spans("a: [b").is_err());
+}
+
+#[test]
+fn test_multibyte_characters() {
+    // 1-, 2-, 3- and 4-byte characters, and an emoji with a variation selector.
+    let yaml = "é: aü€😀\n- \"😀\u{fe0f}x\" # ü€\n";
+    let yaml = &yaml[..yaml.find('-').unwrap()];
+    for parser in [
+        Parser::new_from_str(yaml).collect::<Vec<_>>(),
+        Parser::new(BufferedInput::new(yaml.chars())).collect::<Vec<_>>(),
+    ] {
+        let scalars: Vec<_> = parser
+            .into_iter()
+            .map(Result::unwrap)
+            .filter(|(ev, _)| matches!(ev, Event::Scalar(..)))
+            .map(|(_, span)| {
+                (
+                    &yaml[span.start.index()..span.end.index()],
+                    span.start.col(),
+                    span.end.col(),
+                )
+            })
+            .collect();
+        // Indices count bytes, columns count characters.
+        assert_eq!(scalars, [("é", 0, 1), ("aü€😀", 3, 7)]);
+    }
+
+    let yaml = "- \"😀\u{fe0f}x\"\n- ü: |\n    €😀\n    é\n  😀: ' é '\n# ü€\n- end # ü€\n";
+    for parser in [
+        Parser::new_from_str(yaml).collect::<Vec<_>>(),
+        Parser::new(BufferedInput::new(yaml.chars())).collect::<Vec<_>>(),
+    ] {
+        let scalars: Vec<_> = parser
+            .into_iter()
+            .map(Result::unwrap)
+            .filter(|(ev, _)| matches!(ev, Event::Scalar(..)))
+            .map(|(_, span)| {
+                (
+                    &yaml[span.start.index()..span.end.index()],
+                    (span.start.line(), span.start.col()),
+                    (span.end.line(), span.end.col()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            scalars,
+            [
+                ("\"😀\u{fe0f}x\"", (1, 2), (1, 7)),
+                ("ü", (2, 2), (2, 3)),
+                ("€😀\n    é\n  ", (3, 4), (5, 2)),
+                ("😀", (5, 2), (5, 3)),
+                ("' é '", (5, 5), (5, 10)),
+                ("end", (7, 2), (7, 5)),
+            ]
+        );
+    }
+
+    // Errors after multibyte characters, including in comments, point at the right place.
+    let yaml = "a: é # ü€😀\nb: 😀 c: d";
+    let err = Parser::new_from_str(yaml).find_map(Result::err).unwrap();
+    assert_eq!(&yaml[err.marker().index()..], ": d");
+    assert_eq!((err.marker().line(), err.marker().col()), (2, 6));
+    let err = Parser::new(BufferedInput::new(yaml.chars()))
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(&yaml[err.marker().index()..], ": d");
+    assert_eq!((err.marker().line(), err.marker().col()), (2, 6));
+}
+
+#[test]
+fn test_indicator_events() {
+    let yaml = "a: b\n? c\n: - d\n  -\n  - [e: f]\ng:\t{h: i, ? j}\n";
+    let events_with_indicators = |indicators: bool| {
+        let mut parser = Parser::new_from_str(yaml);
+        parser.set_indicator_events(indicators);
+        parser.map(Result::unwrap).collect::<Vec<_>>()
+    };
+
+    let events = events_with_indicators(true);
+    let indicators: Vec<_> = events
+        .iter()
+        .filter_map(|(ev, span)| match ev {
+            Event::Indicator(c) => {
+                assert_eq!(yaml[span.start.index()..span.end.index()], c.to_string());
+                Some((*c, span.start.line(), span.start.col()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        indicators,
+        [
+            (':', 1, 1),
+            ('?', 2, 0),
+            (':', 3, 0),
+            ('-', 3, 2),
+            ('-', 4, 2),
+            ('-', 5, 2),
+            (':', 5, 6),
+            (':', 6, 1),
+            (':', 6, 5),
+            ('?', 6, 10),
+        ]
+    );
+
+    // Indicators precede the node they introduce.
+    let next_events: Vec<_> = events
+        .windows(2)
+        .filter(|pair| matches!(pair[0].0, Event::Indicator(_)))
+        .map(|pair| pair[1].0.clone())
+        .collect();
+    let scalar = |v: &str| Event::Scalar(v.to_owned(), saphyr_parser::TScalarStyle::Plain, 0, None);
+    assert_eq!(
+        next_events,
+        [
+            scalar("b"),
+            scalar("c"),
+            Event::SequenceStart(0, None),
+            scalar("d"),
+            scalar("~"),
+            Event::SequenceStart(0, None),
+            scalar("f"),
+            Event::MappingStart(0, None),
+            scalar("i"),
+            scalar("j"),
+        ]
+    );
+
+    // Other events are unchanged.
+    let without_indicators: Vec<_> = events
+        .iter()
+        .filter(|(ev, _)| !matches!(ev, Event::Indicator(_)))
+        .cloned()
+        .collect();
+    assert_eq!(without_indicators, events_with_indicators(false));
+
+    // `Parser::load` forwards indicator events as well.
+    struct Sink(Vec<(Event, Span)>);
+    impl SpannedEventReceiver for Sink {
+        fn on_event(&mut self, ev: Event, span: Span) {
+            self.0.push((ev, span));
+        }
+    }
+    let mut sink = Sink(vec![]);
+    let mut parser = Parser::new_from_str(yaml);
+    parser.set_indicator_events(true);
+    parser.load(&mut sink, true).unwrap();
+    assert_eq!(sink.0, events);
+}
+
+#[test]
+fn test_load_spans() {
+    /// Records the events and spans `Parser::load` feeds it.
+    struct SpanRecorder(Vec<(Event, Span)>);
+    impl SpannedEventReceiver for SpanRecorder {
+        fn on_event(&mut self, ev: Event, span: Span) {
+            self.0.push((ev, span));
+        }
+    }
+
+    let yaml = "a: &x [1, 'two']\nb: *x\n---\n- |\n  text\n";
+    let mut recorder = SpanRecorder(vec![]);
+    Parser::new_from_str(yaml)
+        .load(&mut recorder, true)
+        .unwrap();
+
+    // `load` yields the same spans as iterating over the parser.
+    let events: Vec<_> = Parser::new_from_str(yaml).map(Result::unwrap).collect();
+    assert_eq!(recorder.0, events);
+
+    let source = |span: &Span| &yaml[span.start.index()..span.end.index()];
+    let spans: Vec<_> = recorder
+        .0
+        .iter()
+        .filter(|(ev, _)| matches!(ev, Event::Scalar(..) | Event::Alias(_) | Event::SequenceEnd))
+        .map(|(_, span)| source(span))
+        .collect();
+    assert_eq!(spans, ["a", "1", "'two'", "]", "b", "*x", "text\n", ""]);
+}
+
+#[test]
+fn test_trivia() {
+    /// Return the trivia of `input` and what remains of it once they are removed.
+    fn run(input: &str) -> (Vec<&str>, String) {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_record_trivia(true);
+        for event in &mut parser {
+            event.unwrap();
+        }
+        let spans = parser.take_trivia();
+        assert!(parser.take_trivia().is_empty());
+
+        let mut trivia = vec![];
+        let mut rest = String::new();
+        let mut last = 0;
+        for span in spans {
+            assert!(span.start.index() > last || last == 0, "{input:?}");
+            rest.push_str(&input[last..span.start.index()]);
+            trivia.push(&input[span.start.index()..span.end.index()]);
+            last = span.end.index();
+        }
+        rest.push_str(&input[last..]);
+        (trivia, rest)
+    }
+
+    let (trivia, rest) = run("a:  1 # one\n\nb: [x , y]\n");
+    assert_eq!(trivia, ["  ", " # one\n\n", " ", " ", " ", "\n"]);
+    assert_eq!(rest, "a:1b:[x,y]");
+
+    // Whitespace within a plain scalar is part of it, but not the whitespace after it.
+    let (_, rest) = run("- a\n  b  \n- c\n");
+    assert_eq!(rest, "-a\n  b-c");
+
+    // Indentation and comments around documents and collections.
+    let (trivia, rest) = run("# head\n---\nk:\n  - v # c\n...  # end\n");
+    assert_eq!(
+        trivia,
+        ["# head\n", "\n", "\n  ", " ", " # c\n", "  # end\n"]
+    );
+    assert_eq!(rest, "---k:-v...");
+
+    // Trivia are not recorded by default.
+    let mut parser = Parser::new_from_str("a: 1 # one\n");
+    for event in &mut parser {
+        event.unwrap();
+    }
+    assert!(parser.take_trivia().is_empty());
+}
+
+#[test]
+fn test_trailing_whitespace() {
+    /// Return the scalars of `input` and the trailing whitespace stripped from them.
+    fn run(input: &str) -> (Vec<String>, Vec<(&str, usize, usize)>) {
+        let mut parser = Parser::new_from_str(input);
+        parser.set_record_trailing_whitespace(true);
+        let mut scalars = vec![];
+        for event in &mut parser {
+            if let (Event::Scalar(value, ..), _) = event.unwrap() {
+                scalars.push(value);
+            }
+        }
+        let spans = parser
+            .take_trailing_whitespace()
+            .iter()
+            .map(|span| {
+                let text = &input[span.start.index()..span.end.index()];
+                (text, span.start.line(), span.start.col())
+            })
+            .collect();
+        assert!(parser.take_trailing_whitespace().is_empty());
+        (scalars, spans)
+    }
+
+    let (scalars, spans) = run("key: value   ");
+    assert_eq!(scalars, ["key", "value"]);
+    assert_eq!(spans, [("   ", 1, 10)]);
+
+    let (scalars, spans) = run("key: value   \nother: x\t\n");
+    assert_eq!(scalars, ["key", "value", "other", "x"]);
+    assert_eq!(spans, [("   ", 1, 10), ("\t", 2, 8)]);
+
+    // Each line of a multi-line scalar.
+    let (scalars, spans) = run("- a \n  b  \n\n  c\n");
+    assert_eq!(scalars, ["a b\nc"]);
+    assert_eq!(spans, [(" ", 1, 3), ("  ", 2, 3)]);
+
+    // Whitespace before a comment or within a flow collection is not trailing.
+    let (_, spans) = run("a: b  # c\nd: [x , y ]\n");
+    assert!(spans.is_empty());
+
+    // Quoted scalars keep their whitespace.
+    let (scalars, spans) = run("a: 'b  '\n");
+    assert_eq!(scalars, ["a", "b  "]);
+    assert!(spans.is_empty());
+
+    // Trailing whitespace is not recorded by default.
+    let mut parser = Parser::new_from_str("key: value   ");
+    for event in &mut parser {
+        event.unwrap();
+    }
+    assert!(parser.take_trailing_whitespace().is_empty());
+}
+
+#[test]
+fn test_block_scalar_headers() {
+    let padding = " ".repeat(20);
+    let yaml = format!(
+        "\
+a: |2
+    x
+   y
+b: >4+
+      x
+      y
+
+c:
+  - |1-
+     x
+    y
+f:
+{padding}d: >9
+{padding}           x
+e: |
+  auto
+"
+    );
+    for input in [true, false] {
+        let mut scalars = vec![];
+        let headers = if input {
+            let mut parser = Parser::new_from_str(&yaml);
+            parser.set_record_block_scalar_headers(true);
+            for event in &mut parser {
+                if let (Event::Scalar(value, ..), _) = event.unwrap() {
+                    scalars.push(value);
+                }
+            }
+            parser.take_block_scalar_headers()
+        } else {
+            let mut parser = Parser::new(BufferedInput::new(yaml.chars()));
+            parser.set_record_block_scalar_headers(true);
+            for event in &mut parser {
+                if let (Event::Scalar(value, ..), _) = event.unwrap() {
+                    scalars.push(value);
+                }
+            }
+            parser.take_block_scalar_headers()
+        };
+        assert_eq!(
+            scalars,
+            [
+                "a",
+                "  x\n y\n",
+                "b",
+                "  x\n  y\n\n",
+                "c",
+                "  x\n y",
+                "f",
+                "d",
+                "  x\n",
+                "e",
+                "auto\n"
+            ]
+        );
+        let headers: Vec<_> = headers
+            .iter()
+            .map(|header| {
+                (
+                    &yaml[header.span.start.index()..header.span.end.index()],
+                    header.indentation_indicator,
+                    header.chomping,
+                    header.indent,
+                )
+            })
+            .collect();
+        assert_eq!(
+            headers,
+            [
+                ("|2", Some(2), Chomping::Clip, 2),
+                (">4+", Some(4), Chomping::Keep, 4),
+                ("|1-", Some(1), Chomping::Strip, 3),
+                (">9", Some(9), Chomping::Clip, 29),
+                ("|", None, Chomping::Clip, 2),
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_property_spans() {
+    let yaml = "- &a !!str foo\n- !t &b [x]\n- &c\n  k: *a\n- !!null\n- bar\n";
+    let mut parser = Parser::new_from_str(yaml);
+    parser.set_record_property_spans(true);
+    let text = |span: Option<Span>| span.map(|span| &yaml[span.start.index()..span.end.index()]);
+    let mut properties = vec![];
+    while let Some(event) = parser.next_event() {
+        let (event, _) = event.unwrap();
+        if matches!(
+            event,
+            Event::Scalar(..)
+                | Event::Alias(_)
+                | Event::SequenceStart(..)
+                | Event::MappingStart(..)
+        ) {
+            properties.push((text(parser.anchor_span()), text(parser.tag_span())));
+        }
+    }
+    assert_eq!(
+        properties,
+        [
+            // The root sequence.
+            (None, None),
+            (Some("&a"), Some("!!str")),
+            (Some("&b"), Some("!t")),
+            (None, None),
+            (Some("&c"), None),
+            (None, None),
+            (None, None),
+            (None, Some("!!null")),
+            (None, None),
+        ]
+    );
+
+    // Property spans are not recorded by default.
+    let mut parser = Parser::new_from_str("&a !t foo");
+    while let Some(event) = parser.next_event() {
+        event.unwrap();
+        assert_eq!((parser.anchor_span(), parser.tag_span()), (None, None));
+    }
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "async")]
+
+use saphyr_parser::{Event, EventStream, Parser, ScanError, Span, TScalarStyle};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::UnboundedReceiverStream, StreamExt};
+
+/// Run the parser through the string, returning all the events.
+fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
+    Parser::new_from_str(input)
+        .map(|x| x.map(|(ev, _)| ev))
+        .collect()
+}
+
+/// Parse `input`, sent from another task in chunks of `chunk_len` characters.
+///
+/// Return the events yielded before the first error, and that error if any.
+async fn stream_events(input: &str, chunk_len: usize) -> (Vec<Event>, Option<ScanError>) {
+    let (events, error) = stream_spanned_events(input, chunk_len).await;
+    (events.into_iter().map(|(ev, _)| ev).collect(), error)
+}
+
+/// Same as [`stream_events`], with the span of each event.
+async fn stream_spanned_events(
+    input: &str,
+    chunk_len: usize,
+) -> (Vec<(Event, Span)>, Option<ScanError>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let chars: Vec<_> = input.chars().collect();
+    let writer = tokio::spawn(async move {
+        for chunk in chars.chunks(chunk_len) {
+            for &c in chunk {
+                sender.send(c).unwrap();
+            }
+            tokio::task::yield_now().await;
+        }
+    });
+
+    let mut stream = EventStream::new(UnboundedReceiverStream::new(receiver));
+    let mut events = vec![];
+    let mut error = None;
+    while let Some(next) = stream.next().await {
+        match next {
+            Ok(event) => events.push(event),
+            Err(e) => error = Some(e),
+        }
+    }
+    writer.await.unwrap();
+    (events, error)
+}
+
+#[tokio::test]
+async fn test_stream_small_chunks() {
+    let input = "\
+%YAML 1.2
+---
+key: value
+list:
+  - a
+  - 'quoted
+    scalar'
+  - {x: 1, y: [2, 3]}
+block: |
+  line 1
+  line 2
+...
+--- [&anchor plain, *anchor]
+";
+    let expected = run_parser(input).unwrap();
+    for chunk_len in [1, 2, 3, 7, 64, 1024] {
+        let (events, error) = stream_events(input, chunk_len).await;
+        assert!(error.is_none(), "chunk_len {chunk_len}: {error:?}");
+        assert_eq!(events, expected, "chunk_len {chunk_len}");
+    }
+}
+
+#[tokio::test]
+async fn test_stream_yields_events_before_the_input_ends() {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut stream = EventStream::new(UnboundedReceiverStream::new(receiver));
+    let long = "x".repeat(32);
+    for c in format!("- a\n- {long}").chars() {
+        sender.send(c).unwrap();
+    }
+
+    // `a` is complete, the last scalar may be continued by characters yet to be received.
+    let mut events = vec![];
+    for _ in 0..4 {
+        events.push(stream.next().await.unwrap().unwrap().0);
+    }
+    assert_eq!(
+        events[3],
+        Event::Scalar("a".to_owned(), TScalarStyle::Plain, 0, None)
+    );
+
+    sender.send('y').unwrap();
+    drop(sender);
+    let rest: Vec<_> = stream.map(|next| next.unwrap().0).collect().await;
+    assert_eq!(
+        rest[0],
+        Event::Scalar(format!("{long}y"), TScalarStyle::Plain, 0, None)
+    );
+    assert_eq!(rest.last(), Some(&Event::StreamEnd));
+}
+
+#[tokio::test]
+async fn test_stream_errors() {
+    let input = "a: [b\nc: d\n";
+    let expected = run_parser(input).unwrap_err();
+    for chunk_len in [1, 4, 1024] {
+        let (events, error) = stream_events(input, chunk_len).await;
+        assert_eq!(error.as_ref(), Some(&expected), "chunk_len {chunk_len}");
+        let partial: Vec<_> = Parser::new_from_str(input)
+            .map_while(|x| x.ok().map(|(ev, _)| ev))
+            .collect();
+        assert_eq!(events, partial, "chunk_len {chunk_len}");
+    }
+}
+
+#[tokio::test]
+async fn test_stream_many_documents() {
+    // Documents are dropped once parsed: the following ones keep their positions and anchor IDs.
+    let mut input = String::from("%YAML 1.2\n--- &a [1, *a]\n...\n");
+    for i in 0..20 {
+        input.push_str(&format!(
+            "# document {i}\n--- {{k: &b{i} 'v\n  {i}', l: *b{i}}}\n"
+        ));
+        input.push_str(&format!("---\n- &c{i} x\n- *c{i}\n...\nbare {i}\n...\n"));
+    }
+    input.push_str("--- |\n  last\n");
+    let expected: Vec<_> = Parser::new_from_str(&input)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    for chunk_len in [1, 5, 64] {
+        let (events, error) = stream_spanned_events(&input, chunk_len).await;
+        assert!(error.is_none(), "chunk_len {chunk_len}: {error:?}");
+        assert_eq!(events, expected, "chunk_len {chunk_len}");
+    }
+
+    // Errors in later documents are reported where they are in the stream.
+    let input = "a\n...\nb\n---\nc: [d\n";
+    let expected = Parser::new_from_str(input).find_map(Result::err).unwrap();
+    for chunk_len in [1, 3, 1024] {
+        let (_, error) = stream_spanned_events(input, chunk_len).await;
+        assert_eq!(error.as_ref(), Some(&expected), "chunk_len {chunk_len}");
+    }
+}
@@ -0,0 +1,145 @@
+#![cfg(feature = "testutil")]
+
+use saphyr_parser::{
+    testutil::{assert_events_eq, diff_events, dump_events},
+    Event, Parser, Span,
+};
+
+fn run(s: &str) -> Vec<(Event, Span)> {
+    Parser::new_from_str(s).map(Result::unwrap).collect()
+}
+
+#[test]
+fn test_diff_events() {
+    let expected = run("a: [b, c]\n");
+    assert!(diff_events(&expected, &expected).is_none());
+    assert_events_eq(&expected, &expected);
+
+    // The first differing event is shown along with the events preceding it.
+    let actual = run("a: [b, d]\n");
+    let diff = diff_events(&expected, &actual).unwrap();
+    assert_eq!(diff.index, 6);
+    assert_eq!(diff.context, &expected[3..6]);
+    assert_eq!(
+        diff.to_string(),
+        r#"event streams differ at index 6
+     3: Scalar("a", Plain, 0, None) at 1:1..1:2
+     4: SequenceStart(0, None) at 1:4..1:5
+     5: Scalar("b", Plain, 0, None) at 1:5..1:6
+-    6: Scalar("c", Plain, 0, None) at 1:8..1:9
++    6: Scalar("d", Plain, 0, None) at 1:8..1:9
+"#
+    );
+
+    // Spans are compared too.
+    let actual = run("a:  [b, c]\n");
+    let diff = diff_events(&expected, &actual).unwrap();
+    assert_eq!(diff.index, 4);
+    assert_eq!(diff.expected.unwrap().0, diff.actual.unwrap().0);
+
+    // A stream may end early.
+    let diff = diff_events(&expected, &expected[..2]).unwrap();
+    assert_eq!(diff.index, 2);
+    assert_eq!(diff.context, &expected[..2]);
+    assert_eq!(diff.actual, None);
+    assert!(diff.to_string().ends_with("+    2: <end of stream>\n"));
+}
+
+#[test]
+#[should_panic(expected = "event streams differ at index 6")]
+fn test_assert_events_eq() {
+    assert_events_eq(&run("a: [b, c]\n"), &run("a: [b, d]\n"));
+}
+
+/// Parse `input` and dump its events.
+fn dump(input: &str) -> String {
+    dump_events(&run(input))
+}
+
+#[test]
+fn test_dump_events_block_documents() {
+    let input = r#"%YAML 1.2
+---
+name: &n saphyr
+tags: [yaml, "par\tser"]
+ref: *n
+desc: |
+  line 1
+  line 2
+...
+--- !!seq
+- ? k
+  : v
+- >
+  folded
+  text
+- 'it''s'
+"#;
+    assert_eq!(
+        dump(input),
+        r#"1:1..1:1 +STR
+2:1..2:4 +DOC ---
+3:1..3:1 +MAP
+3:1..3:5 =VAL :name
+3:10..3:16 =VAL &1 :saphyr
+4:1..4:5 =VAL :tags
+4:7..4:8 +SEQ
+4:8..4:12 =VAL :yaml
+4:14..4:24 =VAL "par\tser
+4:24..4:25 -SEQ
+5:1..5:4 =VAL :ref
+5:6..5:8 =ALI *1
+6:1..6:5 =VAL :desc
+7:3..9:1 =VAL |line 1\nline 2\n
+9:1..9:1 -MAP
+9:1..9:4 -DOC
+10:1..10:4 +DOC ---
+11:1..11:1 +SEQ <tag:yaml.org,2002:seq>
+11:3..11:3 +MAP
+11:5..11:6 =VAL :k
+12:5..12:6 =VAL :v
+13:1..13:1 -MAP
+14:3..16:1 =VAL >folded text\n
+16:3..16:10 =VAL 'it's
+17:1..17:1 -SEQ
+17:1..17:1 -DOC
+17:1..17:1 -STR
+"#
+    );
+}
+
+#[test]
+fn test_dump_events_flow_and_indicators() {
+    let mut parser = Parser::new_from_str("- {a: [b\\c, &x ''], ? d}\n- *x\n");
+    parser.set_indicator_events(true);
+    let events: Vec<_> = parser.map(Result::unwrap).collect();
+    assert_eq!(
+        dump_events(&events),
+        r"1:1..1:1 +STR
+1:1..1:1 +DOC
+1:1..1:1 +SEQ
+1:1..1:2 =IND -
+1:3..1:4 +MAP
+1:4..1:5 =VAL :a
+1:5..1:6 =IND :
+1:7..1:8 +SEQ
+1:8..1:11 =VAL :b\\c
+1:16..1:18 =VAL &1 '
+1:18..1:19 -SEQ
+1:21..1:22 =IND ?
+1:23..1:24 =VAL :d
+1:24..1:25 =VAL :~
+1:24..1:25 -MAP
+2:1..2:2 =IND -
+2:3..2:5 =ALI *1
+3:1..3:1 -SEQ
+3:1..3:1 -DOC
+3:1..3:1 -STR
+"
+    );
+
+    // Dumps of equal streams are equal, and differ where the streams do.
+    assert_eq!(dump("[a, b]"), dump("[a, b]"));
+    assert_ne!(dump("[a, b]"), dump("[a,  b]"));
+    assert_eq!(dump(""), "1:1..1:1 +STR\n1:1..1:1 -STR\n");
+}
@@ -1,4 +1,7 @@
-use std::fs::{self, DirEntry};
+use std::{
+    fs::{self, DirEntry},
+    path::Path,
+};
 
 use libtest_mimic::{run_tests, Arguments, Outcome, Test};
 
@@ -21,14 +24,25 @@ fn main() -> Result<()> {
     if arguments.num_threads.is_none() {
         arguments.num_threads = Some(1);
     }
-    let tests: Vec<Vec<_>> = std::fs::read_dir("tests/yaml-test-suite/src")?
-        .map(|entry| -> Result<_> {
+    // A checkout of the suite's `data` branch can be used instead of the submodule's sources.
+    let mut tests = if let Some(dir) = std::env::var_os("YAML_TEST_SUITE_DATA") {
+        let mut tests = vec![];
+        for entry in fs::read_dir(dir)? {
             let entry = entry?;
-            let tests = load_tests_from_file(&entry)?;
-            Ok(tests)
-        })
-        .collect::<Result<_>>()?;
-    let mut tests: Vec<_> = tests.into_iter().flatten().collect();
+            let name = entry.file_name().to_string_lossy().to_string();
+            load_tests_from_data_dir(&entry.path(), name, &mut tests)?;
+        }
+        tests
+    } else {
+        let tests: Vec<Vec<_>> = fs::read_dir("tests/yaml-test-suite/src")?
+            .map(|entry| -> Result<_> {
+                let entry = entry?;
+                let tests = load_tests_from_file(&entry)?;
+                Ok(tests)
+            })
+            .collect::<Result<_>>()?;
+        tests.into_iter().flatten().collect()
+    };
     tests.sort_by_key(|t| t.name.clone());
 
     run_tests(&arguments, tests, run_yaml_test).exit();
@@ -131,6 +145,43 @@ fn load_tests_from_file(entry: &DirEntry) -> Result<Vec<Test<YamlTest>>> {
     Ok(result)
 }
 
+/// Load the tests of a directory of the suite's `data` branch.
+///
+/// A test is a directory with an `in.yaml` input, its `test.event` events and an `error` file if
+/// the input is invalid. Tests with several cases have a subdirectory for each.
+fn load_tests_from_data_dir(
+    dir: &Path,
+    name: String,
+    tests: &mut Vec<Test<YamlTest>>,
+) -> Result<()> {
+    if !dir.is_dir() || name.starts_with('.') || name == "name" || name == "tags" {
+        return Ok(());
+    }
+    if !dir.join("in.yaml").exists() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let case = entry.file_name().to_string_lossy().to_string();
+            load_tests_from_data_dir(&entry.path(), format!("{name}-{case}"), tests)?;
+        }
+        return Ok(());
+    }
+
+    let yaml = fs::read_to_string(dir.join("in.yaml"))?;
+    tests.push(Test {
+        name,
+        kind: String::new(),
+        is_ignored: false,
+        is_bench: false,
+        data: YamlTest {
+            yaml_visual: yaml.clone(),
+            yaml,
+            expected_events: fs::read_to_string(dir.join("test.event"))?,
+            expected_error: dir.join("error").exists(),
+        },
+    });
+    Ok(())
+}
+
 fn parse_to_events(source: &str) -> Result<EventReporter, ScanError> {
     let mut reporter = EventReporter::default();
     for x in Parser::new_from_str(source) {
@@ -196,7 +247,8 @@ impl SpannedEventReceiver for EventReporter {
                 )
             }
             Event::Alias(idx) => format!("=ALI *{idx}"),
-            Event::Nothing => return,
+            // `Event` is `#[non_exhaustive]`.
+            _ => return,
         };
         self.events.push(line);
     }
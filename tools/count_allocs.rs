@@ -0,0 +1,86 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    env,
+    fs::File,
+    io::prelude::*,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use saphyr_parser::{Event, Parser, Span, SpannedEventReceiver};
+
+/// An allocator counting the allocations it performs.
+struct CountingAllocator;
+
+/// The number of allocations performed since the start of the program.
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// A sink which discards any event sent.
+struct NullSink {}
+
+impl SpannedEventReceiver for NullSink {
+    fn on_event(&mut self, _: Event, _: Span) {}
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let iterations: usize = args[2].parse().unwrap();
+    let numbers = args.len() == 4 && args[3] == "--numbers";
+    let intern = args.len() == 4 && args[3] == "--intern";
+    let mut f = File::open(&args[1]).unwrap();
+    let mut s = String::new();
+    f.read_to_string(&mut s).unwrap();
+
+    let mut sink = NullSink {};
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let begin = std::time::Instant::now();
+    for _ in 0..iterations {
+        let mut parser = Parser::new_from_str(&s);
+        if numbers {
+            // Read scalars into a reused buffer and numbers as such, as a numeric consumer would.
+            parser.set_parse_numbers(true);
+            let mut buf = String::new();
+            while let Some(ev) = parser.next_event_into(&mut buf) {
+                ev.unwrap();
+                std::hint::black_box(parser.number());
+            }
+        } else if intern {
+            // Read scalars into a reused buffer and keep their interned values, as a consumer
+            // building a tree of shared strings would.
+            parser.set_intern_scalars(true);
+            let mut buf = String::new();
+            while let Some(ev) = parser.next_event_into(&mut buf) {
+                ev.unwrap();
+                std::hint::black_box(parser.interned_scalar());
+            }
+        } else {
+            parser.load(&mut sink, true).unwrap();
+        }
+    }
+    let end = std::time::Instant::now();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+
+    println!(
+        "Parsed {iterations} times in {:?}, {} allocations per parse",
+        end - begin,
+        allocations / iterations
+    );
+}
@@ -34,6 +34,10 @@ fn main() -> std::io::Result<()> {
     println!("Generating strings_array.yaml");
     let mut out = BufWriter::new(File::create(output_path.join("strings_array.yaml")).unwrap());
     generator.gen_strings_array(&mut out, 1_300_000, 1_300_001, 10, 40)?;
+
+    println!("Generating documents.yaml");
+    let mut out = BufWriter::new(File::create(output_path.join("documents.yaml")).unwrap());
+    generator.gen_documents_stream(&mut out, 3_000_000, 3_000_001)?;
     Ok(())
 }
 
@@ -83,6 +87,31 @@ impl Generator {
         })
     }
 
+    /// Generate a stream of small documents, each containing an author as per
+    /// [`Self::gen_author_object`].
+    ///
+    /// Every line starts with a character that may begin a document indicator (`---` or `...`),
+    /// which puts a lot of weight in detecting them.
+    fn gen_documents_stream<W: std::io::Write>(
+        &mut self,
+        writer: &mut W,
+        docs_lo: usize,
+        docs_hi: usize,
+    ) -> std::io::Result<()> {
+        for _ in 0..self.rng.gen_range(docs_lo..docs_hi) {
+            writeln!(writer, "---")?;
+            write!(writer, "- ")?;
+            self.push_indent(2);
+            self.gen_author_object(writer)?;
+            self.pop_indent();
+            writeln!(writer)?;
+            if self.rng.gen_bool(0.5) {
+                writeln!(writer, "...")?;
+            }
+        }
+        Ok(())
+    }
+
     /// Generate a YAML object/mapping containing a record.
     ///
     /// Fields are description, hash, version, home, repository and pdf.